@@ -0,0 +1,119 @@
+//! Ambient-light auto-brightness.
+//!
+//! Configured under `[ambient]` in the config file, e.g.
+//!
+//! ```toml
+//! [ambient]
+//! enabled = true
+//! sensitivity = 1.5
+//! min_brightness = 10
+//! max_brightness = 90
+//! ```
+//!
+//! The worker samples the light sensor every few seconds and maps the
+//! reading onto a logarithmic curve between `min_brightness` and
+//! `max_brightness`. A new target is only applied once it's at least
+//! [`HYSTERESIS`] points away from the last one, so a flickering reading
+//! doesn't make the backlight hunt.
+
+use serde::{Deserialize, Serialize};
+
+/// Smallest change in target brightness (percent) that gets applied.
+pub const HYSTERESIS: u32 = 5;
+
+/// Illuminance that maps to `max_brightness` at sensitivity 1.0 — roughly a
+/// brightly lit office.
+const FULL_LUX: f32 = 1000.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AmbientSettings {
+    /// Off by default; also stays off on machines without a light sensor.
+    pub enabled: bool,
+    /// Multiplier on the sensor reading. Above 1.0 reaches full brightness
+    /// in dimmer rooms.
+    pub sensitivity: f32,
+    /// Brightness (0-100) in the dark.
+    pub min_brightness: u32,
+    /// Brightness (0-100) at [`FULL_LUX`] and above.
+    pub max_brightness: u32,
+}
+
+impl Default for AmbientSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sensitivity: 1.0,
+            min_brightness: 10,
+            max_brightness: 100,
+        }
+    }
+}
+
+impl AmbientSettings {
+    /// Brightness (0-100) for a sensor reading of `lux`. Perceived
+    /// brightness is roughly logarithmic in lux, so the curve is too.
+    pub fn target_for(&self, lux: f32) -> u32 {
+        let low = self.min_brightness.min(100);
+        let high = self.max_brightness.clamp(low, 100);
+
+        let lux = (lux * self.sensitivity).max(0.0);
+        let fraction = ((1.0 + lux).log10() / (1.0 + FULL_LUX).log10()).clamp(0.0, 1.0);
+        low + ((high - low) as f32 * fraction).round() as u32
+    }
+}
+
+/// Whether `target` is far enough from the last applied brightness to be
+/// worth applying.
+pub fn should_apply(last_applied: Option<u32>, target: u32) -> bool {
+    last_applied.is_none_or(|last| last.abs_diff(target) >= HYSTERESIS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curve_spans_min_to_max() {
+        let settings = AmbientSettings::default();
+        assert_eq!(settings.target_for(0.0), 10);
+        assert_eq!(settings.target_for(FULL_LUX), 100);
+        assert_eq!(settings.target_for(100_000.0), 100);
+
+        let dim = settings.target_for(20.0);
+        let office = settings.target_for(300.0);
+        assert!(10 < dim && dim < office && office < 100);
+    }
+
+    #[test]
+    fn sensitivity_scales_reading() {
+        let normal = AmbientSettings::default();
+        let sensitive = AmbientSettings {
+            sensitivity: 4.0,
+            ..AmbientSettings::default()
+        };
+        assert!(sensitive.target_for(50.0) > normal.target_for(50.0));
+    }
+
+    #[test]
+    fn tolerates_bad_settings_and_readings() {
+        let inverted = AmbientSettings {
+            min_brightness: 80,
+            max_brightness: 20,
+            ..AmbientSettings::default()
+        };
+        assert_eq!(inverted.target_for(0.0), 80);
+        assert_eq!(inverted.target_for(FULL_LUX), 80);
+        assert_eq!(AmbientSettings::default().target_for(f32::NAN), 10);
+        assert_eq!(AmbientSettings::default().target_for(-5.0), 10);
+    }
+
+    #[test]
+    fn hysteresis_ignores_small_changes() {
+        assert!(should_apply(None, 50));
+        assert!(!should_apply(Some(50), 53));
+        assert!(!should_apply(Some(50), 46));
+        assert!(should_apply(Some(50), 55));
+        assert!(should_apply(Some(50), 45));
+    }
+}
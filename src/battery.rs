@@ -0,0 +1,114 @@
+//! Brightness capped as a laptop's battery drains.
+//!
+//! Configured under `[battery]` in the config file, e.g.
+//!
+//! ```toml
+//! [battery]
+//! enabled = true
+//! caps = [
+//!     { below = 20, max_brightness = 40 },
+//!     { below = 10, max_brightness = 25 },
+//! ]
+//! ```
+//!
+//! While running on battery, every monitor is held at or under the
+//! `max_brightness` of each step whose `below` the charge has dropped
+//! under; the lowest one wins. Monitors already dimmer are left alone, and
+//! nothing is put back when the cap lifts. A step only lifts once the
+//! charge is [`HYSTERESIS`] points above its threshold again, so a reading
+//! that wobbles around it doesn't make the backlight flicker. Plugging in
+//! lifts every cap at once.
+
+use serde::{Deserialize, Serialize};
+
+/// Points of battery above a step's threshold before its cap lifts.
+pub const HYSTERESIS: u32 = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BatterySettings {
+    /// Off by default; also stays off on machines without a battery.
+    pub enabled: bool,
+    /// Brightness caps by battery level, in any order.
+    pub caps: Vec<BatteryCap>,
+}
+
+/// One step of the battery curve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatteryCap {
+    /// Battery percent under which the cap applies.
+    pub below: u32,
+    /// Highest brightness (0-100) allowed meanwhile.
+    pub max_brightness: u32,
+}
+
+impl Default for BatterySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            caps: vec![BatteryCap {
+                below: 20,
+                max_brightness: 40,
+            }],
+        }
+    }
+}
+
+impl BatterySettings {
+    /// Brightness cap (0-100) at `charge` percent on battery, or `None` when
+    /// no step applies. `current` is the cap in force until now; steps at or
+    /// above it stay engaged within [`HYSTERESIS`] of their threshold.
+    pub fn cap_for(&self, charge: u32, current: Option<u32>) -> Option<u32> {
+        self.caps
+            .iter()
+            .filter(|cap| {
+                let engaged = current.is_some_and(|current| current <= cap.max_brightness);
+                charge < cap.below || (engaged && charge < cap.below.saturating_add(HYSTERESIS))
+            })
+            .map(|cap| cap.max_brightness.min(100))
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> BatterySettings {
+        BatterySettings {
+            enabled: true,
+            caps: vec![
+                BatteryCap {
+                    below: 10,
+                    max_brightness: 25,
+                },
+                BatteryCap {
+                    below: 20,
+                    max_brightness: 40,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn lowest_applicable_cap_wins() {
+        let settings = settings();
+        assert_eq!(settings.cap_for(50, None), None);
+        assert_eq!(settings.cap_for(19, None), Some(40));
+        assert_eq!(settings.cap_for(9, None), Some(25));
+    }
+
+    #[test]
+    fn caps_lift_only_past_the_hysteresis() {
+        let settings = settings();
+        // Wobbling back over the threshold keeps the cap
+        assert_eq!(settings.cap_for(20, Some(40)), Some(40));
+        assert_eq!(settings.cap_for(22, Some(40)), Some(40));
+        assert_eq!(settings.cap_for(23, Some(40)), None);
+        // The tighter step lifts on its own, back to the looser one
+        assert_eq!(settings.cap_for(11, Some(25)), Some(25));
+        assert_eq!(settings.cap_for(13, Some(25)), Some(40));
+        // Without a cap in force the threshold is exact
+        assert_eq!(settings.cap_for(20, None), None);
+    }
+}
@@ -0,0 +1,497 @@
+//! Headless command-line mode.
+//!
+//! `tray-bright list`, `tray-bright get`, `tray-bright set 50` and
+//! `tray-bright vcp 0 10` talk to the monitors synchronously and exit without
+//! creating the tray icon or the window. Running with no subcommand starts
+//! the tray app as usual.
+//!
+//! With `--json`, `list` prints an array of
+//! `{"index", "name", "current", "min", "max"}` objects and `get --monitor`
+//! a single one, and errors go to stderr as `{"error": "..."}`.
+//!
+//! `tray-bright snapshot > state.json` saves every monitor's brightness as
+//! `{"monitors": [{"id", "name", "value"}]}` and `tray-bright restore <
+//! state.json` sets it back, matching monitors by stable id, then by name.
+
+use std::io::Read;
+use std::net::IpAddr;
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+
+use crate::os::{MonitorHandle, MonitorProvider, PlatformMonitorProvider};
+
+#[derive(Debug, Parser)]
+#[command(name = "tray-bright", version, about)]
+pub struct Cli {
+    /// Print debug messages to stderr (`RUST_LOG` takes precedence)
+    #[arg(short, long, global = true)]
+    pub verbose: bool,
+
+    /// Print `list` and `get` results, and errors, as JSON
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Also serve a local HTTP API on this port (tray mode only)
+    #[arg(long, value_name = "PORT")]
+    pub serve: Option<u16>,
+
+    /// Address for the HTTP API. Anything other than 127.0.0.1 exposes it,
+    /// unauthenticated, to the network
+    #[arg(
+        long,
+        value_name = "ADDR",
+        default_value = "127.0.0.1",
+        requires = "serve"
+    )]
+    pub serve_bind: IpAddr,
+
+    /// Also serve `org.traybright.Brightness` on the D-Bus session bus
+    /// (tray mode, Linux only)
+    #[arg(long)]
+    pub dbus: bool,
+
+    /// Start with the window hidden; login entries pass this so it never
+    /// flashes on screen (tray mode only)
+    #[arg(long)]
+    pub hidden: bool,
+
+    /// Add a button for the read-only VCP debug panel to the window header;
+    /// Ctrl+Shift+D opens it either way (tray mode only)
+    #[arg(long)]
+    pub debug: bool,
+
+    /// Control N fake in-memory monitors (default 2) instead of the real
+    /// ones, for trying the app without touching hardware (tray mode only)
+    #[arg(long, value_name = "N")]
+    pub simulate: Option<Option<usize>>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Print index, name and current brightness of every monitor
+    List,
+    /// Print the current brightness
+    Get {
+        /// Monitor index or name (default: all monitors)
+        #[arg(short, long)]
+        monitor: Option<String>,
+    },
+    /// Set brightness and exit
+    Set {
+        /// Brightness value, clamped to each monitor's range
+        value: u32,
+        /// Monitor index or name (default: all monitors)
+        #[arg(short, long)]
+        monitor: Option<String>,
+    },
+    /// Read or write a raw VCP feature
+    Vcp {
+        /// Monitor index or name
+        monitor: String,
+        /// VCP feature code in hex, e.g. 10 or 0x60
+        #[arg(value_parser = parse_vcp_code)]
+        code: u8,
+        /// Value to write; omit to read the current value
+        value: Option<u16>,
+    },
+    /// Print every monitor's brightness as JSON, for `restore`
+    Snapshot,
+    /// Set brightness from a `snapshot` read on stdin
+    Restore,
+}
+
+/// Run a CLI command to completion. Errors are printed to stderr and
+/// reported through the exit code.
+pub fn run(command: Command, json: bool) -> ExitCode {
+    attach_parent_console();
+
+    match execute(command, json) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) if json => {
+            eprintln!("{}", serde_json::json!({ "error": format!("{e:#}") }));
+            ExitCode::FAILURE
+        }
+        Err(e) => {
+            eprintln!("tray-bright: {e:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn execute(command: Command, json: bool) -> anyhow::Result<()> {
+    let mut provider = PlatformMonitorProvider::new();
+    let mut monitors = provider.get_monitors()?;
+
+    let result = match command {
+        Command::List => list(&mut monitors, json),
+        Command::Get { monitor } => get(&mut monitors, monitor.as_deref(), json),
+        Command::Set { value, monitor } => set(&mut monitors, monitor.as_deref(), value),
+        Command::Vcp {
+            monitor,
+            code,
+            value,
+        } => vcp(&mut monitors, &monitor, code, value),
+        Command::Snapshot => snapshot(&mut monitors),
+        Command::Restore => {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input)?;
+            restore(&mut monitors, &input)
+        }
+    };
+
+    provider.cleanup_monitors(&mut monitors);
+    result
+}
+
+fn list<M: MonitorHandle>(monitors: &mut [M], json: bool) -> anyhow::Result<()> {
+    let mut failed = false;
+    let mut objects = Vec::new();
+    for (i, mon) in monitors.iter_mut().enumerate() {
+        match mon.poll_brightness() {
+            Ok(level) if json => objects.push(Level::new(i, mon.name(), level)),
+            Ok((cur, _, _)) => println!("{i}\t{}\t{cur}", mon.name()),
+            Err(e) => {
+                if !json {
+                    eprintln!("{i}\t{}\terror: {e}", mon.name());
+                }
+                failed = true;
+            }
+        }
+    }
+
+    // Readable monitors are still printed when others fail
+    if json {
+        println!("{}", serde_json::to_string(&objects)?);
+    }
+    if failed {
+        anyhow::bail!("Failed to read brightness from some monitors");
+    }
+    Ok(())
+}
+
+fn get<M: MonitorHandle>(
+    monitors: &mut [M],
+    selector: Option<&str>,
+    json: bool,
+) -> anyhow::Result<()> {
+    let mut objects = Vec::new();
+    for i in select(monitors, selector)? {
+        let level = monitors[i].poll_brightness()?;
+        if json {
+            objects.push(Level::new(i, monitors[i].name(), level));
+        } else {
+            println!("{}: {}", monitors[i].name(), level.0);
+        }
+    }
+
+    if json {
+        // A named monitor prints a bare object, all monitors an array
+        match (selector, objects.as_slice()) {
+            (Some(_), [object]) => println!("{}", serde_json::to_string(object)?),
+            _ => println!("{}", serde_json::to_string(&objects)?),
+        }
+    }
+    Ok(())
+}
+
+fn set<M: MonitorHandle>(
+    monitors: &mut [M],
+    selector: Option<&str>,
+    value: u32,
+) -> anyhow::Result<()> {
+    for i in select(monitors, selector)? {
+        monitors[i].set_brightness(value)?;
+        monitors[i].confirm_brightness()?;
+        println!("{}: {value}", monitors[i].name());
+    }
+    Ok(())
+}
+
+fn vcp<M: MonitorHandle>(
+    monitors: &mut [M],
+    selector: &str,
+    code: u8,
+    value: Option<u16>,
+) -> anyhow::Result<()> {
+    for i in select(monitors, Some(selector))? {
+        let mon = &mut monitors[i];
+        match value {
+            Some(value) => {
+                mon.set_vcp(code, value)?;
+                println!("{}: 0x{code:02X} = {value}", mon.name());
+            }
+            None => {
+                let (current, max) = mon.get_vcp(code)?;
+                println!("{}: 0x{code:02X} = {current} (max {max})", mon.name());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn snapshot<M: MonitorHandle>(monitors: &mut [M]) -> anyhow::Result<()> {
+    let mut failed = false;
+    let mut entries = Vec::new();
+    for mon in monitors.iter_mut() {
+        match mon.poll_brightness() {
+            Ok((value, _, _)) => entries.push(SnapshotEntry {
+                id: Some(mon.id()),
+                name: Some(mon.name().to_string()),
+                value,
+            }),
+            Err(e) => {
+                eprintln!("{}: error: {e}", mon.name());
+                failed = true;
+            }
+        }
+    }
+
+    // Like `list`, a partial snapshot is still printed
+    println!("{}", snapshot_json(&entries));
+    if failed {
+        anyhow::bail!("Failed to read brightness from some monitors");
+    }
+    Ok(())
+}
+
+fn restore<M: MonitorHandle>(monitors: &mut [M], input: &str) -> anyhow::Result<()> {
+    let entries = parse_snapshot(input)?;
+    let known: Vec<(String, String)> = monitors
+        .iter()
+        .map(|mon| (mon.id(), mon.name().to_string()))
+        .collect();
+
+    let mut missing = Vec::new();
+    for (entry, target) in entries.iter().zip(match_snapshot(&known, &entries)) {
+        match target {
+            Some(i) => {
+                monitors[i].set_brightness(entry.value)?;
+                monitors[i].confirm_brightness()?;
+                println!("{}: {}", monitors[i].name(), entry.value);
+            }
+            None => missing.push(entry.label()),
+        }
+    }
+    if !missing.is_empty() {
+        anyhow::bail!("Not connected: {}", missing.join(", "));
+    }
+    Ok(())
+}
+
+/// What `snapshot` prints and `restore` reads.
+#[derive(Debug, Serialize, Deserialize)]
+struct Snapshot {
+    monitors: Vec<SnapshotEntry>,
+}
+
+/// One monitor in a snapshot. Snapshots always have both the id and the
+/// name, but a hand-written one may have either.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SnapshotEntry {
+    id: Option<String>,
+    name: Option<String>,
+    value: u32,
+}
+
+impl SnapshotEntry {
+    /// How the entry is named when it can't be restored.
+    fn label(&self) -> String {
+        match (&self.name, &self.id) {
+            (Some(name), Some(id)) => format!("{name} ({id})"),
+            (Some(label), None) | (None, Some(label)) => label.clone(),
+            (None, None) => unreachable!("entries without an id or name are rejected"),
+        }
+    }
+}
+
+fn snapshot_json(entries: &[SnapshotEntry]) -> String {
+    let snapshot = Snapshot {
+        monitors: entries.to_vec(),
+    };
+    serde_json::to_string(&snapshot).expect("snapshots always serialize")
+}
+
+fn parse_snapshot(input: &str) -> anyhow::Result<Vec<SnapshotEntry>> {
+    let snapshot: Snapshot =
+        serde_json::from_str(input).map_err(|e| anyhow::anyhow!("Invalid snapshot: {e}"))?;
+    if let Some(i) = snapshot
+        .monitors
+        .iter()
+        .position(|entry| entry.id.is_none() && entry.name.is_none())
+    {
+        anyhow::bail!("Invalid snapshot: monitor {i} needs an \"id\" or a \"name\"");
+    }
+    Ok(snapshot.monitors)
+}
+
+/// Which of the `known` monitors, as `(id, name)`, each entry restores to.
+/// Ids are matched first across every entry, so a name can't claim a monitor
+/// that another entry names by id; names (ignoring case) only settle what's
+/// left. Each monitor is used once.
+fn match_snapshot(known: &[(String, String)], entries: &[SnapshotEntry]) -> Vec<Option<usize>> {
+    let mut used = vec![false; known.len()];
+    let mut targets = vec![None; entries.len()];
+    for (entry, target) in entries.iter().zip(targets.iter_mut()) {
+        let Some(id) = &entry.id else { continue };
+        if let Some(i) = (0..known.len()).find(|&i| !used[i] && known[i].0 == *id) {
+            used[i] = true;
+            *target = Some(i);
+        }
+    }
+    for (entry, target) in entries.iter().zip(targets.iter_mut()) {
+        let Some(name) = entry.name.as_ref().filter(|_| target.is_none()) else {
+            continue;
+        };
+        if let Some(i) =
+            (0..known.len()).find(|&i| !used[i] && known[i].1.eq_ignore_ascii_case(name))
+        {
+            used[i] = true;
+            *target = Some(i);
+        }
+    }
+    targets
+}
+
+/// One monitor's brightness, as printed by `list` and `get` with `--json`
+/// and served by the HTTP API.
+#[derive(Debug, Serialize)]
+pub(crate) struct Level {
+    index: usize,
+    name: String,
+    current: u32,
+    min: u32,
+    max: u32,
+}
+
+impl Level {
+    pub(crate) fn new(index: usize, name: &str, (current, min, max): (u32, u32, u32)) -> Self {
+        Self {
+            index,
+            name: name.to_string(),
+            current,
+            min,
+            max,
+        }
+    }
+}
+
+/// VCP codes are conventionally written in hex; accept them with or
+/// without a `0x` prefix.
+fn parse_vcp_code(code: &str) -> Result<u8, String> {
+    let digits = code
+        .strip_prefix("0x")
+        .or_else(|| code.strip_prefix("0X"))
+        .unwrap_or(code);
+    u8::from_str_radix(digits, 16).map_err(|_| format!("{code:?} is not a hex VCP code (00-FF)"))
+}
+
+/// Resolve `--monitor` to indices. Accepts an index or a case-insensitive
+/// name; `None` selects every monitor.
+fn select<M: MonitorHandle>(monitors: &[M], selector: Option<&str>) -> anyhow::Result<Vec<usize>> {
+    let Some(selector) = selector else {
+        return Ok((0..monitors.len()).collect());
+    };
+
+    if let Ok(index) = selector.parse::<usize>()
+        && index < monitors.len()
+    {
+        return Ok(vec![index]);
+    }
+
+    monitors
+        .iter()
+        .position(|m| m.name().eq_ignore_ascii_case(selector))
+        .map(|i| vec![i])
+        .ok_or_else(|| anyhow::anyhow!("No monitor matching {selector:?}"))
+}
+
+/// Release builds use the GUI subsystem on Windows, so stdout goes nowhere
+/// unless we attach to the console of the shell that launched us.
+#[cfg(target_os = "windows")]
+fn attach_parent_console() {
+    use windows::Win32::System::Console::{ATTACH_PARENT_PROCESS, AttachConsole};
+    unsafe {
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn attach_parent_console() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simulate_takes_an_optional_count() {
+        let count = |args: &[&str]| Cli::try_parse_from(args).unwrap().simulate;
+        assert_eq!(count(&["tray-bright"]), None);
+        assert_eq!(count(&["tray-bright", "--simulate"]), Some(None));
+        assert_eq!(count(&["tray-bright", "--simulate", "4"]), Some(Some(4)));
+    }
+
+    #[test]
+    fn snapshots_read_back_what_they_wrote() {
+        let entries = vec![
+            SnapshotEntry {
+                id: Some("DEL:DELL U2720Q:7XYZ123".to_string()),
+                name: Some("Dell \"left\"".to_string()),
+                value: 40,
+            },
+            SnapshotEntry {
+                id: None,
+                name: Some("LG".to_string()),
+                value: 0,
+            },
+        ];
+        assert_eq!(parse_snapshot(&snapshot_json(&entries)).unwrap(), entries);
+        assert!(parse_snapshot(r#"{"monitors": [{"value": 40}]}"#).is_err());
+        assert!(parse_snapshot(r#"{"monitors": [{"name": "LG", "value": 4.5}]}"#).is_err());
+        assert!(parse_snapshot("[]").is_err());
+        // Hand-edited snapshots may reorder fields or leave some out
+        let edited = r#"{"monitors": [{"value": 70, "name": "LG", "note": "desk"}]}"#;
+        assert_eq!(
+            parse_snapshot(edited).unwrap()[0].name.as_deref(),
+            Some("LG")
+        );
+    }
+
+    #[test]
+    fn snapshot_matches_ids_before_names() {
+        let known = [
+            ("DEL:1".to_string(), "Dell".to_string()),
+            ("DEL:2".to_string(), "Dell".to_string()),
+            ("GSM:9".to_string(), "LG".to_string()),
+        ];
+        let entry = |id: Option<&str>, name: &str| SnapshotEntry {
+            id: id.map(str::to_string),
+            name: Some(name.to_string()),
+            value: 50,
+        };
+        let entries = [
+            // Swapped for another Dell of the same model: falls back to the
+            // Dell that no entry claims by id
+            entry(Some("DEL:3"), "dell"),
+            entry(Some("DEL:1"), "Dell"),
+            entry(Some("BNQ:4"), "BenQ"),
+            entry(None, "LG"),
+        ];
+        assert_eq!(
+            match_snapshot(&known, &entries),
+            [Some(1), Some(0), None, Some(2)]
+        );
+    }
+
+    #[test]
+    fn level_json_has_every_field() {
+        assert_eq!(
+            serde_json::to_string(&Level::new(1, "Dell \"left\"", (40, 0, 100))).unwrap(),
+            r#"{"index":1,"name":"Dell \"left\"","current":40,"min":0,"max":100}"#
+        );
+    }
+}
@@ -0,0 +1,476 @@
+//! Persistent user settings.
+//!
+//! Stored as TOML at `<config dir>/tray-bright/config.toml` (e.g.
+//! `~/.config` on Linux, `%APPDATA%` on Windows). Missing or unreadable
+//! files fall back to defaults so a bad config never blocks startup.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ambient::AmbientSettings;
+use crate::battery::BatterySettings;
+use crate::fullscreen::FullscreenSettings;
+use crate::schedule::{Curve, Schedule};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Last brightness applied to each monitor, keyed by monitor id.
+    pub brightness: BTreeMap<String, u32>,
+    /// Brightness restored by the reset button and the tray's "Reset all",
+    /// keyed by monitor id.
+    pub default_brightness: BTreeMap<String, u32>,
+    /// Names chosen by the user, keyed by monitor id rather than display
+    /// name so identical monitors can be told apart.
+    pub names: BTreeMap<String, String>,
+    /// Soft brightness range per monitor, keyed by monitor id. Every write
+    /// is clamped into it, whatever sent it.
+    pub limits: BTreeMap<String, BrightnessLimits>,
+    /// Monitors whose slider is drawn the other way round, brightest on
+    /// the left, keyed by monitor id. Only the display is flipped.
+    pub inverted: BTreeSet<String>,
+    /// Percent added to the "All monitors" slider's value for each monitor,
+    /// keyed by monitor id, so linked monitors can track it offset.
+    pub sync_offsets: BTreeMap<String, i32>,
+    /// Brightness (0-100) to set a monitor to when it's plugged in while
+    /// the app is running, keyed by monitor id.
+    pub on_connect: BTreeMap<String, u32>,
+    /// Remember brightness separately for each set of connected monitors,
+    /// such as a docked laptop and the laptop on its own, and restore it
+    /// whenever that set is connected again.
+    pub remember_per_setup: bool,
+    /// Last brightness per set of connected monitors, keyed by
+    /// [`setup_key`] and then by monitor id.
+    pub setups: BTreeMap<String, BTreeMap<String, u32>>,
+    /// Saved brightness setups the user can switch between, by name.
+    pub profiles: BTreeMap<String, Profile>,
+    /// Monitors shown as one slider, by group name, each listing its
+    /// members' monitor ids. A monitor is in at most one group.
+    pub groups: BTreeMap<String, Vec<String>>,
+    /// Monitors hidden from the panel and left alone by the worker, keyed
+    /// by monitor id.
+    pub disabled: BTreeSet<String>,
+    /// Automatic brightness changes by time of day.
+    pub schedule: Schedule,
+    /// Brightness that follows a time-of-day curve.
+    pub curve: Curve,
+    /// Automatic brightness from the ambient light sensor.
+    pub ambient: AmbientSettings,
+    /// Brightness that follows fullscreen apps.
+    pub fullscreen: FullscreenSettings,
+    /// Brightness capped as the battery drains.
+    pub battery: BatterySettings,
+    /// How often the worker reads and writes the monitors.
+    pub polling: PollSettings,
+    /// Read brightness back after each write and retry once if the
+    /// monitor didn't take it. Off by default since it costs an extra
+    /// DDC/CI round trip per write.
+    pub verify_writes: bool,
+    /// Log every brightness change, with what caused it, to a file (see
+    /// [`crate::history`]). Off by default. Read when the app starts.
+    pub history_log: bool,
+    /// Show a notification when a hotkey or the schedule changes
+    /// brightness while the window is hidden (Windows only).
+    pub notify_changes: bool,
+    /// Hide the window as soon as another window takes focus, like the
+    /// OS's own tray flyouts. Off by default.
+    pub hide_on_focus_loss: bool,
+    /// Let the keyboard's brightness keys step every external monitor too
+    /// (macOS only). Off by default since macOS asks for Input Monitoring
+    /// access first.
+    pub brightness_keys: bool,
+    /// What happens to brightness when the app quits.
+    pub on_exit: OnExit,
+    /// How external monitors are reached over DDC/CI (Linux only). Read
+    /// when the app starts.
+    pub ddc_backend: DdcBackend,
+    /// How the slider maps onto each monitor's brightness. Saved
+    /// brightness, limits and presets are all slider positions.
+    pub brightness_scale: BrightnessScale,
+    /// Percent of a monitor's range moved by the global hotkeys, the
+    /// keyboard's brightness keys and Page Up/Down on a slider.
+    pub step_percent: u32,
+    /// Finer step, in percent, for the arrow keys and each mouse-wheel
+    /// notch over a slider. Read from `scroll_step` in older configs.
+    #[serde(alias = "scroll_step")]
+    pub fine_step_percent: u32,
+    /// Window theme.
+    pub theme: ThemeMode,
+    /// One line per monitor (short name, slider and percentage) instead of
+    /// the full rows, for small screens.
+    pub compact: bool,
+    /// Keep the window above other windows while it's open, as tray
+    /// flyouts are.
+    pub always_on_top: bool,
+    /// UI language as a locale tag (`de`, `ja-JP`, ...). Unset follows the
+    /// OS; languages without a translation fall back to English.
+    pub language: Option<String>,
+    /// Quick-set buttons shown under each brightness slider, in percent.
+    pub brightness_presets: Vec<u32>,
+    /// The user closed the DDC/CI help shown when no monitor responds, so
+    /// it isn't shown again.
+    pub ddc_help_dismissed: bool,
+}
+
+/// Worker timings, under `[polling]`. Out-of-range values are clamped
+/// when read rather than rejected, so a typo can't stop the app starting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PollSettings {
+    /// Milliseconds between brightness reads while the window is open.
+    pub poll_interval_ms: u64,
+    /// Milliseconds after the user changes a monitor during which reads of
+    /// it are ignored, so a stale value doesn't move the slider back.
+    /// Monitors slow to apply DDC/CI writes may need longer.
+    pub user_cooldown_ms: u64,
+    /// Milliseconds between checks for new commands while the window is
+    /// open. Lower feels snappier and costs more wake-ups.
+    pub command_check_ms: u64,
+    /// Brightness (0-100) assumed for a monitor that couldn't be read.
+    pub fallback_brightness: u32,
+}
+
+impl Default for PollSettings {
+    fn default() -> Self {
+        Self {
+            poll_interval_ms: 5000,
+            user_cooldown_ms: 4000,
+            command_check_ms: 100,
+            fallback_brightness: 0,
+        }
+    }
+}
+
+impl PollSettings {
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.poll_interval_ms.clamp(1000, 600_000))
+    }
+
+    pub fn user_cooldown(&self) -> Duration {
+        Duration::from_millis(self.user_cooldown_ms.min(60_000))
+    }
+
+    pub fn command_check(&self) -> Duration {
+        Duration::from_millis(self.command_check_ms.clamp(10, 1000))
+    }
+
+    pub fn fallback_brightness(&self) -> u32 {
+        self.fallback_brightness.min(100)
+    }
+}
+
+/// A named brightness setup: brightness (0-100) per monitor, keyed by
+/// monitor id so it still applies after a monitor is reconnected. Monitors
+/// it doesn't list are left alone.
+pub type Profile = BTreeMap<String, u32>;
+
+/// Brightness range a user has narrowed one monitor to, in percent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BrightnessLimits {
+    pub min: u32,
+    pub max: u32,
+}
+
+/// Brightness left on the monitors when the app quits. Written in the
+/// config as `on_exit = "leave"` or `on_exit = { restore_default = 50 }`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnExit {
+    /// Keep whatever brightness was last set.
+    #[default]
+    Leave,
+    /// Set every monitor to this percent of its range.
+    RestoreDefault(u32),
+}
+
+/// How brightness slider positions map onto the values a monitor is sent,
+/// written in the config as `brightness_scale = "perceptual"`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BrightnessScale {
+    /// The slider sends the monitor's own values.
+    #[default]
+    Linear,
+    /// The slider goes through a gamma curve, so equal drags look like
+    /// equal changes. Monitor values are roughly linear in light output,
+    /// which the eye barely tells apart at the bright end.
+    Perceptual,
+}
+
+/// DDC/CI transport for external monitors on Linux, written in the config
+/// as `ddc_backend = "ddcutil"` or `ddc_backend = "i2c"`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DdcBackend {
+    /// Run the `ddcutil` command for every read and write.
+    #[default]
+    Ddcutil,
+    /// Talk to `/dev/i2c-*` directly: faster and needs nothing installed,
+    /// but the user must be able to open the device nodes, usually by
+    /// joining the `i2c` group (`sudo usermod -aG i2c $USER`, then log in
+    /// again) with the `i2c-dev` module loaded.
+    I2c,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeMode {
+    /// Follow the OS light/dark setting.
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            brightness: BTreeMap::new(),
+            default_brightness: BTreeMap::new(),
+            names: BTreeMap::new(),
+            limits: BTreeMap::new(),
+            inverted: BTreeSet::new(),
+            sync_offsets: BTreeMap::new(),
+            on_connect: BTreeMap::new(),
+            remember_per_setup: false,
+            setups: BTreeMap::new(),
+            profiles: BTreeMap::new(),
+            groups: BTreeMap::new(),
+            disabled: BTreeSet::new(),
+            schedule: Schedule::default(),
+            curve: Curve::default(),
+            ambient: AmbientSettings::default(),
+            fullscreen: FullscreenSettings::default(),
+            battery: BatterySettings::default(),
+            polling: PollSettings::default(),
+            verify_writes: false,
+            history_log: false,
+            notify_changes: true,
+            hide_on_focus_loss: false,
+            brightness_keys: false,
+            on_exit: OnExit::default(),
+            ddc_backend: DdcBackend::default(),
+            brightness_scale: BrightnessScale::default(),
+            step_percent: 10,
+            fine_step_percent: 2,
+            theme: ThemeMode::default(),
+            compact: false,
+            always_on_top: true,
+            language: None,
+            brightness_presets: vec![25, 50, 75, 100],
+            ddc_help_dismissed: false,
+        }
+    }
+}
+
+impl Config {
+    /// [`Config::step_percent`], kept to 1-100 so a step always moves.
+    pub fn step_percent(&self) -> u32 {
+        self.step_percent.clamp(1, 100)
+    }
+
+    /// [`Config::fine_step_percent`], kept to 1-100 so a step always moves.
+    pub fn fine_step_percent(&self) -> u32 {
+        self.fine_step_percent.clamp(1, 100)
+    }
+
+    /// Brightness to restore on monitor `id` while the connected monitors
+    /// make up `setup`. With `remember_per_setup` on, a setup seen before
+    /// gets its own levels back and a new one starts from each monitor's
+    /// default brightness.
+    pub fn saved_brightness(&self, setup: &str, id: &str) -> Option<u32> {
+        let last = self.brightness.get(id).copied();
+        if !self.remember_per_setup {
+            return last;
+        }
+        match self.setups.get(setup) {
+            Some(levels) => levels.get(id).copied().or(last),
+            None => self.default_brightness.get(id).copied().or(last),
+        }
+    }
+
+    /// Move `brightness`, `default_brightness` and `disabled` entries that
+    /// older versions keyed by display name over to the ids of `monitors`,
+    /// given as `(id, name)`. Returns whether anything moved.
+    pub fn migrate_name_keys(&mut self, monitors: &[(&str, &str)]) -> bool {
+        let brightness = rekey_by_id(&mut self.brightness, monitors);
+        let defaults = rekey_by_id(&mut self.default_brightness, monitors);
+        let disabled = rekey_set_by_id(&mut self.disabled, monitors);
+        brightness || defaults || disabled
+    }
+
+    /// Location of the config file, or `None` if the platform has no
+    /// config directory.
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("tray-bright").join("config.toml"))
+    }
+
+    /// Load the config file, falling back to defaults if it's missing or
+    /// can't be parsed.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str(&contents).unwrap_or_else(|e| {
+                log::warn!("Ignoring invalid config {}: {e}", path.display());
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Write the config to `path`, creating the directory if needed. Writes
+    /// to a temporary file first so a crash mid-write can't truncate it.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let tmp = path.with_extension("toml.tmp");
+        fs::write(&tmp, toml::to_string_pretty(self)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Move each entry of `map` keyed by one of `monitors`' names, given as
+/// `(id, name)`, to that monitor's id, unless the id has its own entry.
+/// Identical monitors share a name, so each of them gets the value. Names
+/// that are also some monitor's id are left alone. Returns whether any
+/// entry moved.
+pub fn rekey_by_id(map: &mut BTreeMap<String, u32>, monitors: &[(&str, &str)]) -> bool {
+    let names = stale_names(monitors, |name| map.contains_key(name));
+    for &(id, name) in monitors {
+        if let Some(&value) = map.get(name)
+            && names.contains(name)
+        {
+            map.entry(id.to_string()).or_insert(value);
+        }
+    }
+    for name in &names {
+        map.remove(*name);
+    }
+    !names.is_empty()
+}
+
+/// [`rekey_by_id`] for a set of monitors.
+pub fn rekey_set_by_id(set: &mut BTreeSet<String>, monitors: &[(&str, &str)]) -> bool {
+    let names = stale_names(monitors, |name| set.contains(name));
+    for &(id, name) in monitors {
+        if names.contains(name) {
+            set.insert(id.to_string());
+        }
+    }
+    for name in &names {
+        set.remove(*name);
+    }
+    !names.is_empty()
+}
+
+/// Names of `monitors` that `is_key` says are in use as keys, apart from
+/// any that are also some monitor's id.
+fn stale_names<'a>(
+    monitors: &[(&str, &'a str)],
+    is_key: impl Fn(&str) -> bool,
+) -> BTreeSet<&'a str> {
+    monitors
+        .iter()
+        .map(|&(_, name)| name)
+        .filter(|&name| !monitors.iter().any(|&(id, _)| id == name) && is_key(name))
+        .collect()
+}
+
+/// Identifies a set of connected monitors by their ids, whatever order
+/// they were enumerated in, for [`Config::setups`].
+pub fn setup_key(ids: &[String]) -> String {
+    let mut ids = ids.to_vec();
+    ids.sort_unstable();
+    ids.dedup();
+    ids.join(" + ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn poll_settings_clamp_out_of_range_values() {
+        let polling = PollSettings {
+            poll_interval_ms: 0,
+            user_cooldown_ms: u64::MAX,
+            command_check_ms: 5000,
+            fallback_brightness: 150,
+        };
+        assert_eq!(polling.poll_interval(), Duration::from_secs(1));
+        assert_eq!(polling.user_cooldown(), Duration::from_secs(60));
+        assert_eq!(polling.command_check(), Duration::from_secs(1));
+        assert_eq!(polling.fallback_brightness(), 100);
+    }
+
+    #[test]
+    fn setups_restore_their_own_levels() {
+        let office = setup_key(&["DEL-B".into(), "LAPTOP".into(), "DEL-A".into()]);
+        assert_eq!(
+            office,
+            setup_key(&["DEL-A".into(), "DEL-B".into(), "LAPTOP".into()])
+        );
+        let home = setup_key(&["LAPTOP".into()]);
+
+        let mut config = Config::default();
+        config.brightness.insert("LAPTOP".into(), 30);
+        config.default_brightness.insert("LAPTOP".into(), 70);
+        config
+            .setups
+            .insert(office.clone(), BTreeMap::from([("LAPTOP".into(), 90)]));
+
+        // Off: always the last brightness, whatever is connected
+        assert_eq!(config.saved_brightness(&office, "LAPTOP"), Some(30));
+
+        config.remember_per_setup = true;
+        assert_eq!(config.saved_brightness(&office, "LAPTOP"), Some(90));
+        // A setup not seen before starts from the default
+        assert_eq!(config.saved_brightness(&home, "LAPTOP"), Some(70));
+        assert_eq!(config.saved_brightness(&home, "OTHER"), None);
+    }
+
+    #[test]
+    fn name_keys_move_to_monitor_ids() {
+        let mut config = Config::default();
+        config.brightness.insert("DELL U2720Q".into(), 40);
+        config.brightness.insert("DEL:A1:1".into(), 60);
+        config.brightness.insert("Unplugged".into(), 20);
+        config.default_brightness.insert("DELL U2720Q".into(), 70);
+        config.disabled.insert("DELL U2720Q".into());
+
+        let monitors = [
+            ("DEL:A1:1", "DELL U2720Q"),
+            ("DEL:A1:2", "DELL U2720Q"),
+            ("Laptop", "Laptop"),
+        ];
+        assert!(config.migrate_name_keys(&monitors));
+        // An id's own entry wins over the old name's
+        assert_eq!(
+            config.brightness,
+            BTreeMap::from([
+                ("DEL:A1:1".into(), 60),
+                ("DEL:A1:2".into(), 40),
+                ("Unplugged".into(), 20),
+            ])
+        );
+        assert_eq!(
+            config.default_brightness,
+            BTreeMap::from([("DEL:A1:1".into(), 70), ("DEL:A1:2".into(), 70)])
+        );
+        assert_eq!(
+            config.disabled,
+            BTreeSet::from(["DEL:A1:1".into(), "DEL:A1:2".into()])
+        );
+        assert!(!config.migrate_name_keys(&monitors));
+    }
+}
@@ -0,0 +1,165 @@
+//! Optional D-Bus service on the Linux session bus, for desktop brightness
+//! keys and scripts.
+//!
+//! Started with `--dbus` alongside the tray app. Owns the name
+//! `org.traybright.Brightness` and exports `/org/traybright/Brightness`
+//! with interface `org.traybright.Brightness`:
+//!
+//! - `GetMonitors() -> a(suuu)` lists every monitor as
+//!   `(name, current, min, max)`; the array index is the monitor index
+//! - `SetBrightness(u index, u value)` sets one monitor, clamped to its range
+//! - signal `BrightnessChanged(u index, u value)` fires whenever a monitor's
+//!   brightness changes, whoever changed it
+//!
+//! Like the HTTP API, writes go through the UI's command channel and reads
+//! come from the worker's snapshot, so a call never waits on DDC/CI. A
+//! missing or unreachable bus is logged and the app carries on without the
+//! service.
+
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+
+use anyhow::{Context, bail};
+use zbus::blocking::connection;
+use zbus::object_server::SignalEmitter;
+use zbus::{DBusError, interface};
+
+use crate::ui::{MonitorCmd, MonitorSnapshot};
+
+const BUS_NAME: &str = "org.traybright.Brightness";
+const OBJECT_PATH: &str = "/org/traybright/Brightness";
+
+/// How often the snapshot is compared to emit `BrightnessChanged`.
+const SIGNAL_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, DBusError)]
+#[zbus(prefix = "org.traybright.Brightness.Error")]
+enum Error {
+    #[zbus(error)]
+    ZBus(zbus::Error),
+    NoSuchMonitor(String),
+}
+
+/// The exported object. Owned by zbus's object server, which calls it from
+/// its own thread.
+struct Brightness {
+    snapshot: MonitorSnapshot,
+    tx_cmd: Sender<MonitorCmd>,
+}
+
+#[interface(name = "org.traybright.Brightness")]
+impl Brightness {
+    fn get_monitors(&self) -> Vec<(String, u32, u32, u32)> {
+        self.snapshot
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, (current, min, max))| (name.clone(), *current, *min, *max))
+            .collect()
+    }
+
+    fn set_brightness(&self, index: u32, value: u32) -> Result<(), Error> {
+        let Some(&(_, (_, min, max))) = self.snapshot.lock().unwrap().get(index as usize) else {
+            return Err(Error::NoSuchMonitor(format!("No monitor {index}")));
+        };
+        let _ = self.tx_cmd.send(MonitorCmd::SetBrightness(
+            index as usize,
+            value.clamp(min, max),
+        ));
+        Ok(())
+    }
+
+    #[zbus(signal)]
+    async fn brightness_changed(
+        emitter: &SignalEmitter<'_>,
+        index: u32,
+        value: u32,
+    ) -> zbus::Result<()>;
+}
+
+/// Connect to the session bus and serve on a background thread. Failing to
+/// reach the bus or get the name is logged but doesn't stop the tray app.
+pub fn serve(snapshot: MonitorSnapshot, tx_cmd: Sender<MonitorCmd>) {
+    std::thread::spawn(move || {
+        if let Err(e) = run(snapshot, tx_cmd) {
+            log::error!("D-Bus service stopped: {e:#}");
+        }
+    });
+}
+
+fn run(snapshot: MonitorSnapshot, tx_cmd: Sender<MonitorCmd>) -> anyhow::Result<()> {
+    let brightness = Brightness {
+        snapshot: snapshot.clone(),
+        tx_cmd,
+    };
+    let builder = connection::Builder::session()?
+        .serve_at(OBJECT_PATH, brightness)?
+        .name(BUS_NAME)?;
+    // The name is requested without queueing, so a second instance fails
+    // here instead of waiting for the first to exit
+    let connection = match builder.build() {
+        Err(zbus::Error::NameTaken) => {
+            bail!("{BUS_NAME} is already taken; is another instance running?")
+        }
+        result => result.context("can't connect to the session bus")?,
+    };
+    log::info!("Serving D-Bus as {BUS_NAME}");
+
+    let iface = connection
+        .object_server()
+        .interface::<_, Brightness>(OBJECT_PATH)?;
+    emit_changes(iface.signal_emitter(), &snapshot)
+}
+
+/// Watch the snapshot and signal every monitor whose brightness moved,
+/// including monitors that weren't there before.
+fn emit_changes(emitter: &SignalEmitter<'_>, snapshot: &MonitorSnapshot) -> anyhow::Result<()> {
+    let levels = || -> Vec<u32> {
+        snapshot
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, (cur, _, _))| *cur)
+            .collect()
+    };
+    let mut last = levels();
+    loop {
+        std::thread::sleep(SIGNAL_CHECK_INTERVAL);
+        let current = levels();
+        for (index, &value) in current.iter().enumerate() {
+            if last.get(index) != Some(&value) {
+                zbus::block_on(Brightness::brightness_changed(emitter, index as u32, value))
+                    .context("can't send BrightnessChanged")?;
+            }
+        }
+        last = current;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    #[test]
+    fn set_brightness_clamps_to_the_monitor_range() {
+        let (tx_cmd, rx_cmd) = channel();
+        let brightness = Brightness {
+            snapshot: Arc::new(Mutex::new(vec![("DELL U2720Q".to_string(), (50, 10, 90))])),
+            tx_cmd,
+        };
+
+        brightness.set_brightness(0, 95).unwrap();
+        assert!(matches!(
+            rx_cmd.try_recv(),
+            Ok(MonitorCmd::SetBrightness(0, 90))
+        ));
+        assert!(matches!(
+            brightness.set_brightness(1, 50),
+            Err(Error::NoSuchMonitor(_))
+        ));
+        assert!(rx_cmd.try_recv().is_err());
+    }
+}
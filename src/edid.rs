@@ -0,0 +1,447 @@
+//! EDID and PnP device-id helpers.
+//!
+//! Monitors identify themselves with a three-letter PnP manufacturer code
+//! and a 16-bit product code, both in the EDID block and in the device ids
+//! the OS builds from it (`DISPLAY\DEL41A4\...` on Windows). These are
+//! used for stable per-monitor ids and for naming monitors that don't
+//! report a friendly name. HDR displays also state their peak luminance in
+//! a CTA-861 extension block.
+
+/// Common PnP manufacturer codes and their vendors, sorted by code.
+const VENDORS: [(&str, &str); 72] = [
+    ("AAC", "AcerView"),
+    ("ACI", "ASUS"),
+    ("ACR", "Acer"),
+    ("AMW", "AMW"),
+    ("AOC", "AOC"),
+    ("APP", "Apple"),
+    ("AUO", "AU Optronics"),
+    ("AUS", "ASUS"),
+    ("BNQ", "BenQ"),
+    ("BOE", "BOE"),
+    ("CMN", "Innolux"),
+    ("CMO", "Chi Mei"),
+    ("CPQ", "Compaq"),
+    ("CSO", "CSOT"),
+    ("CTX", "CTX"),
+    ("DEL", "Dell"),
+    ("EIZ", "EIZO"),
+    ("ELO", "Elo Touch"),
+    ("ENC", "EIZO"),
+    ("EPI", "Envision"),
+    ("FUS", "Fujitsu Siemens"),
+    ("GBT", "Gigabyte"),
+    ("GSM", "LG"),
+    ("GWY", "Gateway"),
+    ("HEI", "Hyundai"),
+    ("HIQ", "Hyundai ImageQuest"),
+    ("HKC", "HKC"),
+    ("HPN", "HP"),
+    ("HRE", "Haier"),
+    ("HSD", "HannStar"),
+    ("HWP", "HP"),
+    ("IBM", "IBM"),
+    ("IFS", "InFocus"),
+    ("IVM", "iiyama"),
+    ("IVO", "InfoVision"),
+    ("KDS", "KDS"),
+    ("LEN", "Lenovo"),
+    ("LGD", "LG Display"),
+    ("LPL", "LG Philips"),
+    ("LTN", "Lite-On"),
+    ("MAG", "MAG InnoVision"),
+    ("MAX", "Belinea"),
+    ("MED", "Medion"),
+    ("MEI", "Panasonic"),
+    ("MSI", "MSI"),
+    ("NAN", "Nanao"),
+    ("NEC", "NEC"),
+    ("NOK", "Nokia"),
+    ("NVD", "NVIDIA"),
+    ("ONK", "Onkyo"),
+    ("PGS", "Princeton"),
+    ("PHL", "Philips"),
+    ("PIO", "Pioneer"),
+    ("PNR", "Planar"),
+    ("QDS", "Quanta Display"),
+    ("SAM", "Samsung"),
+    ("SAN", "Sanyo"),
+    ("SDC", "Samsung Display"),
+    ("SEC", "Seiko Epson"),
+    ("SGI", "SGI"),
+    ("SHP", "Sharp"),
+    ("SNY", "Sony"),
+    ("SPT", "Sceptre"),
+    ("TAT", "Tatung"),
+    ("TMX", "Tianma"),
+    ("TOS", "Toshiba"),
+    ("TSB", "Toshiba"),
+    ("VIZ", "Vizio"),
+    ("VSC", "ViewSonic"),
+    ("WAC", "Wacom"),
+    ("XMI", "Xiaomi"),
+    ("YMH", "Yamaha"),
+];
+
+/// Vendor name for a PnP manufacturer code, e.g. `DEL` → `Dell`.
+pub fn vendor_name(code: &str) -> Option<&'static str> {
+    VENDORS
+        .binary_search_by_key(&code, |&(c, _)| c)
+        .ok()
+        .map(|i| VENDORS[i].1)
+}
+
+/// The parts of a PnP device id.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DeviceId {
+    /// Three-letter PnP manufacturer code, e.g. `DEL`. Kept even when
+    /// [`vendor_name`] doesn't know it.
+    pub manufacturer: String,
+    /// Product code as four hex digits, e.g. `41A4`.
+    pub product: String,
+    /// Device instance, e.g. `5&1a2b3c&0&UID4353`, when the id has one.
+    pub instance: Option<String>,
+}
+
+impl DeviceId {
+    /// `Dell 41A4`, or `XYZ 1234` for a manufacturer not in the table.
+    pub fn display_name(&self) -> String {
+        let vendor = vendor_name(&self.manufacturer).unwrap_or(&self.manufacturer);
+        format!("{vendor} {}", self.product)
+    }
+}
+
+/// Parse a monitor's PnP device id in any of the forms Windows hands out:
+///
+/// - `MONITOR\DEL41A4\{4d36e96e-e325-11ce-bfc1-08002be10318}\0001`
+/// - `DISPLAY\DEL41A4\5&1a2b3c&0&UID4353`
+/// - `\\?\DISPLAY#DEL41A4#5&1a2b3c&0&UID4353#{e6f07b5f-...}`
+/// - a bare hardware id, `DEL41A4`
+// Only Windows hands out ids in this form
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn parse_device_id(id: &str) -> Option<DeviceId> {
+    let id = id.strip_prefix(r"\\?\").unwrap_or(id);
+    let mut segments = id.split(['\\', '#']).filter(|s| !s.is_empty()).peekable();
+    if segments
+        .peek()
+        .is_some_and(|s| s.eq_ignore_ascii_case("MONITOR") || s.eq_ignore_ascii_case("DISPLAY"))
+    {
+        segments.next();
+    }
+
+    let hardware_id = segments.next()?;
+    if hardware_id.len() != 7 || !hardware_id.is_ascii() {
+        return None;
+    }
+    let (manufacturer, product) = hardware_id.split_at(3);
+    if !manufacturer.bytes().all(|b| b.is_ascii_alphabetic())
+        || !product.bytes().all(|b| b.is_ascii_hexdigit())
+    {
+        return None;
+    }
+
+    // Class GUIDs aren't instances
+    let instance = segments.next().filter(|s| !s.starts_with('{'));
+
+    Some(DeviceId {
+        manufacturer: manufacturer.to_ascii_uppercase(),
+        product: product.to_ascii_uppercase(),
+        instance: instance.map(str::to_string),
+    })
+}
+
+/// Stable identifier from an EDID block: `MFG:PRODUCT:SERIAL`, e.g.
+/// `DEL:41A4:ABC123`. The serial is the descriptor string when there is
+/// one, else the numeric serial; it's left off when the monitor reports
+/// neither. `None` if `edid` isn't a valid base block.
+pub fn stable_id(edid: &[u8]) -> Option<String> {
+    let DeviceId {
+        manufacturer,
+        product,
+        ..
+    } = edid_device(edid)?;
+
+    let serial_text = descriptor_texts(edid, SERIAL_DESCRIPTOR).next();
+    let serial_number = u32::from_le_bytes([edid[12], edid[13], edid[14], edid[15]]);
+    let serial = match serial_text {
+        Some(text) if !text.is_empty() => Some(text),
+        _ if serial_number != 0 => Some(serial_number.to_string()),
+        _ => None,
+    };
+
+    Some(match serial {
+        Some(serial) => format!("{manufacturer}:{product}:{serial}"),
+        None => format!("{manufacturer}:{product}"),
+    })
+}
+
+/// Model name from an EDID block: the monitor name descriptor, else the
+/// vendor and the panel's part number (built-in panels usually carry only
+/// that), else the vendor and product code as [`DeviceId::display_name`].
+/// `None` if `edid` isn't a valid base block.
+// Only Linux names built-in panels from their EDID
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub fn model_name(edid: &[u8]) -> Option<String> {
+    let device = edid_device(edid)?;
+    if let Some(name) = descriptor_texts(edid, NAME_DESCRIPTOR).find(|text| !text.is_empty()) {
+        return Some(name);
+    }
+    // Panels list the maker's name first and the part number last
+    let vendor = vendor_name(&device.manufacturer).unwrap_or(&device.manufacturer);
+    let part = descriptor_texts(edid, TEXT_DESCRIPTOR)
+        .filter(|text| !text.is_empty())
+        .last();
+    Some(match part {
+        Some(part) => format!("{vendor} {part}"),
+        None => device.display_name(),
+    })
+}
+
+/// Display descriptor tags whose payload is text.
+const SERIAL_DESCRIPTOR: u8 = 0xFF;
+const TEXT_DESCRIPTOR: u8 = 0xFE;
+const NAME_DESCRIPTOR: u8 = 0xFC;
+
+/// Trimmed text of each display descriptor tagged `tag` in a base block
+/// already checked by [`edid_device`], in order. The text ends at a newline.
+fn descriptor_texts(edid: &[u8], tag: u8) -> impl Iterator<Item = String> + '_ {
+    edid[54..126]
+        .chunks_exact(18)
+        .filter(move |d| d[..3] == [0, 0, 0] && d[3] == tag)
+        .map(|d| {
+            String::from_utf8_lossy(&d[5..])
+                .split('\n')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        })
+}
+
+/// The `MFG:PRODUCT` part of a monitor id, shared by every unit of the
+/// same model. `None` for ids that didn't come from EDID (or ddcutil's
+/// equivalent `MFG:MODEL:SERIAL` summary), such as plain names.
+pub fn model_key(id: &str) -> Option<&str> {
+    let mut parts = id.splitn(3, ':');
+    let manufacturer = parts.next()?;
+    let product = parts.next().filter(|p| !p.is_empty())?;
+    if manufacturer.len() != 3 || !manufacturer.bytes().all(|b| b.is_ascii_uppercase()) {
+        return None;
+    }
+    Some(&id[..manufacturer.len() + 1 + product.len()])
+}
+
+/// Manufacturer and product code from an EDID block, or `None` if `edid`
+/// isn't a valid base block.
+pub fn edid_device(edid: &[u8]) -> Option<DeviceId> {
+    const HEADER: [u8; 8] = [0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00];
+    if edid.len() < 128 || edid[..8] != HEADER {
+        return None;
+    }
+
+    // Three 5-bit letters, 'A' = 1, big-endian
+    let packed = u16::from_be_bytes([edid[8], edid[9]]);
+    let manufacturer = [10, 5, 0]
+        .iter()
+        .map(|shift| char::from(b'@' + ((packed >> shift) & 0x1F) as u8))
+        .collect();
+    let product = u16::from_le_bytes([edid[10], edid[11]]);
+
+    Some(DeviceId {
+        manufacturer,
+        product: format!("{product:04X}"),
+        instance: None,
+    })
+}
+
+/// Tag of a CTA-861 extension block, the one that carries HDR metadata.
+const CTA_EXTENSION: u8 = 0x02;
+
+/// Peak luminance in cd/m² (nits) from the HDR static metadata block of a
+/// CTA-861 extension. `None` for SDR displays, which don't have the block,
+/// and for HDR ones that leave the value out.
+pub fn max_luminance(edid: &[u8]) -> Option<u32> {
+    edid_device(edid)?;
+    edid.chunks_exact(128)
+        .skip(1)
+        .filter(|block| block[0] == CTA_EXTENSION && block[1] >= 3)
+        .find_map(hdr_max_luminance)
+}
+
+/// Walk a CTA-861 extension's data blocks for the HDR static metadata block
+/// (extended tag 6) and decode its desired content max luminance.
+fn hdr_max_luminance(block: &[u8]) -> Option<u32> {
+    // Data blocks run from byte 4 up to where the detailed timings start
+    let end = (block[2] as usize).clamp(4, 127);
+    let mut pos = 4;
+    while pos < end {
+        let tag = block[pos] >> 5;
+        let len = (block[pos] & 0x1F) as usize;
+        let payload = block.get(pos + 1..(pos + 1 + len).min(end))?;
+        // Extended tag, then EOTFs, metadata types and max luminance
+        if tag == 7 && payload.len() >= 4 && payload[0] == 0x06 {
+            let code = payload[3];
+            // CTA-861.3: 50 * 2^(code / 32), with 0 meaning "not given"
+            return (code != 0).then(|| (50.0 * 2f64.powf(f64::from(code) / 32.0)).round() as u32);
+        }
+        pos += 1 + len;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal EDID base block for Dell (DEL) product 0x41A4.
+    fn edid(serial_number: u32, serial_text: Option<&str>) -> Vec<u8> {
+        let mut edid = vec![0u8; 128];
+        edid[..8].copy_from_slice(&[0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00]);
+        edid[8..10].copy_from_slice(&[0x10, 0xAC]);
+        edid[10..12].copy_from_slice(&0x41A4u16.to_le_bytes());
+        edid[12..16].copy_from_slice(&serial_number.to_le_bytes());
+        if let Some(text) = serial_text {
+            // Second descriptor; the first is usually the detailed timing
+            let descriptor = &mut edid[72..90];
+            descriptor[3] = 0xFF;
+            descriptor[5..].fill(b' ');
+            descriptor[5..5 + text.len()].copy_from_slice(text.as_bytes());
+            descriptor[5 + text.len()] = b'\n';
+        }
+        edid
+    }
+
+    #[test]
+    fn stable_id_prefers_serial_string() {
+        assert_eq!(
+            stable_id(&edid(1234, Some("ABC123"))).unwrap(),
+            "DEL:41A4:ABC123"
+        );
+        assert_eq!(stable_id(&edid(1234, None)).unwrap(), "DEL:41A4:1234");
+        assert_eq!(stable_id(&edid(0, None)).unwrap(), "DEL:41A4");
+    }
+
+    #[test]
+    fn model_key_drops_serial() {
+        assert_eq!(model_key("DEL:41A4:ABC123"), Some("DEL:41A4"));
+        assert_eq!(model_key("DEL:41A4"), Some("DEL:41A4"));
+        assert_eq!(model_key("DEL:DELL U2720Q:7XYZ"), Some("DEL:DELL U2720Q"));
+        assert_eq!(model_key("card0-DP-1"), None);
+        assert_eq!(model_key("Built-in Display:42"), None);
+        assert_eq!(model_key("DEL:"), None);
+    }
+
+    #[test]
+    fn edid_device_names_vendor() {
+        assert_eq!(
+            edid_device(&edid(0, None)).unwrap().display_name(),
+            "Dell 41A4"
+        );
+    }
+
+    #[test]
+    fn model_name_prefers_name_descriptor() {
+        let text = |edid: &mut Vec<u8>, at: usize, tag: u8, text: &str| {
+            let descriptor = &mut edid[at..at + 18];
+            descriptor[3] = tag;
+            descriptor[5..].fill(b' ');
+            descriptor[5..5 + text.len()].copy_from_slice(text.as_bytes());
+            descriptor[5 + text.len()] = b'\n';
+        };
+        let mut panel = edid(0, None);
+        assert_eq!(model_name(&panel).unwrap(), "Dell 41A4");
+        text(&mut panel, 90, TEXT_DESCRIPTOR, "DELL HF");
+        text(&mut panel, 108, TEXT_DESCRIPTOR, "B140HAN04.0");
+        assert_eq!(model_name(&panel).unwrap(), "Dell B140HAN04.0");
+        text(&mut panel, 72, NAME_DESCRIPTOR, "DELL U2720Q");
+        assert_eq!(model_name(&panel).unwrap(), "DELL U2720Q");
+        assert_eq!(model_name(&[0; 128]), None);
+    }
+
+    #[test]
+    fn stable_id_rejects_garbage() {
+        assert_eq!(stable_id(&[0; 128]), None);
+        assert_eq!(stable_id(&edid(1, None)[..64]), None);
+    }
+
+    #[test]
+    fn max_luminance_reads_hdr_metadata() {
+        let mut hdr = edid(0, None);
+        hdr[126] = 1;
+        let mut cta = vec![0u8; 128];
+        cta[..4].copy_from_slice(&[CTA_EXTENSION, 3, 14, 0]);
+        // An audio block to skip, then HDR static metadata with a max
+        // luminance code of 96 (400 nits)
+        cta[4..14].copy_from_slice(&[0x23, 0x09, 0x07, 0x07, 0xE5, 0x06, 0x05, 0x01, 0x60, 0x40]);
+        hdr.extend(cta);
+
+        assert_eq!(max_luminance(&hdr), Some(400));
+        assert_eq!(max_luminance(&edid(0, None)), None);
+
+        hdr[140] = 0;
+        assert_eq!(max_luminance(&hdr), None);
+    }
+
+    #[test]
+    fn vendor_table_is_sorted_and_unique() {
+        assert!(VENDORS.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(VENDORS.iter().all(|(code, _)| code.len() == 3));
+        assert_eq!(vendor_name("DEL"), Some("Dell"));
+        assert_eq!(vendor_name("GSM"), Some("LG"));
+        assert_eq!(vendor_name("ZZZ"), None);
+    }
+
+    #[test]
+    fn parses_display_instance() {
+        let id = parse_device_id(r"DISPLAY\DEL41A4\5&1a2b3c&0&UID4353").unwrap();
+        assert_eq!(
+            id,
+            DeviceId {
+                manufacturer: "DEL".to_string(),
+                product: "41A4".to_string(),
+                instance: Some("5&1a2b3c&0&UID4353".to_string()),
+            }
+        );
+        assert_eq!(id.display_name(), "Dell 41A4");
+    }
+
+    #[test]
+    fn parses_monitor_prefix_and_interface_path() {
+        let id = parse_device_id(r"MONITOR\SAM0F9E\{4d36e96e-e325-11ce-bfc1-08002be10318}\0001")
+            .unwrap();
+        assert_eq!(
+            (id.manufacturer.as_str(), id.product.as_str()),
+            ("SAM", "0F9E")
+        );
+        assert_eq!(id.instance, None);
+
+        let id = parse_device_id(
+            r"\\?\DISPLAY#GSM5B7F#5&2c03a83e&0&UID4352#{e6f07b5f-ee97-4a90-b076-33f57bf4eaa7}",
+        )
+        .unwrap();
+        assert_eq!(id.display_name(), "LG 5B7F");
+        assert_eq!(id.instance.as_deref(), Some("5&2c03a83e&0&UID4352"));
+
+        assert_eq!(
+            parse_device_id("del41a4").unwrap().display_name(),
+            "Dell 41A4"
+        );
+    }
+
+    #[test]
+    fn keeps_unknown_vendor_codes() {
+        let id = parse_device_id(r"DISPLAY\XYZ1234\1").unwrap();
+        assert_eq!(id.manufacturer, "XYZ");
+        assert_eq!(id.display_name(), "XYZ 1234");
+    }
+
+    #[test]
+    fn rejects_short_and_malformed_ids() {
+        assert_eq!(parse_device_id(""), None);
+        assert_eq!(parse_device_id("MONITOR"), None);
+        assert_eq!(parse_device_id(r"MONITOR\DEL41"), None);
+        assert_eq!(parse_device_id(r"DISPLAY\D3L41A4"), None);
+        assert_eq!(parse_device_id(r"DISPLAY\DEL41G4"), None);
+        assert_eq!(parse_device_id(r"DISPLAY\DÉL41A"), None);
+    }
+}
@@ -0,0 +1,95 @@
+//! Brightness that follows fullscreen apps.
+//!
+//! Configured under `[fullscreen]` in the config file, e.g.
+//!
+//! ```toml
+//! [fullscreen]
+//! enabled = true
+//! fullscreen_brightness = 100
+//! windowed_brightness = 50
+//! ```
+//!
+//! When an app goes fullscreen every monitor is set to
+//! `fullscreen_brightness`. When it leaves, monitors go to
+//! `windowed_brightness`, or back to where they were before if that's unset.
+//! Changes only happen on those transitions, so a monitor the user adjusts
+//! keeps its value until the next one, and a monitor adjusted while the app
+//! was fullscreen isn't put back when it exits.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FullscreenSettings {
+    /// Off by default; also stays off on platforms that can't tell.
+    pub enabled: bool,
+    /// Brightness (0-100) while a fullscreen app is in front.
+    pub fullscreen_brightness: u32,
+    /// Brightness (0-100) once it's gone; `None` restores the brightness
+    /// each monitor had before.
+    pub windowed_brightness: Option<u32>,
+}
+
+impl Default for FullscreenSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            fullscreen_brightness: 100,
+            windowed_brightness: None,
+        }
+    }
+}
+
+impl FullscreenSettings {
+    /// Brightness to write to each monitor when a fullscreen app appears
+    /// (`entering`) or goes away. `before` is each monitor's brightness when
+    /// the app appeared, and `touched` whether the user has changed it
+    /// since; those are left alone on the way out.
+    pub fn targets(&self, entering: bool, before: &[u32], touched: &[bool]) -> Vec<Option<u32>> {
+        before
+            .iter()
+            .zip(touched)
+            .map(|(&before, &touched)| {
+                if entering {
+                    Some(self.fullscreen_brightness.min(100))
+                } else if touched {
+                    None
+                } else {
+                    Some(self.windowed_brightness.unwrap_or(before).min(100))
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entering_sets_every_monitor() {
+        let settings = FullscreenSettings::default();
+        assert_eq!(
+            settings.targets(true, &[30, 60], &[true, false]),
+            vec![Some(100), Some(100)]
+        );
+    }
+
+    #[test]
+    fn leaving_restores_untouched_monitors() {
+        let settings = FullscreenSettings::default();
+        assert_eq!(
+            settings.targets(false, &[30, 60], &[false, true]),
+            vec![Some(30), None]
+        );
+
+        let fixed = FullscreenSettings {
+            windowed_brightness: Some(120),
+            ..FullscreenSettings::default()
+        };
+        assert_eq!(
+            fixed.targets(false, &[30, 60], &[false, false]),
+            vec![Some(100), Some(100)]
+        );
+    }
+}
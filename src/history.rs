@@ -0,0 +1,154 @@
+//! Opt-in log of every brightness change, for working out why a monitor's
+//! brightness moved.
+//!
+//! Turned on with `history_log = true` in the config. The worker appends
+//! one line per change to `history.log` in the platform's data directory
+//! (`~/.local/share/tray-bright` on Linux, `%APPDATA%\tray-bright` on
+//! Windows, `~/Library/Application Support/tray-bright` on macOS):
+//!
+//! ```text
+//! 2026-10-16T21:04:11+02:00 schedule DEL:DELL U2720Q:7XYZ123 40%
+//! ```
+//!
+//! Once the file reaches [`MAX_SIZE`] it's moved to `history.log.1`,
+//! replacing the one before, so at most two files are kept.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Local, SecondsFormat};
+
+/// Size in bytes at which the log is rotated.
+pub const MAX_SIZE: u64 = 1024 * 1024;
+
+/// What asked for a brightness change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// The panel, the tray, the HTTP or D-Bus API, a profile or a reset.
+    User,
+    /// A global hotkey or the keyboard's brightness keys.
+    Hotkey,
+    Schedule,
+    Curve,
+    Ambient,
+    Fullscreen,
+    /// The battery cap lowering a monitor.
+    Battery,
+    /// Saved brightness put back at startup or after a power event.
+    Restore,
+    /// A monitor's on-connect preset.
+    Connect,
+    /// Not a change the app made: a poll read something other than what
+    /// it last knew, e.g. after the monitor's own buttons were used.
+    Poll,
+}
+
+impl Source {
+    fn label(self) -> &'static str {
+        match self {
+            Source::User => "user",
+            Source::Hotkey => "hotkey",
+            Source::Schedule => "schedule",
+            Source::Curve => "curve",
+            Source::Ambient => "ambient",
+            Source::Fullscreen => "fullscreen",
+            Source::Battery => "battery",
+            Source::Restore => "restore",
+            Source::Connect => "connect",
+            Source::Poll => "poll",
+        }
+    }
+}
+
+pub struct History {
+    path: PathBuf,
+    /// A write has failed, so later failures aren't logged again.
+    failed: bool,
+}
+
+impl History {
+    /// The log in the platform's data directory, or `None` if it has none
+    /// or it can't be created.
+    pub fn open() -> Option<Self> {
+        let dir = dirs::data_dir()?.join("tray-bright");
+        if let Err(e) = fs::create_dir_all(&dir) {
+            log::warn!("Can't create {}: {e}", dir.display());
+            return None;
+        }
+        Some(Self::at(dir.join("history.log")))
+    }
+
+    fn at(path: PathBuf) -> Self {
+        Self {
+            path,
+            failed: false,
+        }
+    }
+
+    /// Append a line saying monitor `id` was set to `percent`.
+    pub fn record(&mut self, source: Source, id: &str, percent: u32) {
+        let line = format_line(Local::now(), source, id, percent);
+        if let Err(e) = self.append(&line) {
+            if !self.failed {
+                log::warn!("Failed to write {}: {e}", self.path.display());
+            }
+            self.failed = true;
+        }
+    }
+
+    fn append(&self, line: &str) -> std::io::Result<()> {
+        if fs::metadata(&self.path).is_ok_and(|meta| meta.len() >= MAX_SIZE) {
+            fs::rename(&self.path, self.path.with_extension("log.1"))?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{line}")
+    }
+}
+
+fn format_line(time: DateTime<Local>, source: Source, id: &str, percent: u32) -> String {
+    format!(
+        "{} {} {id} {percent}%",
+        time.to_rfc3339_opts(SecondsFormat::Secs, false),
+        source.label()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_carry_time_source_id_and_percent() {
+        let time = DateTime::parse_from_rfc3339("2026-10-16T21:04:11Z")
+            .unwrap()
+            .with_timezone(&Local);
+        let line = format_line(time, Source::Schedule, "DEL:DELL U2720Q:7XYZ123", 40);
+        assert!(line.ends_with(" schedule DEL:DELL U2720Q:7XYZ123 40%"));
+        assert_eq!(
+            DateTime::parse_from_rfc3339(line.split(' ').next().unwrap()).unwrap(),
+            time
+        );
+    }
+
+    #[test]
+    fn full_log_is_rotated_before_writing() {
+        let dir = std::env::temp_dir().join(format!("tray-bright-history-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("history.log");
+        fs::write(&path, vec![b'x'; MAX_SIZE as usize]).unwrap();
+
+        let mut history = History::at(path.clone());
+        history.record(Source::User, "DP-1", 50);
+        let current = fs::read_to_string(&path).unwrap();
+        let rotated = fs::metadata(dir.join("history.log.1")).map(|meta| meta.len());
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(current.ends_with(" user DP-1 50%\n"));
+        assert_eq!(current.lines().count(), 1);
+        assert_eq!(rotated.unwrap(), MAX_SIZE);
+    }
+}
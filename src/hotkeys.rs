@@ -0,0 +1,120 @@
+//! System-wide keyboard shortcuts that work while the window is hidden.
+//!
+//! Ctrl+Alt+Up / Ctrl+Alt+Down step every monitor by the configured
+//! `step_percent` (see [`set_step`]). Presses are forwarded to the monitor worker as
+//! `IncreaseAll`/`DecreaseAll` commands. Ctrl+Alt+Shift+B is the panic
+//! key: it sends `RestoreAll`, putting every monitor at full brightness,
+//! for when one has been turned down too far to see the panel.
+//!
+//! With `brightness_keys` set in the config, the keyboard's own brightness
+//! keys step every monitor the same way on platforms that support it.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::Sender;
+
+use global_hotkey::hotkey::{Code, HotKey, Modifiers};
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+
+use crate::os::{BrightnessKeys, PlatformBrightnessKeys, SetupRequired};
+use crate::ui::MonitorCmd;
+
+/// Percent of each monitor's range moved by one hotkey press. Shared with
+/// the hotkey handlers so a change in the panel applies straight away.
+static STEP_PERCENT: AtomicU32 = AtomicU32::new(10);
+
+/// Set how far one hotkey or brightness-key press moves each monitor.
+pub fn set_step(percent: u32) {
+    STEP_PERCENT.store(percent, Ordering::Relaxed);
+}
+
+fn step() -> u32 {
+    STEP_PERCENT.load(Ordering::Relaxed)
+}
+
+thread_local! {
+    /// The manager must stay on the thread that created it — on Windows it
+    /// owns a message-only window and isn't `Send`.
+    static MANAGER: RefCell<Option<(GlobalHotKeyManager, Vec<HotKey>)>> =
+        const { RefCell::new(None) };
+}
+
+/// Forward the keyboard's brightness keys as hotkey steps. Returns `false`
+/// when the OS wants a permission granted first, so the panel can say so;
+/// other failures are only logged.
+pub fn watch_brightness_keys(tx_cmd: Sender<MonitorCmd>) -> bool {
+    let watched = PlatformBrightnessKeys::watch(Box::new(move |up| {
+        let cmd = if up {
+            MonitorCmd::IncreaseAll(step())
+        } else {
+            MonitorCmd::DecreaseAll(step())
+        };
+        let _ = tx_cmd.send(cmd);
+    }));
+    match watched {
+        Ok(()) => true,
+        Err(e) => {
+            log::warn!("Brightness keys unavailable: {e}");
+            !e.is::<SetupRequired>()
+        }
+    }
+}
+
+/// Register the brightness hotkeys on the current (event-loop) thread.
+/// Shortcuts already claimed by another app are skipped with a warning.
+pub fn register(tx_cmd: Sender<MonitorCmd>) {
+    let manager = match GlobalHotKeyManager::new() {
+        Ok(manager) => manager,
+        Err(e) => {
+            log::warn!("Global hotkeys unavailable: {e}");
+            return;
+        }
+    };
+
+    let up = HotKey::new(Some(Modifiers::CONTROL | Modifiers::ALT), Code::ArrowUp);
+    let down = HotKey::new(Some(Modifiers::CONTROL | Modifiers::ALT), Code::ArrowDown);
+    let restore = HotKey::new(
+        Some(Modifiers::CONTROL | Modifiers::ALT | Modifiers::SHIFT),
+        Code::KeyB,
+    );
+
+    let mut registered = Vec::new();
+    for hotkey in [up, down, restore] {
+        match manager.register(hotkey) {
+            Ok(()) => registered.push(hotkey),
+            Err(e) => log::warn!("Failed to register hotkey {hotkey}: {e}"),
+        }
+    }
+
+    let (up_id, down_id, restore_id) = (up.id(), down.id(), restore.id());
+    GlobalHotKeyEvent::set_event_handler(Some(move |event: GlobalHotKeyEvent| {
+        if event.state() != HotKeyState::Pressed {
+            return;
+        }
+
+        let cmd = if event.id() == up_id {
+            MonitorCmd::IncreaseAll(step())
+        } else if event.id() == down_id {
+            MonitorCmd::DecreaseAll(step())
+        } else if event.id() == restore_id {
+            // Warning level so it shows without --verbose
+            log::warn!("Panic hotkey pressed: setting every monitor to full brightness");
+            MonitorCmd::RestoreAll
+        } else {
+            return;
+        };
+        let _ = tx_cmd.send(cmd);
+    }));
+
+    MANAGER.with(|m| *m.borrow_mut() = Some((manager, registered)));
+}
+
+/// Release the hotkeys registered by [`register`]. No-op when called from
+/// a different thread or if registration failed.
+pub fn unregister() {
+    MANAGER.with(|m| {
+        if let Some((manager, hotkeys)) = m.borrow_mut().take() {
+            let _ = manager.unregister_all(&hotkeys);
+        }
+    });
+}
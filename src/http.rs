@@ -0,0 +1,186 @@
+//! Optional local HTTP API for home-automation tools.
+//!
+//! Started with `--serve <port>` alongside the tray app:
+//!
+//! - `GET /monitors` lists every monitor as
+//!   `{"index", "name", "current", "min", "max"}`
+//! - `GET /monitors/{i}/brightness` returns `{"value": <percent>}`
+//! - `PUT /monitors/{i}/brightness` with a `{"value": <percent>}` body sets it
+//!
+//! Writes go through the same command channel as the UI and tray, and reads
+//! come from the worker's latest snapshot, so no request ever waits on a
+//! DDC/CI round-trip.
+//!
+//! There is no authentication: anything that can reach the port can change
+//! brightness. It listens on 127.0.0.1 unless `--serve-bind` says otherwise.
+
+use std::io::Read;
+use std::net::SocketAddr;
+use std::sync::mpsc::Sender;
+
+use serde::Deserialize;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::cli::Level;
+use crate::ui::{MonitorCmd, MonitorSnapshot};
+
+/// Requests bigger than this are rejected; the API only takes tiny bodies.
+const MAX_BODY: u64 = 1024;
+
+/// The body of a `PUT /monitors/{i}/brightness`.
+#[derive(Debug, Deserialize)]
+struct ValueBody {
+    value: u32,
+}
+
+/// Bind `addr` and answer requests on a background thread. Failing to bind
+/// is logged but doesn't stop the tray app.
+pub fn serve(addr: SocketAddr, snapshot: MonitorSnapshot, tx_cmd: Sender<MonitorCmd>) {
+    let server = match Server::http(addr) {
+        Ok(server) => server,
+        Err(e) => {
+            log::error!("Can't serve HTTP on {addr}: {e}");
+            return;
+        }
+    };
+    if !addr.ip().is_loopback() {
+        log::warn!("HTTP API on {addr} is reachable from the network and has no authentication");
+    }
+    log::info!("Serving HTTP on {addr}");
+
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            // The body is read here, so each request gets its own thread
+            // and a client that stalls mid-body can't hold up the others
+            let (snapshot, tx_cmd) = (snapshot.clone(), tx_cmd.clone());
+            std::thread::spawn(move || handle(request, &snapshot, &tx_cmd));
+        }
+    });
+}
+
+fn handle(mut request: Request, snapshot: &MonitorSnapshot, tx_cmd: &Sender<MonitorCmd>) {
+    let mut body = String::new();
+    let read = request
+        .as_reader()
+        .take(MAX_BODY + 1)
+        .read_to_string(&mut body);
+    let reply = if read.is_err() || body.len() as u64 > MAX_BODY {
+        Reply::error(400, "Malformed request")
+    } else {
+        route(request.method(), request.url(), &body, snapshot, tx_cmd)
+    };
+
+    let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+        .expect("static header is valid");
+    let response = Response::from_string(reply.body)
+        .with_status_code(reply.status)
+        .with_header(content_type);
+    if let Err(e) = request.respond(response) {
+        log::debug!("HTTP request failed: {e}");
+    }
+}
+
+struct Reply {
+    status: u16,
+    body: String,
+}
+
+impl Reply {
+    fn ok(body: serde_json::Value) -> Self {
+        Self {
+            status: 200,
+            body: body.to_string(),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            body: serde_json::json!({ "error": message }).to_string(),
+        }
+    }
+}
+
+fn route(
+    method: &Method,
+    path: &str,
+    body: &str,
+    snapshot: &MonitorSnapshot,
+    tx_cmd: &Sender<MonitorCmd>,
+) -> Reply {
+    let monitors = snapshot.lock().unwrap().clone();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    match (method, segments.as_slice()) {
+        (Method::Get, ["monitors"]) => {
+            let levels: Vec<Level> = monitors
+                .iter()
+                .enumerate()
+                .map(|(i, (name, level))| Level::new(i, name, *level))
+                .collect();
+            Reply::ok(serde_json::json!(levels))
+        }
+        (method, ["monitors", index, "brightness"]) => {
+            let Some((index, (_, (current, min, max)))) = index
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| monitors.get(i).map(|m| (i, m)))
+            else {
+                return Reply::error(404, "No such monitor");
+            };
+
+            match method {
+                Method::Get => Reply::ok(serde_json::json!({ "value": current })),
+                Method::Put => match serde_json::from_str::<ValueBody>(body) {
+                    Ok(ValueBody { value }) => {
+                        let value = value.clamp(*min, *max);
+                        let _ = tx_cmd.send(MonitorCmd::SetBrightness(index, value));
+                        Reply::ok(serde_json::json!({ "value": value }))
+                    }
+                    Err(_) => Reply::error(400, "Expected a body like {\"value\": 50}"),
+                },
+                _ => Reply::error(405, "Use GET or PUT"),
+            }
+        }
+        _ => Reply::error(404, "Not found"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    #[test]
+    fn put_takes_any_json_object_with_a_value() {
+        let snapshot: MonitorSnapshot =
+            Arc::new(Mutex::new(vec![("Dell".to_string(), (50, 10, 90))]));
+        let (tx_cmd, rx_cmd) = channel();
+        let put = |body| {
+            route(
+                &Method::Put,
+                "/monitors/0/brightness",
+                body,
+                &snapshot,
+                &tx_cmd,
+            )
+        };
+
+        let reply = put(r#"{"source": "automation", "value": 95}"#);
+        assert_eq!(
+            (reply.status, reply.body.as_str()),
+            (200, r#"{"value":90}"#)
+        );
+        assert!(matches!(
+            rx_cmd.try_recv(),
+            Ok(MonitorCmd::SetBrightness(0, 90))
+        ));
+
+        assert_eq!(put(r#"{"value": -1}"#).status, 400);
+        assert_eq!(put(r#"{"brightness": 50}"#).status, 400);
+        assert_eq!(put("50").status, 400);
+        assert!(rx_cmd.try_recv().is_err());
+    }
+}
@@ -0,0 +1,1089 @@
+//! Translated UI strings.
+//!
+//! Every language is a table of `(key, text)` pairs compiled into the
+//! binary. English is the reference table; a key missing from another
+//! language falls back to its English text. The language comes from the
+//! `language` config key when it names one of ours, else the OS locale.
+
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+use eframe::egui;
+use egui::epaint::text::{FontInsert, FontPriority, InsertFontFamily};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Language {
+    En,
+    De,
+    Es,
+    Fr,
+    Ja,
+}
+
+impl Language {
+    /// Match a locale tag such as `de-DE`, `fr_CA.UTF-8` or `ja` on its
+    /// language part. `None` for languages without a table.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let lang = tag.split(['-', '_', '.', '@']).next()?;
+        match lang.to_ascii_lowercase().as_str() {
+            "en" => Some(Self::En),
+            "de" => Some(Self::De),
+            "es" => Some(Self::Es),
+            "fr" => Some(Self::Fr),
+            "ja" => Some(Self::Ja),
+            _ => None,
+        }
+    }
+
+    fn table(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::En => EN,
+            Self::De => DE,
+            Self::Es => ES,
+            Self::Fr => FR,
+            Self::Ja => JA,
+        }
+    }
+}
+
+static LANGUAGE: OnceLock<Language> = OnceLock::new();
+
+/// Choose the UI language from `configured` (the `language` config key),
+/// falling back to the OS locale and then English. Has no effect once any
+/// string has been looked up.
+pub fn init(configured: Option<&str>) {
+    let _ = LANGUAGE.set(select(configured));
+}
+
+fn select(configured: Option<&str>) -> Language {
+    if let Some(tag) = configured {
+        match Language::from_tag(tag) {
+            Some(lang) => return lang,
+            None => log::warn!("No translation for language {tag:?}, using the system language"),
+        }
+    }
+    sys_locale::get_locale()
+        .as_deref()
+        .and_then(Language::from_tag)
+        .unwrap_or(Language::En)
+}
+
+pub fn language() -> Language {
+    *LANGUAGE.get_or_init(|| select(None))
+}
+
+/// The text for `key` in the UI language. Unknown keys come back as-is so
+/// a typo shows up on screen rather than as a blank label.
+pub fn tr(key: &'static str) -> &'static str {
+    lookup(language(), key)
+}
+
+/// [`tr`] with each `{name}` placeholder replaced by its value.
+pub fn tr_args(key: &'static str, args: &[(&str, &dyn Display)]) -> String {
+    fill(tr(key), args)
+}
+
+fn lookup(lang: Language, key: &'static str) -> &'static str {
+    let find = |table: &'static [(&str, &'static str)]| {
+        table.iter().find(|(k, _)| *k == key).map(|(_, text)| *text)
+    };
+    find(lang.table()).or_else(|| find(EN)).unwrap_or(key)
+}
+
+fn fill(template: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut text = template.to_string();
+    for (name, value) in args {
+        text = text.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+    text
+}
+
+/// Fonts with Japanese glyphs that ship with each OS, in order of
+/// preference. egui's built-in fonts have none.
+const CJK_FONTS: &[&str] = &[
+    "C:\\Windows\\Fonts\\YuGothM.ttc",
+    "C:\\Windows\\Fonts\\meiryo.ttc",
+    "C:\\Windows\\Fonts\\msgothic.ttc",
+    "/System/Library/Fonts/ヒラギノ角ゴシック W3.ttc",
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+];
+
+/// Add a system font as a fallback when the UI language needs glyphs egui
+/// doesn't bundle. Without one, Japanese labels render as boxes.
+pub fn install_fonts(ctx: &egui::Context) {
+    if language() != Language::Ja {
+        return;
+    }
+    let Some(bytes) = CJK_FONTS.iter().find_map(|path| std::fs::read(path).ok()) else {
+        log::warn!("No Japanese font found; labels may not render");
+        return;
+    };
+    let families = [egui::FontFamily::Proportional, egui::FontFamily::Monospace]
+        .into_iter()
+        .map(|family| InsertFontFamily {
+            family,
+            priority: FontPriority::Lowest,
+        })
+        .collect();
+    ctx.add_font(FontInsert::new(
+        "system-cjk",
+        egui::FontData::from_owned(bytes),
+        families,
+    ));
+}
+
+const EN: &[(&str, &str)] = &[
+    ("app_name", "Tray Bright"),
+    ("primary", "Primary"),
+    ("primary_hint", "The OS's main display"),
+    ("software", "software"),
+    (
+        "software_hint",
+        "No hardware control found, so the picture is dimmed instead. \
+         The backlight itself doesn't change.",
+    ),
+    ("max_nits", "~{value} nits at 100%"),
+    (
+        "brightness_keys_permission",
+        "Brightness keys need Input Monitoring access: allow Tray Bright in System Settings > Privacy & Security, then restart it.",
+    ),
+    ("hide", "Hide"),
+    ("hide_hint", "Hide this monitor and stop controlling it"),
+    ("identify", "Identify"),
+    (
+        "identify_hint",
+        "Flash this monitor so you can tell which one it is",
+    ),
+    ("show", "Show"),
+    ("reset_to_default", "Reset to default ({value}%)"),
+    ("set_default", "Set current as default"),
+    ("double_click_reset", "Double-click to reset to {value}%"),
+    ("clear_default", "Clear default"),
+    ("apply_same_model", "Apply to all same model"),
+    ("updated_one", "Updated 1 monitor"),
+    ("updated_many", "Updated {count} monitors"),
+    ("contrast", "Contrast"),
+    ("volume", "Volume"),
+    ("color", "Color"),
+    ("undo_one", "{name} set to {value}%"),
+    ("undo_many", "{count} monitors set nearly off"),
+    ("undo", "Undo"),
+    ("theme_system", "Following system theme"),
+    ("theme_light", "Light theme"),
+    ("theme_dark", "Dark theme"),
+    ("compact_layout", "Compact layout"),
+    ("always_on_top", "Keep on top"),
+    ("brightness_steps", "Brightness steps"),
+    ("step", "Step "),
+    ("fine_step", "Fine step "),
+    ("rename", "Rename…"),
+    ("reset_name", "Reset name"),
+    ("copy_brightness_to", "Copy brightness to"),
+    ("group", "Group"),
+    ("new_group", "New group"),
+    ("ungroup", "Ungroup"),
+    ("remove_from_group", "Remove {name}"),
+    ("brightness_limits", "Brightness limits"),
+    ("limit_min", "Min "),
+    ("limit_max", "Max "),
+    ("clear_limits", "Clear limits"),
+    ("invert_slider", "Invert slider"),
+    ("sync_offset", "Linked offset "),
+    (
+        "sync_offset_hint",
+        "Added to this monitor's brightness when the All monitors slider moves it",
+    ),
+    ("profile", "Profile"),
+    ("save", "Save"),
+    ("vcp_debug", "VCP debug"),
+    (
+        "vcp_debug_warning",
+        "Advanced and read-only: raw DDC/CI data from each monitor, to paste into a bug report.",
+    ),
+    ("reading_monitors", "Reading monitors…"),
+    ("read_again", "Read again"),
+    ("capability_string", "Capability string"),
+    ("copy", "Copy"),
+    ("no_vcp_codes", "No VCP codes listed"),
+    ("vcp_read_failed", "Read failed"),
+    (
+        "brightness_unsupported",
+        "Doesn't support brightness control",
+    ),
+    ("brightness_supported", "Supports brightness control"),
+    ("cancel", "Cancel"),
+    ("settings", "Settings"),
+    ("back", "Back"),
+    ("theme", "Theme"),
+    ("poll_interval", "Poll interval"),
+    ("user_cooldown", "Manual override"),
+    (
+        "user_cooldown_hint",
+        "How long the schedule and curve wait after you change a brightness yourself",
+    ),
+    ("follow_schedule", "Follow schedule"),
+    ("follow_curve", "Follow ambient curve"),
+    ("notify_changes", "Notify of changes while hidden"),
+    (
+        "notify_changes_hint",
+        "Show a notification when a hotkey or the schedule changes brightness while the window is hidden",
+    ),
+    ("hide_on_focus_loss", "Hide when focus is lost"),
+    (
+        "hide_on_focus_loss_hint",
+        "Hide the window as soon as you click another window",
+    ),
+    (
+        "remember_per_setup",
+        "Remember brightness per monitor setup",
+    ),
+    (
+        "remember_per_setup_hint",
+        "Keep separate brightness for each set of connected monitors, like docked and undocked, and restore it when that set is connected again",
+    ),
+    ("brightness_notification", "Brightness {percent}%"),
+    ("start_on_login_failed", "Couldn't change start on login"),
+    (
+        "save_profile_hint",
+        "Save the current brightness as a profile",
+    ),
+    ("delete", "Delete"),
+    ("hidden_count", "{count} hidden"),
+    ("ddc_not_responding", "DDC/CI not responding"),
+    (
+        "ddc_contended",
+        "Another app may be controlling this display over DDC/CI",
+    ),
+    ("retrying", "Retrying…"),
+    ("retry", "Retry"),
+    (
+        "slider_keys_hint",
+        "Focus, then use ←/→ and PgUp/PgDn to step, 1–9 for 10–90% or 0 for 100%",
+    ),
+    ("ddc_help_title", "Your monitors aren't responding"),
+    (
+        "ddc_help_body",
+        "Brightness is changed over DDC/CI, which many monitors ship with \
+         turned off. Open the monitor's on-screen menu, look for DDC/CI \
+         (often under Setup, System or Other settings), turn it on, then \
+         press Test.",
+    ),
+    ("testing", "Testing…"),
+    ("test", "Test"),
+    ("dont_show_again", "Don't show again"),
+    ("responds", "responds"),
+    ("no_response", "no response"),
+    ("no_displays", "No controllable displays found"),
+    (
+        "check_ddc",
+        "Check that DDC/CI is enabled in the monitor's menu.",
+    ),
+    ("scanning", "Scanning…"),
+    ("rescan_monitors", "Rescan monitors ({shortcut})"),
+    ("discovering", "Discovering displays…"),
+    ("rescan", "Rescan"),
+    ("all_monitors", "All monitors"),
+    ("link", "🔗 Link"),
+    ("link_hint", "Drive every monitor from one slider"),
+    ("start_on_login", "Start on login"),
+    (
+        "stop_start_on_login",
+        "Stop starting Tray Bright when you log in?",
+    ),
+    ("turn_off", "Turn off"),
+    ("profiles", "Profiles"),
+    ("no_profiles", "No saved profiles"),
+    ("reset_all", "Reset all to default"),
+    ("power_off", "Turn off display"),
+    ("wake_display", "Wake display (some monitors ignore this)"),
+    ("sleep_all", "Sleep all displays"),
+    ("wake_all", "Wake all displays (some monitors ignore this)"),
+    ("quit", "Quit"),
+    ("tray_tooltip", "Tray Bright - Monitor Brightness Control"),
+    ("tray_tooltip_average", "Tray Bright — avg {value}%"),
+    ("preset_native", "Native"),
+    ("preset_user", "User {n}"),
+    ("preset_other", "Preset 0x{code}"),
+];
+
+const DE: &[(&str, &str)] = &[
+    ("primary", "Hauptbildschirm"),
+    ("primary_hint", "Der Hauptbildschirm des Systems"),
+    ("software", "Software"),
+    (
+        "software_hint",
+        "Keine Hardwaresteuerung gefunden, daher wird stattdessen das Bild \
+         abgedunkelt. Die Hintergrundbeleuchtung selbst ändert sich nicht.",
+    ),
+    ("max_nits", "~{value} cd/m² bei 100 %"),
+    (
+        "brightness_keys_permission",
+        "Helligkeitstasten brauchen Zugriff auf die Eingabeüberwachung: Tray Bright unter Systemeinstellungen > Datenschutz & Sicherheit erlauben und neu starten.",
+    ),
+    ("hide", "Ausblenden"),
+    (
+        "hide_hint",
+        "Diesen Monitor ausblenden und nicht mehr steuern",
+    ),
+    ("identify", "Erkennen"),
+    (
+        "identify_hint",
+        "Diesen Monitor aufblinken lassen, um ihn zu finden",
+    ),
+    ("show", "Anzeigen"),
+    ("reset_to_default", "Auf Standard zurücksetzen ({value} %)"),
+    ("set_default", "Aktuellen Wert als Standard setzen"),
+    (
+        "double_click_reset",
+        "Doppelklicken, um auf {value} % zurückzusetzen",
+    ),
+    ("clear_default", "Standard entfernen"),
+    (
+        "apply_same_model",
+        "Auf alle Monitore dieses Modells anwenden",
+    ),
+    ("updated_one", "1 Monitor aktualisiert"),
+    ("updated_many", "{count} Monitore aktualisiert"),
+    ("contrast", "Kontrast"),
+    ("volume", "Lautstärke"),
+    ("color", "Farbe"),
+    ("undo_one", "{name} auf {value} % gesetzt"),
+    ("undo_many", "{count} Monitore fast ausgeschaltet"),
+    ("undo", "Rückgängig"),
+    ("theme_system", "Systemdesign folgen"),
+    ("theme_light", "Helles Design"),
+    ("theme_dark", "Dunkles Design"),
+    ("compact_layout", "Kompakte Ansicht"),
+    ("always_on_top", "Im Vordergrund halten"),
+    ("brightness_steps", "Helligkeitsschritte"),
+    ("step", "Schritt "),
+    ("fine_step", "Feiner Schritt "),
+    ("rename", "Umbenennen…"),
+    ("reset_name", "Name zurücksetzen"),
+    ("copy_brightness_to", "Helligkeit kopieren nach"),
+    ("group", "Gruppe"),
+    ("new_group", "Neue Gruppe"),
+    ("ungroup", "Gruppe auflösen"),
+    ("remove_from_group", "{name} entfernen"),
+    ("brightness_limits", "Helligkeitsgrenzen"),
+    ("limit_min", "Min. "),
+    ("limit_max", "Max. "),
+    ("clear_limits", "Grenzen entfernen"),
+    ("invert_slider", "Regler umkehren"),
+    ("sync_offset", "Verknüpfter Versatz "),
+    (
+        "sync_offset_hint",
+        "Wird zur Helligkeit dieses Monitors addiert, wenn der Regler „Alle Monitore“ ihn bewegt",
+    ),
+    ("profile", "Profil"),
+    ("save", "Speichern"),
+    ("vcp_debug", "VCP-Debug"),
+    (
+        "vcp_debug_warning",
+        "Fortgeschritten, nur lesend: DDC/CI-Rohdaten jedes Monitors zum Einfügen in einen Fehlerbericht.",
+    ),
+    ("reading_monitors", "Monitore werden gelesen…"),
+    ("read_again", "Erneut lesen"),
+    ("capability_string", "Capability-String"),
+    ("copy", "Kopieren"),
+    ("no_vcp_codes", "Keine VCP-Codes angegeben"),
+    ("vcp_read_failed", "Lesen fehlgeschlagen"),
+    (
+        "brightness_unsupported",
+        "Unterstützt keine Helligkeitssteuerung",
+    ),
+    ("brightness_supported", "Unterstützt Helligkeitssteuerung"),
+    ("cancel", "Abbrechen"),
+    ("settings", "Einstellungen"),
+    ("back", "Zurück"),
+    ("theme", "Design"),
+    ("poll_interval", "Abfrageintervall"),
+    ("user_cooldown", "Manuelle Änderung"),
+    (
+        "user_cooldown_hint",
+        "Wie lange Zeitplan und Kurve nach einer eigenen Helligkeitsänderung warten",
+    ),
+    ("follow_schedule", "Zeitplan folgen"),
+    ("follow_curve", "Umgebungskurve folgen"),
+    ("notify_changes", "Änderungen im Hintergrund melden"),
+    (
+        "notify_changes_hint",
+        "Eine Benachrichtigung zeigen, wenn ein Tastenkürzel oder der Zeitplan die Helligkeit bei verborgenem Fenster ändert",
+    ),
+    ("hide_on_focus_loss", "Bei Fokusverlust ausblenden"),
+    (
+        "hide_on_focus_loss_hint",
+        "Das Fenster ausblenden, sobald ein anderes Fenster angeklickt wird",
+    ),
+    ("remember_per_setup", "Helligkeit pro Monitor-Setup merken"),
+    (
+        "remember_per_setup_hint",
+        "Für jede Kombination angeschlossener Monitore, etwa angedockt und unterwegs, eine eigene Helligkeit merken und wiederherstellen, sobald sie wieder angeschlossen ist",
+    ),
+    ("brightness_notification", "Helligkeit {percent} %"),
+    (
+        "start_on_login_failed",
+        "Start beim Anmelden konnte nicht geändert werden",
+    ),
+    (
+        "save_profile_hint",
+        "Aktuelle Helligkeit als Profil speichern",
+    ),
+    ("delete", "Löschen"),
+    ("hidden_count", "{count} ausgeblendet"),
+    ("ddc_not_responding", "DDC/CI antwortet nicht"),
+    (
+        "ddc_contended",
+        "Möglicherweise steuert eine andere App diesen Bildschirm über DDC/CI",
+    ),
+    ("retrying", "Erneuter Versuch…"),
+    ("retry", "Erneut versuchen"),
+    (
+        "slider_keys_hint",
+        "Fokussieren, dann mit ←/→ und Bild↑/Bild↓ schrittweise ändern, 1–9 für 10–90 % oder 0 für 100 %",
+    ),
+    ("ddc_help_title", "Ihre Monitore antworten nicht"),
+    (
+        "ddc_help_body",
+        "Die Helligkeit wird über DDC/CI geändert, das bei vielen Monitoren \
+         ab Werk ausgeschaltet ist. Öffnen Sie das Bildschirmmenü des \
+         Monitors, suchen Sie nach DDC/CI (oft unter Setup, System oder \
+         Sonstiges), schalten Sie es ein und klicken Sie dann auf Testen.",
+    ),
+    ("testing", "Teste…"),
+    ("test", "Testen"),
+    ("dont_show_again", "Nicht mehr anzeigen"),
+    ("responds", "antwortet"),
+    ("no_response", "keine Antwort"),
+    ("no_displays", "Keine steuerbaren Bildschirme gefunden"),
+    (
+        "check_ddc",
+        "Prüfen Sie, ob DDC/CI im Menü des Monitors aktiviert ist.",
+    ),
+    ("scanning", "Suche…"),
+    ("rescan_monitors", "Monitore erneut suchen ({shortcut})"),
+    ("discovering", "Bildschirme werden erkannt…"),
+    ("rescan", "Erneut suchen"),
+    ("all_monitors", "Alle Monitore"),
+    ("link", "🔗 Koppeln"),
+    ("link_hint", "Alle Monitore mit einem Regler steuern"),
+    ("start_on_login", "Beim Anmelden starten"),
+    (
+        "stop_start_on_login",
+        "Tray Bright nicht mehr beim Anmelden starten?",
+    ),
+    ("turn_off", "Ausschalten"),
+    ("profiles", "Profile"),
+    ("no_profiles", "Keine gespeicherten Profile"),
+    ("reset_all", "Alle auf Standard zurücksetzen"),
+    ("power_off", "Bildschirm ausschalten"),
+    (
+        "wake_display",
+        "Bildschirm aufwecken (manche Monitore ignorieren das)",
+    ),
+    ("sleep_all", "Alle Bildschirme ausschalten"),
+    (
+        "wake_all",
+        "Alle Bildschirme aufwecken (manche Monitore ignorieren das)",
+    ),
+    ("quit", "Beenden"),
+    ("tray_tooltip", "Tray Bright - Monitorhelligkeit"),
+    ("tray_tooltip_average", "Tray Bright — Ø {value} %"),
+    ("preset_native", "Nativ"),
+    ("preset_user", "Benutzer {n}"),
+    ("preset_other", "Voreinstellung 0x{code}"),
+];
+
+const ES: &[(&str, &str)] = &[
+    ("primary", "Principal"),
+    ("primary_hint", "La pantalla principal del sistema"),
+    (
+        "software_hint",
+        "No se encontró control por hardware, así que se oscurece la imagen. \
+         La retroiluminación no cambia.",
+    ),
+    ("max_nits", "~{value} nits al 100 %"),
+    (
+        "brightness_keys_permission",
+        "Las teclas de brillo necesitan acceso a Monitorización de entrada: permite Tray Bright en Ajustes del Sistema > Privacidad y seguridad y vuelve a abrirlo.",
+    ),
+    ("hide", "Ocultar"),
+    ("hide_hint", "Ocultar este monitor y dejar de controlarlo"),
+    ("identify", "Identificar"),
+    (
+        "identify_hint",
+        "Hacer parpadear este monitor para saber cuál es",
+    ),
+    ("show", "Mostrar"),
+    (
+        "reset_to_default",
+        "Restablecer al valor predeterminado ({value} %)",
+    ),
+    ("set_default", "Usar el valor actual como predeterminado"),
+    (
+        "double_click_reset",
+        "Doble clic para restablecer a {value} %",
+    ),
+    ("clear_default", "Quitar predeterminado"),
+    ("apply_same_model", "Aplicar a todos los del mismo modelo"),
+    ("updated_one", "1 monitor actualizado"),
+    ("updated_many", "{count} monitores actualizados"),
+    ("contrast", "Contraste"),
+    ("volume", "Volumen"),
+    ("color", "Color"),
+    ("undo_one", "{name} ajustado al {value} %"),
+    ("undo_many", "{count} monitores casi apagados"),
+    ("undo", "Deshacer"),
+    ("theme_system", "Seguir el tema del sistema"),
+    ("theme_light", "Tema claro"),
+    ("theme_dark", "Tema oscuro"),
+    ("compact_layout", "Vista compacta"),
+    ("always_on_top", "Mantener encima"),
+    ("brightness_steps", "Pasos de brillo"),
+    ("step", "Paso "),
+    ("fine_step", "Paso fino "),
+    ("rename", "Cambiar nombre…"),
+    ("reset_name", "Restablecer nombre"),
+    ("copy_brightness_to", "Copiar brillo a"),
+    ("group", "Grupo"),
+    ("new_group", "Nuevo grupo"),
+    ("ungroup", "Desagrupar"),
+    ("remove_from_group", "Quitar {name}"),
+    ("brightness_limits", "Límites de brillo"),
+    ("limit_min", "Mín. "),
+    ("limit_max", "Máx. "),
+    ("clear_limits", "Quitar límites"),
+    ("invert_slider", "Invertir control deslizante"),
+    ("sync_offset", "Desfase vinculado "),
+    (
+        "sync_offset_hint",
+        "Se suma al brillo de este monitor cuando lo mueve el control «Todos los monitores»",
+    ),
+    ("profile", "Perfil"),
+    ("save", "Guardar"),
+    ("vcp_debug", "Depuración VCP"),
+    (
+        "vcp_debug_warning",
+        "Avanzado y de solo lectura: datos DDC/CI sin procesar de cada monitor para pegar en un informe de error.",
+    ),
+    ("reading_monitors", "Leyendo monitores…"),
+    ("read_again", "Leer de nuevo"),
+    ("capability_string", "Cadena de capacidades"),
+    ("copy", "Copiar"),
+    ("no_vcp_codes", "No se indican códigos VCP"),
+    ("vcp_read_failed", "Error de lectura"),
+    ("brightness_unsupported", "No admite el control de brillo"),
+    ("brightness_supported", "Admite el control de brillo"),
+    ("cancel", "Cancelar"),
+    ("settings", "Ajustes"),
+    ("back", "Volver"),
+    ("theme", "Tema"),
+    ("poll_interval", "Intervalo de sondeo"),
+    ("user_cooldown", "Ajuste manual"),
+    (
+        "user_cooldown_hint",
+        "Cuánto esperan el horario y la curva después de que cambies un brillo tú mismo",
+    ),
+    ("follow_schedule", "Seguir horario"),
+    ("follow_curve", "Seguir curva ambiental"),
+    ("notify_changes", "Notificar cambios en segundo plano"),
+    (
+        "notify_changes_hint",
+        "Mostrar una notificación cuando un atajo o el horario cambian el brillo con la ventana oculta",
+    ),
+    ("hide_on_focus_loss", "Ocultar al perder el foco"),
+    (
+        "hide_on_focus_loss_hint",
+        "Ocultar la ventana en cuanto se hace clic en otra ventana",
+    ),
+    (
+        "remember_per_setup",
+        "Recordar el brillo por configuración de monitores",
+    ),
+    (
+        "remember_per_setup_hint",
+        "Guardar un brillo distinto para cada conjunto de monitores conectados, como con o sin base, y restaurarlo al volver a conectarlo",
+    ),
+    ("brightness_notification", "Brillo {percent} %"),
+    (
+        "start_on_login_failed",
+        "No se pudo cambiar el inicio de sesión",
+    ),
+    ("save_profile_hint", "Guardar el brillo actual como perfil"),
+    ("delete", "Eliminar"),
+    ("hidden_count", "{count} ocultos"),
+    ("ddc_not_responding", "DDC/CI no responde"),
+    (
+        "ddc_contended",
+        "Puede que otra aplicación esté controlando esta pantalla por DDC/CI",
+    ),
+    ("retrying", "Reintentando…"),
+    ("retry", "Reintentar"),
+    (
+        "slider_keys_hint",
+        "Con el foco, usa ←/→ y RePág/AvPág para ajustar, 1–9 para 10–90 % o 0 para 100 %",
+    ),
+    ("ddc_help_title", "Tus monitores no responden"),
+    (
+        "ddc_help_body",
+        "El brillo se cambia mediante DDC/CI, que muchos monitores traen \
+         desactivado. Abre el menú en pantalla del monitor, busca DDC/CI \
+         (a menudo en Configuración, Sistema u Otros ajustes), actívalo y \
+         pulsa Probar.",
+    ),
+    ("testing", "Probando…"),
+    ("test", "Probar"),
+    ("dont_show_again", "No volver a mostrar"),
+    ("responds", "responde"),
+    ("no_response", "sin respuesta"),
+    ("no_displays", "No se encontraron pantallas controlables"),
+    (
+        "check_ddc",
+        "Comprueba que DDC/CI esté activado en el menú del monitor.",
+    ),
+    ("scanning", "Buscando…"),
+    ("rescan_monitors", "Volver a buscar monitores ({shortcut})"),
+    ("discovering", "Detectando pantallas…"),
+    ("rescan", "Volver a buscar"),
+    ("all_monitors", "Todos los monitores"),
+    ("link", "🔗 Vincular"),
+    (
+        "link_hint",
+        "Controlar todos los monitores con un solo control",
+    ),
+    ("start_on_login", "Abrir al iniciar sesión"),
+    (
+        "stop_start_on_login",
+        "¿Dejar de abrir Tray Bright al iniciar sesión?",
+    ),
+    ("turn_off", "Desactivar"),
+    ("profiles", "Perfiles"),
+    ("no_profiles", "No hay perfiles guardados"),
+    ("reset_all", "Restablecer todo al predeterminado"),
+    ("power_off", "Apagar pantalla"),
+    (
+        "wake_display",
+        "Activar pantalla (algunos monitores lo ignoran)",
+    ),
+    ("sleep_all", "Apagar todas las pantallas"),
+    (
+        "wake_all",
+        "Activar todas las pantallas (algunos monitores lo ignoran)",
+    ),
+    ("quit", "Salir"),
+    (
+        "tray_tooltip",
+        "Tray Bright - Control de brillo del monitor",
+    ),
+    ("tray_tooltip_average", "Tray Bright — media {value} %"),
+    ("preset_native", "Nativo"),
+    ("preset_user", "Usuario {n}"),
+    ("preset_other", "Preajuste 0x{code}"),
+];
+
+const FR: &[(&str, &str)] = &[
+    ("primary", "Principal"),
+    ("primary_hint", "L'écran principal du système"),
+    ("software", "logiciel"),
+    (
+        "software_hint",
+        "Aucun contrôle matériel trouvé, l'image est donc assombrie à la \
+         place. Le rétroéclairage ne change pas.",
+    ),
+    ("max_nits", "~{value} nits à 100 %"),
+    (
+        "brightness_keys_permission",
+        "Les touches de luminosité ont besoin de l'accès à la surveillance de l'entrée : autorisez Tray Bright dans Réglages Système > Confidentialité et sécurité, puis relancez-le.",
+    ),
+    ("hide", "Masquer"),
+    ("hide_hint", "Masquer cet écran et ne plus le contrôler"),
+    ("identify", "Identifier"),
+    ("identify_hint", "Faire clignoter cet écran pour le repérer"),
+    ("show", "Afficher"),
+    (
+        "reset_to_default",
+        "Rétablir la valeur par défaut ({value} %)",
+    ),
+    ("set_default", "Définir la valeur actuelle par défaut"),
+    (
+        "double_click_reset",
+        "Double-cliquer pour revenir à {value} %",
+    ),
+    ("clear_default", "Supprimer la valeur par défaut"),
+    (
+        "apply_same_model",
+        "Appliquer à tous les écrans du même modèle",
+    ),
+    ("updated_one", "1 écran mis à jour"),
+    ("updated_many", "{count} écrans mis à jour"),
+    ("contrast", "Contraste"),
+    ("volume", "Volume"),
+    ("color", "Couleur"),
+    ("undo_one", "{name} réglé à {value} %"),
+    ("undo_many", "{count} écrans presque éteints"),
+    ("undo", "Annuler"),
+    ("theme_system", "Suivre le thème du système"),
+    ("theme_light", "Thème clair"),
+    ("theme_dark", "Thème sombre"),
+    ("compact_layout", "Affichage compact"),
+    ("always_on_top", "Garder au premier plan"),
+    ("brightness_steps", "Pas de luminosité"),
+    ("step", "Pas "),
+    ("fine_step", "Pas fin "),
+    ("rename", "Renommer…"),
+    ("reset_name", "Rétablir le nom"),
+    ("copy_brightness_to", "Copier la luminosité vers"),
+    ("group", "Groupe"),
+    ("new_group", "Nouveau groupe"),
+    ("ungroup", "Dissoudre le groupe"),
+    ("remove_from_group", "Retirer {name}"),
+    ("brightness_limits", "Limites de luminosité"),
+    ("limit_min", "Min "),
+    ("limit_max", "Max "),
+    ("clear_limits", "Supprimer les limites"),
+    ("invert_slider", "Inverser le curseur"),
+    ("sync_offset", "Décalage lié "),
+    (
+        "sync_offset_hint",
+        "Ajouté à la luminosité de cet écran quand le curseur « Tous les écrans » le déplace",
+    ),
+    ("profile", "Profil"),
+    ("save", "Enregistrer"),
+    ("vcp_debug", "Débogage VCP"),
+    (
+        "vcp_debug_warning",
+        "Avancé, en lecture seule : données DDC/CI brutes de chaque écran, à coller dans un rapport de bug.",
+    ),
+    ("reading_monitors", "Lecture des écrans…"),
+    ("read_again", "Relire"),
+    ("capability_string", "Chaîne de capacités"),
+    ("copy", "Copier"),
+    ("no_vcp_codes", "Aucun code VCP indiqué"),
+    ("vcp_read_failed", "Échec de la lecture"),
+    (
+        "brightness_unsupported",
+        "Ne prend pas en charge le réglage de la luminosité",
+    ),
+    (
+        "brightness_supported",
+        "Prend en charge le réglage de la luminosité",
+    ),
+    ("cancel", "Annuler"),
+    ("settings", "Paramètres"),
+    ("back", "Retour"),
+    ("theme", "Thème"),
+    ("poll_interval", "Intervalle d'interrogation"),
+    ("user_cooldown", "Réglage manuel"),
+    (
+        "user_cooldown_hint",
+        "Durée pendant laquelle le planning et la courbe attendent après un réglage manuel",
+    ),
+    ("follow_schedule", "Suivre le planning"),
+    ("follow_curve", "Suivre la courbe ambiante"),
+    ("notify_changes", "Notifier les changements en arrière-plan"),
+    (
+        "notify_changes_hint",
+        "Afficher une notification quand un raccourci ou la planification change la luminosité alors que la fenêtre est masquée",
+    ),
+    ("hide_on_focus_loss", "Masquer à la perte du focus"),
+    (
+        "hide_on_focus_loss_hint",
+        "Masquer la fenêtre dès qu'une autre fenêtre est cliquée",
+    ),
+    (
+        "remember_per_setup",
+        "Mémoriser la luminosité par configuration d'écrans",
+    ),
+    (
+        "remember_per_setup_hint",
+        "Garder une luminosité distincte pour chaque ensemble d'écrans connectés, comme avec ou sans station d'accueil, et la rétablir quand il est reconnecté",
+    ),
+    ("brightness_notification", "Luminosité {percent} %"),
+    (
+        "start_on_login_failed",
+        "Impossible de modifier le lancement à l'ouverture de session",
+    ),
+    (
+        "save_profile_hint",
+        "Enregistrer la luminosité actuelle comme profil",
+    ),
+    ("delete", "Supprimer"),
+    ("hidden_count", "{count} masqués"),
+    ("ddc_not_responding", "DDC/CI ne répond pas"),
+    (
+        "ddc_contended",
+        "Une autre application contrôle peut-être cet écran via DDC/CI",
+    ),
+    ("retrying", "Nouvelle tentative…"),
+    ("retry", "Réessayer"),
+    (
+        "slider_keys_hint",
+        "Après sélection, ←/→ et Pg préc./Pg suiv. ajustent, 1–9 pour 10–90 % ou 0 pour 100 %",
+    ),
+    ("ddc_help_title", "Vos écrans ne répondent pas"),
+    (
+        "ddc_help_body",
+        "La luminosité est réglée via DDC/CI, que beaucoup d'écrans \
+         désactivent d'usine. Ouvrez le menu à l'écran du moniteur, cherchez \
+         DDC/CI (souvent dans Configuration, Système ou Autres réglages), \
+         activez-le, puis appuyez sur Tester.",
+    ),
+    ("testing", "Test en cours…"),
+    ("test", "Tester"),
+    ("dont_show_again", "Ne plus afficher"),
+    ("responds", "répond"),
+    ("no_response", "aucune réponse"),
+    ("no_displays", "Aucun écran contrôlable trouvé"),
+    (
+        "check_ddc",
+        "Vérifiez que DDC/CI est activé dans le menu de l'écran.",
+    ),
+    ("scanning", "Recherche…"),
+    (
+        "rescan_monitors",
+        "Rechercher à nouveau les moniteurs ({shortcut})",
+    ),
+    ("discovering", "Détection des écrans…"),
+    ("rescan", "Relancer la recherche"),
+    ("all_monitors", "Tous les écrans"),
+    ("link", "🔗 Lier"),
+    ("link_hint", "Régler tous les écrans avec un seul curseur"),
+    ("start_on_login", "Lancer à l'ouverture de session"),
+    (
+        "stop_start_on_login",
+        "Ne plus lancer Tray Bright à l'ouverture de session ?",
+    ),
+    ("turn_off", "Désactiver"),
+    ("profiles", "Profils"),
+    ("no_profiles", "Aucun profil enregistré"),
+    ("reset_all", "Tout rétablir par défaut"),
+    ("power_off", "Éteindre l'écran"),
+    (
+        "wake_display",
+        "Réveiller l'écran (certains moniteurs l'ignorent)",
+    ),
+    ("sleep_all", "Éteindre tous les écrans"),
+    (
+        "wake_all",
+        "Réveiller tous les écrans (certains moniteurs l'ignorent)",
+    ),
+    ("quit", "Quitter"),
+    ("tray_tooltip", "Tray Bright - Luminosité des écrans"),
+    ("tray_tooltip_average", "Tray Bright — moy. {value} %"),
+    ("preset_native", "Natif"),
+    ("preset_user", "Utilisateur {n}"),
+    ("preset_other", "Préréglage 0x{code}"),
+];
+
+const JA: &[(&str, &str)] = &[
+    ("primary", "メイン"),
+    ("primary_hint", "OS のメインディスプレイ"),
+    ("software", "ソフトウェア"),
+    (
+        "software_hint",
+        "ハードウェア制御が見つからないため、代わりに画面を暗くしています。\
+         バックライト自体は変わりません。",
+    ),
+    ("max_nits", "100% で約 {value} nits"),
+    (
+        "brightness_keys_permission",
+        "明るさキーには入力監視の許可が必要です。システム設定 > プライバシーとセキュリティで Tray Bright を許可してから再起動してください。",
+    ),
+    ("hide", "非表示"),
+    ("hide_hint", "このモニターを非表示にして制御を停止します"),
+    ("identify", "識別"),
+    ("identify_hint", "このモニターを点滅させてどれか確認します"),
+    ("show", "表示"),
+    ("reset_to_default", "既定値に戻す ({value}%)"),
+    ("set_default", "現在の値を既定値にする"),
+    ("double_click_reset", "ダブルクリックで {value}% に戻す"),
+    ("clear_default", "既定値を消去"),
+    ("apply_same_model", "同じモデルすべてに適用"),
+    ("updated_one", "1 台のモニターを更新しました"),
+    ("updated_many", "{count} 台のモニターを更新しました"),
+    ("contrast", "コントラスト"),
+    ("volume", "音量"),
+    ("color", "色"),
+    ("undo_one", "{name} を {value}% にしました"),
+    ("undo_many", "{count} 台のモニターをほぼオフにしました"),
+    ("undo", "元に戻す"),
+    ("theme_system", "システムのテーマに従う"),
+    ("theme_light", "ライトテーマ"),
+    ("theme_dark", "ダークテーマ"),
+    ("compact_layout", "コンパクト表示"),
+    ("always_on_top", "常に手前に表示"),
+    ("brightness_steps", "明るさの刻み"),
+    ("step", "刻み "),
+    ("fine_step", "細かい刻み "),
+    ("rename", "名前を変更…"),
+    ("reset_name", "名前をリセット"),
+    ("copy_brightness_to", "明るさをコピー"),
+    ("group", "グループ"),
+    ("new_group", "新しいグループ"),
+    ("ungroup", "グループを解除"),
+    ("remove_from_group", "{name} を外す"),
+    ("brightness_limits", "明るさの制限"),
+    ("limit_min", "最小 "),
+    ("limit_max", "最大 "),
+    ("clear_limits", "制限を解除"),
+    ("invert_slider", "スライダーを反転"),
+    ("sync_offset", "連動オフセット "),
+    (
+        "sync_offset_hint",
+        "「すべてのモニター」スライダーで動かすとき、このモニターの明るさに加算されます",
+    ),
+    ("profile", "プロファイル"),
+    ("save", "保存"),
+    ("vcp_debug", "VCP デバッグ"),
+    (
+        "vcp_debug_warning",
+        "上級者向け・読み取り専用：各モニターの DDC/CI 生データ。バグ報告に貼り付けてください。",
+    ),
+    ("reading_monitors", "モニターを読み取り中…"),
+    ("read_again", "再読み取り"),
+    ("capability_string", "機能文字列"),
+    ("copy", "コピー"),
+    ("no_vcp_codes", "VCP コードがありません"),
+    ("vcp_read_failed", "読み取り失敗"),
+    ("brightness_unsupported", "明るさの調整に対応していません"),
+    ("brightness_supported", "明るさの調整に対応しています"),
+    ("cancel", "キャンセル"),
+    ("settings", "設定"),
+    ("back", "戻る"),
+    ("theme", "テーマ"),
+    ("poll_interval", "ポーリング間隔"),
+    ("user_cooldown", "手動変更"),
+    (
+        "user_cooldown_hint",
+        "手動で明るさを変えた後、スケジュールとカーブが待つ時間",
+    ),
+    ("follow_schedule", "スケジュールに従う"),
+    ("follow_curve", "環境光カーブに従う"),
+    ("notify_changes", "非表示中の変更を通知"),
+    (
+        "notify_changes_hint",
+        "ウィンドウが非表示のときにショートカットまたはスケジュールで明るさが変わったら通知を表示",
+    ),
+    ("hide_on_focus_loss", "フォーカスを失ったら隠す"),
+    (
+        "hide_on_focus_loss_hint",
+        "別のウィンドウをクリックするとすぐにウィンドウを隠す",
+    ),
+    ("remember_per_setup", "モニター構成ごとに明るさを記憶"),
+    (
+        "remember_per_setup_hint",
+        "ドッキング時と単体時など、接続されたモニターの組み合わせごとに明るさを記憶し、再接続時に復元します",
+    ),
+    ("brightness_notification", "明るさ {percent}%"),
+    (
+        "start_on_login_failed",
+        "ログイン時の起動を変更できませんでした",
+    ),
+    ("save_profile_hint", "現在の明るさをプロファイルとして保存"),
+    ("delete", "削除"),
+    ("hidden_count", "{count} 台を非表示"),
+    ("ddc_not_responding", "DDC/CI が応答しません"),
+    (
+        "ddc_contended",
+        "別のアプリが DDC/CI でこのディスプレイを制御している可能性があります",
+    ),
+    ("retrying", "再試行中…"),
+    ("retry", "再試行"),
+    (
+        "slider_keys_hint",
+        "フォーカス後、←/→ と PgUp/PgDn で調整、1〜9 で 10〜90%、0 で 100%",
+    ),
+    ("ddc_help_title", "モニターが応答しません"),
+    (
+        "ddc_help_body",
+        "明るさは DDC/CI で変更しますが、多くのモニターでは出荷時にオフに\
+         なっています。モニターのオンスクリーンメニューで DDC/CI（セット\
+         アップ、システム、その他の設定などにあります）をオンにしてから、\
+         「テスト」を押してください。",
+    ),
+    ("testing", "テスト中…"),
+    ("test", "テスト"),
+    ("dont_show_again", "今後表示しない"),
+    ("responds", "応答あり"),
+    ("no_response", "応答なし"),
+    ("no_displays", "制御できるディスプレイが見つかりません"),
+    (
+        "check_ddc",
+        "モニターのメニューで DDC/CI が有効か確認してください。",
+    ),
+    ("scanning", "スキャン中…"),
+    ("rescan_monitors", "モニターを再スキャン ({shortcut})"),
+    ("discovering", "ディスプレイを検出中…"),
+    ("rescan", "再スキャン"),
+    ("all_monitors", "すべてのモニター"),
+    ("link", "🔗 連動"),
+    ("link_hint", "1 つのスライダーですべてのモニターを操作"),
+    ("start_on_login", "ログイン時に起動"),
+    (
+        "stop_start_on_login",
+        "ログイン時に Tray Bright を起動しないようにしますか？",
+    ),
+    ("turn_off", "オフにする"),
+    ("profiles", "プロファイル"),
+    ("no_profiles", "保存済みのプロファイルはありません"),
+    ("reset_all", "すべて既定値に戻す"),
+    ("power_off", "画面をオフにする"),
+    (
+        "wake_display",
+        "画面をオンにする（無視するモニターもあります）",
+    ),
+    ("sleep_all", "すべての画面をオフにする"),
+    (
+        "wake_all",
+        "すべての画面をオンにする（無視するモニターもあります）",
+    ),
+    ("quit", "終了"),
+    ("tray_tooltip", "Tray Bright - モニターの明るさ調整"),
+    ("tray_tooltip_average", "Tray Bright — 平均 {value}%"),
+    ("preset_native", "ネイティブ"),
+    ("preset_user", "ユーザー {n}"),
+    ("preset_other", "プリセット 0x{code}"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locale_tags_match_on_language() {
+        assert_eq!(Language::from_tag("de_DE.UTF-8"), Some(Language::De));
+        assert_eq!(Language::from_tag("fr-CA"), Some(Language::Fr));
+        assert_eq!(Language::from_tag("JA"), Some(Language::Ja));
+        assert_eq!(Language::from_tag("pt-BR"), None);
+        assert_eq!(Language::from_tag(""), None);
+    }
+
+    #[test]
+    fn every_translated_key_exists_in_english() {
+        for lang in [Language::De, Language::Es, Language::Fr, Language::Ja] {
+            for (key, _) in lang.table() {
+                assert!(
+                    EN.iter().any(|(k, _)| k == key),
+                    "{lang:?} has unknown key {key}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn missing_keys_fall_back_to_english() {
+        assert_eq!(lookup(Language::De, "app_name"), "Tray Bright");
+        assert_eq!(lookup(Language::Fr, "quit"), "Quitter");
+        assert_eq!(lookup(Language::Ja, "no_such_key"), "no_such_key");
+        assert_eq!(
+            fill(
+                "{name} set to {value}%",
+                &[("name", &"DELL"), ("value", &40)]
+            ),
+            "DELL set to 40%"
+        );
+    }
+}
@@ -0,0 +1,20 @@
+//! stderr logging for the `log` macros, through `env_logger`.
+//!
+//! Only warnings and errors are printed by default. `--verbose` adds this
+//! crate's debug messages (FPS counter, DDC/CI retries), and `RUST_LOG`
+//! directives apply on top of both, e.g. `RUST_LOG=debug` or
+//! `RUST_LOG=warn,tray_bright=trace`.
+
+use log::LevelFilter;
+
+/// Install the logger. Call once, before anything logs.
+pub fn init(verbose: bool) {
+    let mut builder = env_logger::Builder::new();
+    builder.filter_level(LevelFilter::Warn);
+    if verbose {
+        builder.filter_module(env!("CARGO_CRATE_NAME"), LevelFilter::Debug);
+    }
+    // Parsed last so a directive for the same target replaces the above
+    builder.parse_default_env();
+    let _ = builder.try_init();
+}
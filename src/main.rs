@@ -1,68 +1,57 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use std::net::SocketAddr;
+use std::process::ExitCode;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+use clap::Parser;
 use eframe::egui;
 use raw_window_handle::HasWindowHandle;
-use tray_icon::menu::{Menu, MenuEvent, MenuItem};
-use tray_icon::{Icon, MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
-
-use crate::os::{PlatformWindow, WindowController};
-use crate::ui::{TrayBrightUI, get_app_options, load_icon_rgba};
 
+use crate::os::{
+    MonitorProvider, PlatformPowerEvents, PlatformWindow, PowerEvents, WindowController,
+};
+use crate::sim::SimMonitorProvider;
+use crate::ui::{TrayBrightUI, get_app_options};
+
+mod ambient;
+mod battery;
+mod cli;
+mod config;
+#[cfg(target_os = "linux")]
+mod dbus;
+mod edid;
+mod fullscreen;
+mod history;
+mod hotkeys;
+mod http;
+mod i18n;
+mod logging;
+mod monitors;
 mod os;
 mod platform;
+mod schedule;
+mod sim;
+mod tray;
 mod ui;
+mod vcp;
 
 static WINDOW: Mutex<Option<PlatformWindow>> = Mutex::new(None);
 static VISIBLE: Mutex<Option<Arc<AtomicBool>>> = Mutex::new(None);
 static EGUI_CTX: Mutex<Option<egui::Context>> = Mutex::new(None);
-
-fn create_tray_icon() -> tray_icon::TrayIcon {
-    let (rgba, width, height) = load_icon_rgba();
-    let icon = Icon::from_rgba(rgba, width, height).expect("Failed to create tray icon");
-
-    // Create context menu
-    let menu = Menu::new();
-    let open_item = MenuItem::with_id("open", "Open App", true, None);
-    let quit_item = MenuItem::with_id("quit", "Quit", true, None);
-    menu.append(&open_item).unwrap();
-    menu.append(&quit_item).unwrap();
-
-    TrayIconBuilder::new()
-        .with_menu(Box::new(menu))
-        .with_menu_on_left_click(false) // Only show menu on right-click
-        .with_tooltip("Tray Bright - Monitor Brightness Control")
-        .with_icon(icon)
-        .build()
-        .unwrap()
-}
-
-fn setup_event_handlers() {
-    // Handle tray icon click events
-    TrayIconEvent::set_event_handler(Some(move |event: TrayIconEvent| {
-        if let TrayIconEvent::Click {
-            button: MouseButton::Left,
-            button_state: MouseButtonState::Up,
-            ..
-        } = event
-        {
-            toggle_window_visibility();
-        }
-    }));
-
-    // Handle menu events
-    MenuEvent::set_event_handler(Some(move |event: MenuEvent| match event.id.0.as_str() {
-        "open" => {
-            show_window();
-        }
-        "quit" => {
-            // Exit immediately - can't rely on event loop when window is hidden
-            std::process::exit(0);
-        }
-        _ => {}
-    }));
+/// Where the tray icon was last clicked, in physical pixels, so the window
+/// opens next to it.
+static TRAY_ANCHOR: Mutex<Option<egui::Rect>> = Mutex::new(None);
+/// When the window last hid itself on losing focus. Clicking the tray icon
+/// takes focus before the click arrives, so that click would otherwise show
+/// the window straight back.
+static FOCUS_HIDDEN_AT: Mutex<Option<Instant>> = Mutex::new(None);
+const REOPEN_GRACE: Duration = Duration::from_millis(300);
+
+pub fn set_tray_anchor(anchor: egui::Rect) {
+    *TRAY_ANCHOR.lock().unwrap() = Some(anchor);
 }
 
 fn set_visible(val: bool) {
@@ -71,14 +60,23 @@ fn set_visible(val: bool) {
     }
     // When becoming visible, wake the egui event loop immediately
     // so the UI renders without waiting for the next scheduled repaint.
-    if val {
-        if let Some(ref ctx) = *EGUI_CTX.lock().unwrap() {
-            ctx.request_repaint();
+    if val && let Some(ref ctx) = *EGUI_CTX.lock().unwrap() {
+        if let Some(anchor) = *TRAY_ANCHOR.lock().unwrap() {
+            ui::move_near_tray(ctx, anchor);
         }
+        ctx.request_repaint();
     }
 }
 
 fn toggle_window_visibility() {
+    let just_hidden = FOCUS_HIDDEN_AT
+        .lock()
+        .unwrap()
+        .take()
+        .is_some_and(|at| at.elapsed() < REOPEN_GRACE);
+    if just_hidden {
+        return;
+    }
     if let Some(ref ctrl) = *WINDOW.lock().unwrap() {
         ctrl.toggle();
         set_visible(ctrl.is_visible());
@@ -99,20 +97,85 @@ pub fn hide_window() {
     }
 }
 
-fn main() -> eframe::Result {
-    // Create tray icon (must be kept alive)
-    let _tray_icon = create_tray_icon();
+/// Hide after another window took focus, so a tray click that caused it
+/// doesn't reopen the window.
+pub fn hide_window_on_focus_loss() {
+    *FOCUS_HIDDEN_AT.lock().unwrap() = Some(Instant::now());
+    hide_window();
+}
+
+fn main() -> ExitCode {
+    // Any subcommand runs headless and exits without touching the GUI
+    let args = cli::Cli::parse();
+    logging::init(args.verbose);
+    if let Some(command) = args.command {
+        return cli::run(command, args.json);
+    }
+
+    let serve = args
+        .serve
+        .map(|port| SocketAddr::new(args.serve_bind, port));
+    match run_tray_app(serve, args.dbus, args.hidden, args.debug, args.simulate) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            log::error!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run_tray_app(
+    serve: Option<SocketAddr>,
+    dbus: bool,
+    hidden: bool,
+    debug: bool,
+    simulate: Option<Option<usize>>,
+) -> eframe::Result {
+    let app = match simulate {
+        Some(count) => TrayBrightUI::with_provider(move || {
+            count.map_or_else(SimMonitorProvider::new, SimMonitorProvider::with_count)
+        }),
+        None => TrayBrightUI::new(),
+    };
+    // Reported through main's logging rather than a panic; `tray-bright
+    // list` is the way to dig into what enumeration found
+    let mut app = app.map_err(|e| {
+        eframe::Error::AppCreation(format!("Failed to initialize app: {e:#}").into())
+    })?;
+    if debug {
+        app.show_debug_button();
+    }
+
+    let averages = app.take_average_receiver();
+    tray::create_tray_icon(&app.monitor_names(), &app.profile_names(), averages);
 
     // Set up event handlers
-    setup_event_handlers();
+    tray::setup_event_handlers(app.command_sender(), app.tray_request_sender());
 
-    let app = TrayBrightUI::new().expect("Failed to initialize app");
     *VISIBLE.lock().unwrap() = Some(app.visible_flag());
-    let monitor_count = app.monitor_count();
+    hotkeys::set_step(app.step_percent());
+    hotkeys::register(app.command_sender());
+    if app.brightness_keys() && !hotkeys::watch_brightness_keys(app.command_sender()) {
+        app.set_keys_permission_missing();
+    }
+    let tx_power = app.command_sender();
+    PlatformPowerEvents::watch(Box::new(move || {
+        let _ = tx_power.send(ui::MonitorCmd::PowerChanged);
+    }));
+    if let Some(addr) = serve {
+        http::serve(addr, app.snapshot(), app.command_sender());
+    }
+    if dbus {
+        #[cfg(target_os = "linux")]
+        dbus::serve(app.snapshot(), app.command_sender());
+        #[cfg(not(target_os = "linux"))]
+        log::warn!("--dbus is only supported on Linux");
+    }
+    let preferred_height = app.preferred_height();
 
     eframe::run_native(
         "Tray Bright",
-        get_app_options(monitor_count),
+        get_app_options(preferred_height, hidden),
         Box::new(|cc| {
             // Get the native window handle
             let raw_handle = cc
@@ -120,7 +183,7 @@ fn main() -> eframe::Result {
                 .expect("Failed to get window handle")
                 .as_raw();
 
-            let ctrl = PlatformWindow::from_raw_handle(raw_handle)
+            let ctrl = PlatformWindow::from_raw_handle(raw_handle, &cc.egui_ctx)
                 .expect("Unsupported platform window handle");
 
             // Hide window immediately (tray-first app)
@@ -129,6 +192,7 @@ fn main() -> eframe::Result {
 
             // Store egui context for immediate repaint on show
             *EGUI_CTX.lock().unwrap() = Some(cc.egui_ctx.clone());
+            i18n::install_fonts(&cc.egui_ctx);
 
             Ok(Box::new(app))
         }),
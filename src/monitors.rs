@@ -0,0 +1,973 @@
+//! Platform-independent brightness helpers shared by the worker, the global
+//! hotkeys and the platform backends.
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use crate::config::{BrightnessScale, OnExit};
+use crate::os::MonitorHandle;
+use crate::vcp;
+
+/// Delays before each retry of a failed DDC/CI read. Some monitors
+/// intermittently NAK a request and answer fine moments later.
+const RETRY_DELAYS: [Duration; 3] = [
+    Duration::from_millis(50),
+    Duration::from_millis(100),
+    Duration::from_millis(200),
+];
+
+/// Shortest gap between two brightness writes to the same monitor.
+/// DDC/CI monitors drop or queue requests that arrive faster than this.
+pub const WRITE_INTERVAL: Duration = Duration::from_millis(80);
+
+/// How far (percent of the monitor's range) a read-back may be from the
+/// value written before the write counts as ignored. Monitors with a
+/// coarse raw range round.
+const VERIFY_TOLERANCE: u32 = 2;
+
+/// Percents of its range an identify flash alternates a monitor between.
+const IDENTIFY_LEVELS: [u32; 2] = [20, 80];
+
+/// Dim-bright cycles in an identify flash.
+const IDENTIFY_FLASHES: usize = 3;
+
+/// How long each level of an identify flash is shown.
+const IDENTIFY_STEP: Duration = Duration::from_millis(250);
+
+/// How long a monitor must have been gone before it counts as newly
+/// connected again, so a flaky cable doesn't keep re-applying its preset.
+const RECONNECT_GRACE: Duration = Duration::from_secs(10);
+
+/// Exponent of [`BrightnessScale::Perceptual`], the usual display gamma.
+const PERCEPTUAL_GAMMA: f64 = 2.2;
+
+/// Failed reads or writes in a row before a monitor that has answered
+/// before is suspected of having its DDC/CI bus held by another app.
+const CONTENTION_FAILURES: u32 = 3;
+
+/// Output level, as a fraction of full, that software brightness bottoms
+/// out at. A gamma ramp can take the screen to black, which would leave
+/// the user unable to find the slider to undo it.
+#[cfg_attr(target_os = "macos", allow(dead_code))]
+const SOFTWARE_FLOOR: f32 = 0.2;
+
+/// Rate-limits brightness writes per monitor, whatever the source — slider,
+/// tray, hotkeys, schedule. A value arriving within [`WRITE_INTERVAL`] of
+/// the last write is held back and replaced by anything newer, so only the
+/// latest one is written once the interval has passed.
+pub struct WriteLimiter {
+    last_write: Vec<Option<Instant>>,
+    held: Vec<Option<u32>>,
+}
+
+impl WriteLimiter {
+    pub fn new(monitor_count: usize) -> Self {
+        Self {
+            last_write: vec![None; monitor_count],
+            held: vec![None; monitor_count],
+        }
+    }
+
+    /// Fold `queued` into the held values and return the ones due for
+    /// writing at `now`.
+    pub fn take_due(&mut self, queued: &[Option<u32>], now: Instant) -> Vec<Option<u32>> {
+        for (held, &value) in self.held.iter_mut().zip(queued) {
+            if value.is_some() {
+                *held = value;
+            }
+        }
+
+        self.held
+            .iter_mut()
+            .zip(self.last_write.iter_mut())
+            .map(|(held, last_write)| {
+                let ready = last_write.is_none_or(|t| now.duration_since(t) >= WRITE_INTERVAL);
+                if held.is_some() && ready {
+                    *last_write = Some(now);
+                    held.take()
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// How long until the next held value is due, or `None` if nothing is
+    /// held.
+    pub fn next_due(&self, now: Instant) -> Option<Duration> {
+        self.held
+            .iter()
+            .zip(&self.last_write)
+            .filter(|(held, _)| held.is_some())
+            .map(|(_, last_write)| {
+                last_write.map_or(Duration::ZERO, |t| {
+                    WRITE_INTERVAL.saturating_sub(now.duration_since(t))
+                })
+            })
+            .min()
+    }
+
+    /// Drop any value held for monitor `idx`.
+    pub fn clear(&mut self, idx: usize) {
+        self.held[idx] = None;
+    }
+}
+
+/// Run `f`, retrying after each of [`RETRY_DELAYS`] if it fails. Only the
+/// error from the final attempt is returned.
+pub fn retry_with_backoff<T>(mut f: impl FnMut() -> anyhow::Result<T>) -> anyhow::Result<T> {
+    for delay in RETRY_DELAYS {
+        if let Ok(value) = f() {
+            return Ok(value);
+        }
+        std::thread::sleep(delay);
+    }
+    f()
+}
+
+/// Spots monitors appearing between enumerations, for the config's
+/// on-connect presets. Works on monitor ids since indices shift whenever
+/// the list changes.
+pub struct ConnectWatcher {
+    present: Vec<String>,
+    gone_at: HashMap<String, Instant>,
+}
+
+impl ConnectWatcher {
+    /// Start from the monitors found at launch, which don't count as
+    /// connecting.
+    pub fn new(ids: Vec<String>) -> Self {
+        Self {
+            present: ids,
+            gone_at: HashMap::new(),
+        }
+    }
+
+    /// Record the monitors now connected (`ids`, in list order) and return
+    /// `(index, percent)` for each new one with a preset in `presets`.
+    /// Monitors back within [`RECONNECT_GRACE`] of dropping out are skipped.
+    pub fn update(
+        &mut self,
+        ids: Vec<String>,
+        presets: &BTreeMap<String, u32>,
+        now: Instant,
+    ) -> Vec<(usize, u32)> {
+        for id in &self.present {
+            if !ids.contains(id) {
+                self.gone_at.insert(id.clone(), now);
+            }
+        }
+        self.gone_at
+            .retain(|_, gone| now.duration_since(*gone) < RECONNECT_GRACE);
+
+        let connected = ids
+            .iter()
+            .enumerate()
+            .filter(|(_, id)| !self.present.contains(id) && !self.gone_at.contains_key(*id))
+            .filter_map(|(idx, id)| presets.get(id).map(|&percent| (idx, percent)))
+            .collect();
+        self.present = ids;
+        connected
+    }
+}
+
+/// Spots monitors whose DDC/CI reads and writes keep failing after they
+/// worked, the usual sign of another brightness tool or a GPU control
+/// panel holding the bus. A monitor that never answered more likely has
+/// DDC/CI switched off, which the DDC/CI help already covers.
+pub struct ContentionTracker {
+    answered: Vec<bool>,
+    failures: Vec<u32>,
+    flagged: Vec<bool>,
+}
+
+impl ContentionTracker {
+    pub fn new(monitor_count: usize) -> Self {
+        Self {
+            answered: vec![false; monitor_count],
+            failures: vec![0; monitor_count],
+            flagged: vec![false; monitor_count],
+        }
+    }
+
+    /// Record whether a read or write of monitor `idx` went through.
+    /// Returns the monitor's new state when it starts or stops looking
+    /// contended.
+    pub fn record(&mut self, idx: usize, ok: bool) -> Option<bool> {
+        if ok {
+            self.answered[idx] = true;
+            self.failures[idx] = 0;
+        } else {
+            self.failures[idx] += 1;
+        }
+        let flagged = self.answered[idx] && self.failures[idx] >= CONTENTION_FAILURES;
+        (flagged != std::mem::replace(&mut self.flagged[idx], flagged)).then_some(flagged)
+    }
+}
+
+/// Set `mon`'s brightness and read it back, writing once more if the
+/// monitor ignored the first write (some do while busy). `value` is in the
+/// monitor's own units, as is the brightness it reports afterwards, which
+/// is returned.
+pub fn set_verified<M: MonitorHandle>(mon: &mut M, value: u32) -> anyhow::Result<u32> {
+    let mut actual = value;
+    for attempt in 0..2 {
+        if attempt > 0 {
+            log::debug!(
+                "{} reads {actual} after writing {value}, retrying",
+                mon.name()
+            );
+        }
+        mon.set_brightness(value)?;
+        mon.confirm_brightness()?;
+        std::thread::sleep(WRITE_INTERVAL);
+        let (read, min, max) = mon.poll_brightness()?;
+        actual = read;
+        // Scaled up rather than converted, so rounding can't hide a miss
+        if actual.abs_diff(value) * 100 <= VERIFY_TOLERANCE * max.saturating_sub(min) {
+            break;
+        }
+    }
+    Ok(actual)
+}
+
+/// VCP feature code for brightness.
+const VCP_BRIGHTNESS: u8 = 0x10;
+
+/// Whether `mon` honours brightness, for backends to probe once per DDC/CI
+/// monitor when it's found. `features` are the codes its capability string
+/// lists, if it's been read. Only a monitor whose capabilities leave 0x10
+/// out, or that answers a read of it with no range, counts as unsupported;
+/// one that doesn't answer at all may just be slow, and keeps its retry.
+pub fn probe_brightness<M: MonitorHandle>(mon: &mut M, features: &[u8]) -> bool {
+    (features.is_empty() || features.contains(&VCP_BRIGHTNESS))
+        && !matches!(mon.get_vcp(VCP_BRIGHTNESS), Ok((_, 0)))
+}
+
+/// Check that `mon` takes DDC/CI writes by nudging its brightness one
+/// step and putting it back. Fails if the monitor can't be read or refuses
+/// either write, which usually means DDC/CI is off in its on-screen menu.
+pub fn test_ddc<M: MonitorHandle>(mon: &mut M) -> anyhow::Result<()> {
+    let (current, min, max) = retry_with_backoff(|| mon.poll_brightness())?;
+    let nudged = if current < max {
+        current + 1
+    } else {
+        current.saturating_sub(1).max(min)
+    };
+    mon.set_brightness(nudged)?;
+    mon.confirm_brightness()?;
+    std::thread::sleep(WRITE_INTERVAL);
+    mon.set_brightness(current)?;
+    mon.confirm_brightness()
+}
+
+/// One monitor's raw DDC/CI data as shown in the debug panel. Errors are
+/// kept as text since they're only ever displayed.
+#[derive(Debug, Clone)]
+pub struct VcpDump {
+    pub name: String,
+    /// See [`MonitorHandle::supports_brightness`].
+    pub supports_brightness: bool,
+    pub capabilities: Result<String, String>,
+    /// Every code the capability string lists, in its order.
+    pub features: Vec<VcpReading>,
+}
+
+/// A VCP code from the capability string and what the monitor answered.
+#[derive(Debug, Clone)]
+pub struct VcpReading {
+    pub code: u8,
+    /// Values listed for the code; empty for continuous features.
+    pub values: Vec<u8>,
+    /// `(current, max)` as reported.
+    pub reply: Result<(u16, u16), String>,
+}
+
+/// Read `mon`'s capability string and the current value of every code it
+/// lists. Only reads, never writes. Takes a DDC/CI round trip per code.
+pub fn dump_vcp<M: MonitorHandle>(mon: &mut M) -> VcpDump {
+    let capabilities = mon.capabilities().map_err(|e| e.to_string());
+    let features = capabilities
+        .as_deref()
+        .map(vcp::parse_vcp_features)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(code, values)| VcpReading {
+            code,
+            values,
+            reply: mon.get_vcp(code).map_err(|e| e.to_string()),
+        })
+        .collect();
+
+    VcpDump {
+        name: mon.name().to_string(),
+        supports_brightness: mon.supports_brightness(),
+        capabilities,
+        features,
+    }
+}
+
+/// Flash `mon` between dim and bright a few times (~1.5s) so the user can
+/// tell which physical screen it is, then put `current` back. `level` is
+/// its last known `(current, min, max)`. The restore is written even if a
+/// flash write fails.
+pub fn identify<M: MonitorHandle>(mon: &mut M, level: (u32, u32, u32)) -> anyhow::Result<()> {
+    let (current, min, max) = level;
+    let mut flashed = Ok(());
+    'flash: for _ in 0..IDENTIFY_FLASHES {
+        for percent in IDENTIFY_LEVELS {
+            flashed = mon
+                .set_brightness(percent_of_range(min, max, percent))
+                .and_then(|()| mon.confirm_brightness());
+            if flashed.is_err() {
+                break 'flash;
+            }
+            std::thread::sleep(IDENTIFY_STEP);
+        }
+    }
+    let restored = mon
+        .set_brightness(current)
+        .and_then(|()| mon.confirm_brightness());
+    flashed.and(restored)
+}
+
+/// Apply the user's `on_exit` choice to `mon` as the app quits. `range`
+/// is the part of its `min..=max` range the user allows, and the default
+/// is a slider position on `scale` like any other.
+pub fn apply_on_exit<M: MonitorHandle>(
+    mon: &mut M,
+    on_exit: OnExit,
+    scale: BrightnessScale,
+    (min, max): (u32, u32),
+    range: (u32, u32),
+) -> anyhow::Result<()> {
+    match on_exit {
+        OnExit::Leave => Ok(()),
+        OnExit::RestoreDefault(percent) => {
+            let position = percent_of_range(range.0, range.1, percent);
+            mon.set_brightness(to_hardware(scale, position, min, max))?;
+            mon.confirm_brightness()
+        }
+    }
+}
+
+/// Move the primary monitor to the front, keeping everything else in the
+/// order it was found.
+pub fn primary_first<T>(monitors: &mut [T], is_primary: impl Fn(&T) -> bool) {
+    monitors.sort_by_key(|mon| !is_primary(mon));
+}
+
+/// Raise `current` by `percent` of the monitor's `min..=max` range, or of
+/// 0–100 when that range is empty or inverted (see [`step_range`]).
+pub fn increase_brightness(current: u32, min: u32, max: u32, percent: u32) -> u32 {
+    let (min, max) = step_range(min, max);
+    current
+        .saturating_add(step_size(min, max, percent))
+        .clamp(min, max)
+}
+
+/// Lower `current` by `percent` of the monitor's `min..=max` range, or of
+/// 0–100 when that range is empty or inverted (see [`step_range`]).
+pub fn decrease_brightness(current: u32, min: u32, max: u32, percent: u32) -> u32 {
+    let (min, max) = step_range(min, max);
+    current
+        .saturating_sub(step_size(min, max, percent))
+        .clamp(min, max)
+}
+
+/// Brightness for a monitor following the master slider at `master` with
+/// `offset` percent added, kept inside its `(min, max)` range.
+pub fn offset_brightness(master: u32, offset: i32, (min, max): (u32, u32)) -> u32 {
+    master.saturating_add_signed(offset).clamp(min, max)
+}
+
+/// Absolute brightness at `percent` of the monitor's `min..=max` range,
+/// rounded to the nearest raw value.
+pub fn percent_of_range(min: u32, max: u32, percent: u32) -> u32 {
+    let range = max.saturating_sub(min) as u64;
+    let offset = (range * percent.min(100) as u64 + 50) / 100;
+    min + offset as u32
+}
+
+/// Inverse of [`percent_of_range`]: where `raw` sits within `min..=max`,
+/// as a rounded 0–100 percentage. A degenerate range reads as 0%.
+pub fn range_to_percent(raw: u32, min: u32, max: u32) -> u32 {
+    let range = max.saturating_sub(min) as u64;
+    if range == 0 {
+        return 0;
+    }
+    let offset = raw.clamp(min, max).saturating_sub(min) as u64;
+    ((offset * 100 + range / 2) / range) as u32
+}
+
+/// Monitor brightness for slider `position`, both within `min..=max`. On
+/// the perceptual scale the position's share of the range is raised to
+/// [`PERCEPTUAL_GAMMA`], which gives the dim end finer steps.
+pub fn to_hardware(scale: BrightnessScale, position: u32, min: u32, max: u32) -> u32 {
+    match scale {
+        BrightnessScale::Linear => position,
+        BrightnessScale::Perceptual => curve_range(position, min, max, PERCEPTUAL_GAMMA),
+    }
+}
+
+/// Slider position for monitor brightness `value`, the inverse of
+/// [`to_hardware`]. The perceptual scale has fewer monitor values than
+/// positions at the dim end, so the slider stays at `current` while that
+/// still maps to `value` rather than jumping after every read.
+pub fn from_hardware(
+    scale: BrightnessScale,
+    value: u32,
+    current: Option<u32>,
+    min: u32,
+    max: u32,
+) -> u32 {
+    match scale {
+        BrightnessScale::Linear => value,
+        BrightnessScale::Perceptual => current
+            .filter(|&current| to_hardware(scale, current, min, max) == value)
+            .unwrap_or_else(|| curve_range(value, min, max, PERCEPTUAL_GAMMA.recip())),
+    }
+}
+
+/// `value`'s share of `min..=max` raised to `exponent`, back in the range.
+/// A degenerate range leaves `value` alone.
+fn curve_range(value: u32, min: u32, max: u32, exponent: f64) -> u32 {
+    if max <= min {
+        return value;
+    }
+    let span = f64::from(max - min);
+    let share = f64::from(value.clamp(min, max) - min) / span;
+    min + (share.powf(exponent) * span).round() as u32
+}
+
+/// The part of a monitor's `min..=max` range the user allows with their
+/// soft `limits`, which are pulled inside the hardware range. A lower limit
+/// above the upper one collapses onto it. When the hardware range isn't
+/// known (`min == max`, as for a monitor that couldn't be read) the limits
+/// are used as they are.
+pub fn limit_range(min: u32, max: u32, limits: Option<(u32, u32)>) -> (u32, u32) {
+    match limits {
+        None => (min, max),
+        Some((low, high)) if min == max => (low.min(high), high),
+        Some((low, high)) => {
+            let high = high.clamp(min, max);
+            (low.clamp(min, high), high)
+        }
+    }
+}
+
+/// Gamma ramp of `size` entries that dims a screen to `percent`
+/// brightness, for monitors dimmed in software. The ramp stays linear and
+/// 0% maps to [`SOFTWARE_FLOOR`] rather than black.
+#[cfg_attr(target_os = "macos", allow(dead_code))]
+pub fn gamma_ramp(size: usize, percent: u32) -> Vec<u16> {
+    let scale = SOFTWARE_FLOOR + (1.0 - SOFTWARE_FLOOR) * percent.min(100) as f32 / 100.0;
+    let last = size.saturating_sub(1).max(1) as f32;
+    (0..size)
+        .map(|i| (i as f32 / last * scale * f32::from(u16::MAX)).round() as u16)
+        .collect()
+}
+
+/// Inverse of [`gamma_ramp`]: the brightness a ramp ending in `top` was
+/// made for. Ramps brighter than any [`gamma_ramp`] read as 100%.
+#[cfg_attr(target_os = "macos", allow(dead_code))]
+pub fn gamma_percent(top: u16) -> u32 {
+    let scale = f32::from(top) / f32::from(u16::MAX);
+    let percent = (scale - SOFTWARE_FLOOR) / (1.0 - SOFTWARE_FLOOR) * 100.0;
+    percent.round().clamp(0.0, 100.0) as u32
+}
+
+/// Brightness presets for a monitor with the given `min..=max` range, each
+/// clamped into it. Neighbours that clamp to the same value collapse into
+/// one so the UI doesn't show duplicate buttons.
+pub fn clamp_presets(presets: &[u32], min: u32, max: u32) -> Vec<u32> {
+    let mut clamped: Vec<u32> = presets.iter().map(|&p| p.clamp(min, max)).collect();
+    clamped.dedup();
+    clamped
+}
+
+/// The range to step within. Some broken monitors report `max == min`, or
+/// even `max < min`, which would leave nothing to step through; those are
+/// stepped as 0–100 like a standard MCCS brightness control instead.
+fn step_range(min: u32, max: u32) -> (u32, u32) {
+    if max > min { (min, max) } else { (0, 100) }
+}
+
+/// One `percent` step of the range. Never zero, so monitors with a narrow
+/// range still move on every press, and never more than the whole range.
+fn step_size(min: u32, max: u32, percent: u32) -> u32 {
+    let range = max.saturating_sub(min) as u64;
+    (range * percent.min(100) as u64 / 100).max(1) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fails the first `failures` brightness reads, then reports 42.
+    /// Brightness writes are recorded in `writes`.
+    struct FlakyMonitor {
+        failures: u32,
+        calls: u32,
+        writes: Vec<u32>,
+    }
+
+    impl MonitorHandle for FlakyMonitor {
+        fn name(&self) -> &str {
+            "Flaky"
+        }
+
+        fn id(&self) -> String {
+            "flaky".to_string()
+        }
+
+        fn poll_brightness(&mut self) -> anyhow::Result<(u32, u32, u32)> {
+            self.calls += 1;
+            if self.calls <= self.failures {
+                anyhow::bail!("NAK");
+            }
+            Ok((42, 0, 100))
+        }
+
+        fn set_brightness(&mut self, value: u32) -> anyhow::Result<()> {
+            self.writes.push(value);
+            Ok(())
+        }
+
+        fn poll_contrast(&mut self) -> anyhow::Result<(u32, u32, u32)> {
+            anyhow::bail!("unsupported")
+        }
+
+        fn set_contrast(&mut self, _value: u32) -> anyhow::Result<()> {
+            anyhow::bail!("unsupported")
+        }
+
+        fn list_color_presets(&mut self) -> Vec<(u8, String)> {
+            Vec::new()
+        }
+
+        fn poll_color_preset(&mut self) -> anyhow::Result<u8> {
+            anyhow::bail!("unsupported")
+        }
+
+        fn set_color_preset(&mut self, _value: u8) -> anyhow::Result<()> {
+            anyhow::bail!("unsupported")
+        }
+
+        fn supports_brightness(&self) -> bool {
+            true
+        }
+
+        fn has_volume(&self) -> bool {
+            false
+        }
+
+        fn poll_volume(&mut self) -> anyhow::Result<(u32, u32, u32)> {
+            anyhow::bail!("unsupported")
+        }
+
+        fn set_volume(&mut self, _value: u32) -> anyhow::Result<()> {
+            anyhow::bail!("unsupported")
+        }
+        fn get_vcp(&mut self, _code: u8) -> anyhow::Result<(u16, u16)> {
+            anyhow::bail!("unsupported")
+        }
+        fn set_vcp(&mut self, _code: u8, _value: u16) -> anyhow::Result<()> {
+            anyhow::bail!("unsupported")
+        }
+        fn set_power_mode(&mut self, _mode: u8) -> anyhow::Result<()> {
+            anyhow::bail!("unsupported")
+        }
+        fn capabilities(&mut self) -> anyhow::Result<String> {
+            Ok("(prot(monitor)vcp(10 14(05 08))mccs_ver(2.1))".to_string())
+        }
+        fn is_software(&self) -> bool {
+            false
+        }
+
+        fn is_primary(&self) -> bool {
+            false
+        }
+        fn max_luminance(&self) -> Option<u32> {
+            None
+        }
+        fn display_mode(&self) -> Option<crate::os::DisplayMode> {
+            None
+        }
+    }
+
+    #[test]
+    fn retry_returns_eventual_value() {
+        let mut mon = FlakyMonitor {
+            failures: 2,
+            calls: 0,
+            writes: Vec::new(),
+        };
+        let result = retry_with_backoff(|| mon.poll_brightness());
+        assert_eq!(result.unwrap(), (42, 0, 100));
+        assert_eq!(mon.calls, 3);
+    }
+
+    #[test]
+    fn retry_gives_up_after_last_delay() {
+        let mut mon = FlakyMonitor {
+            failures: u32::MAX,
+            calls: 0,
+            writes: Vec::new(),
+        };
+        assert!(retry_with_backoff(|| mon.poll_brightness()).is_err());
+        assert_eq!(mon.calls, RETRY_DELAYS.len() as u32 + 1);
+    }
+
+    #[test]
+    fn brightness_probe_only_rules_out_what_the_monitor_says() {
+        let mut mon = FlakyMonitor {
+            failures: 0,
+            calls: 0,
+            writes: Vec::new(),
+        };
+        // A failed read isn't proof, so it keeps the benefit of the doubt
+        assert!(probe_brightness(&mut mon, &[]));
+        assert!(probe_brightness(&mut mon, &[0x10, 0x14]));
+        assert!(!probe_brightness(&mut mon, &[0x14, 0x60]));
+    }
+
+    #[test]
+    fn ddc_test_nudges_and_restores() {
+        let mut mon = FlakyMonitor {
+            failures: 0,
+            calls: 0,
+            writes: Vec::new(),
+        };
+        assert!(test_ddc(&mut mon).is_ok());
+        assert_eq!(mon.writes, vec![43, 42]);
+
+        let mut silent = FlakyMonitor {
+            failures: u32::MAX,
+            calls: 0,
+            writes: Vec::new(),
+        };
+        assert!(test_ddc(&mut silent).is_err());
+        assert!(silent.writes.is_empty());
+    }
+
+    #[test]
+    fn identify_flashes_then_restores() {
+        let mut mon = FlakyMonitor {
+            failures: 0,
+            calls: 0,
+            writes: Vec::new(),
+        };
+        assert!(identify(&mut mon, (42, 0, 100)).is_ok());
+        assert_eq!(mon.writes, vec![20, 80, 20, 80, 20, 80, 42]);
+    }
+
+    #[test]
+    fn on_exit_restores_default_within_range() {
+        let mut mon = FlakyMonitor {
+            failures: 0,
+            calls: 0,
+            writes: Vec::new(),
+        };
+        let linear = BrightnessScale::Linear;
+        assert!(apply_on_exit(&mut mon, OnExit::Leave, linear, (0, 100), (0, 100)).is_ok());
+        assert!(mon.writes.is_empty());
+        let restore = OnExit::RestoreDefault(50);
+        assert!(apply_on_exit(&mut mon, restore, linear, (0, 100), (20, 80)).is_ok());
+        assert_eq!(mon.writes, vec![50]);
+
+        // The default is a slider position, so it's written through the curve
+        let perceptual = BrightnessScale::Perceptual;
+        assert!(apply_on_exit(&mut mon, restore, perceptual, (0, 100), (20, 80)).is_ok());
+        assert_eq!(mon.writes[1], to_hardware(perceptual, 50, 0, 100));
+        assert!(mon.writes[1] < 50);
+    }
+
+    #[test]
+    fn verified_write_retries_once_when_ignored() {
+        let mut mon = FlakyMonitor {
+            failures: 0,
+            calls: 0,
+            writes: Vec::new(),
+        };
+        assert_eq!(set_verified(&mut mon, 43).unwrap(), 42);
+        assert_eq!(mon.writes, vec![43]);
+
+        mon.writes.clear();
+        assert_eq!(set_verified(&mut mon, 80).unwrap(), 42);
+        assert_eq!(mon.writes, vec![80, 80]);
+    }
+
+    #[test]
+    fn gamma_ramp_round_trips_and_never_goes_black() {
+        let full = gamma_ramp(256, 100);
+        assert_eq!(full[0], 0);
+        assert_eq!(full[255], u16::MAX);
+
+        let dark = gamma_ramp(256, 0);
+        assert!(dark[255] > 0);
+
+        for percent in [0, 1, 37, 50, 99, 100] {
+            let ramp = gamma_ramp(256, percent);
+            assert_eq!(gamma_percent(ramp[255]), percent);
+        }
+    }
+
+    #[test]
+    fn primary_moves_first_and_others_keep_their_order() {
+        let mut monitors = [("a", false), ("b", false), ("c", true), ("d", false)];
+        primary_first(&mut monitors, |&(_, primary)| primary);
+        let names: Vec<&str> = monitors.iter().map(|&(name, _)| name).collect();
+        assert_eq!(names, ["c", "a", "b", "d"]);
+    }
+
+    #[test]
+    fn connect_presets_apply_once_per_connection() {
+        let ids = |list: &[&str]| list.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        let presets = BTreeMap::from([("desk".to_string(), 70)]);
+        let start = Instant::now();
+        let mut watcher = ConnectWatcher::new(ids(&["laptop"]));
+
+        // Plugged in: applied at its new index. Monitors without a preset
+        // are left alone.
+        let connected = watcher.update(ids(&["laptop", "tv", "desk"]), &presets, start);
+        assert_eq!(connected, vec![(2, 70)]);
+        assert!(
+            watcher
+                .update(ids(&["laptop", "tv", "desk"]), &presets, start)
+                .is_empty()
+        );
+
+        // A brief dropout doesn't count as reconnecting
+        let blip = start + Duration::from_secs(1);
+        assert!(watcher.update(ids(&["laptop"]), &presets, blip).is_empty());
+        let back = blip + Duration::from_secs(2);
+        assert!(
+            watcher
+                .update(ids(&["laptop", "desk"]), &presets, back)
+                .is_empty()
+        );
+
+        // Unplugged for longer, it does
+        let unplugged = back + Duration::from_secs(1);
+        assert!(
+            watcher
+                .update(ids(&["laptop"]), &presets, unplugged)
+                .is_empty()
+        );
+        let later = unplugged + RECONNECT_GRACE;
+        assert_eq!(
+            watcher.update(ids(&["desk", "laptop"]), &presets, later),
+            vec![(0, 70)]
+        );
+    }
+
+    #[test]
+    fn steps_clamp_to_range() {
+        assert_eq!(increase_brightness(95, 0, 100, 10), 100);
+        assert_eq!(decrease_brightness(5, 0, 100, 10), 0);
+        // A narrow range still moves by at least one unit
+        assert_eq!(increase_brightness(1, 0, 5, 10), 2);
+    }
+
+    #[test]
+    fn steps_stay_in_range_at_the_edges() {
+        // Already at an edge: stepping outwards stays put
+        assert_eq!(increase_brightness(100, 0, 100, 10), 100);
+        assert_eq!(decrease_brightness(20, 20, 80, 10), 20);
+        // Steps bigger than the range stop at the edge
+        assert_eq!(increase_brightness(30, 20, 80, 250), 80);
+        assert_eq!(decrease_brightness(30, 20, 80, 250), 20);
+        // Raw ranges near u32::MAX don't overflow
+        assert_eq!(increase_brightness(u32::MAX - 1, 0, u32::MAX, 50), u32::MAX);
+        assert_eq!(decrease_brightness(1, 0, u32::MAX, 50), 0);
+    }
+
+    #[test]
+    fn degenerate_ranges_step_as_percent() {
+        // max == min: stepped through 0-100 rather than stuck
+        assert_eq!(increase_brightness(50, 50, 50, 10), 60);
+        assert_eq!(decrease_brightness(50, 50, 50, 10), 40);
+        // max < min doesn't underflow or leave 0-100
+        assert_eq!(increase_brightness(95, 80, 20, 10), 100);
+        assert_eq!(decrease_brightness(5, 80, 20, 10), 0);
+        assert_eq!(increase_brightness(300, 80, 20, 10), 100);
+    }
+
+    #[test]
+    fn percent_maps_onto_range() {
+        assert_eq!(percent_of_range(0, 100, 50), 50);
+        assert_eq!(percent_of_range(20, 120, 25), 45);
+        assert_eq!(percent_of_range(0, 100, 150), 100);
+    }
+
+    /// Every percentage survives a trip through the raw range and back.
+    fn assert_round_trips(min: u32, max: u32) {
+        for percent in 0..=100 {
+            let raw = percent_of_range(min, max, percent);
+            assert!((min..=max).contains(&raw), "{raw} outside {min}..={max}");
+            assert_eq!(range_to_percent(raw, min, max), percent, "{min}..={max}");
+        }
+    }
+
+    #[test]
+    fn normalizes_0_to_100() {
+        assert_eq!(range_to_percent(0, 0, 100), 0);
+        assert_eq!(range_to_percent(42, 0, 100), 42);
+        assert_eq!(range_to_percent(100, 0, 100), 100);
+        assert_round_trips(0, 100);
+    }
+
+    #[test]
+    fn normalizes_0_to_255() {
+        assert_eq!(range_to_percent(0, 0, 255), 0);
+        assert_eq!(range_to_percent(128, 0, 255), 50);
+        assert_eq!(range_to_percent(255, 0, 255), 100);
+        assert_round_trips(0, 255);
+    }
+
+    #[test]
+    fn normalizes_20_to_80() {
+        assert_eq!(range_to_percent(20, 20, 80), 0);
+        assert_eq!(range_to_percent(50, 20, 80), 50);
+        assert_eq!(range_to_percent(80, 20, 80), 100);
+        // Out-of-range reads clamp instead of wrapping
+        assert_eq!(range_to_percent(10, 20, 80), 0);
+        assert_eq!(range_to_percent(90, 20, 80), 100);
+    }
+
+    #[test]
+    fn perceptual_scale_spreads_out_the_dim_end() {
+        let scale = BrightnessScale::Perceptual;
+        assert_eq!(to_hardware(scale, 0, 0, 100), 0);
+        assert_eq!(to_hardware(scale, 50, 0, 100), 22);
+        assert_eq!(to_hardware(scale, 100, 0, 100), 100);
+        assert_eq!(to_hardware(scale, 60, 20, 80), 45);
+        assert_eq!(from_hardware(scale, 22, None, 0, 100), 50);
+        assert_eq!(from_hardware(scale, 45, None, 20, 80), 60);
+        // Linear passes values through
+        assert_eq!(to_hardware(BrightnessScale::Linear, 37, 0, 255), 37);
+        assert_eq!(from_hardware(BrightnessScale::Linear, 37, None, 0, 255), 37);
+    }
+
+    #[test]
+    fn reads_keep_a_position_that_still_matches() {
+        let scale = BrightnessScale::Perceptual;
+        // Positions 9 and 10 both land on 1; a read of 1 leaves either be
+        assert_eq!(to_hardware(scale, 9, 0, 100), 1);
+        assert_eq!(to_hardware(scale, 10, 0, 100), 1);
+        assert_eq!(from_hardware(scale, 1, Some(10), 0, 100), 10);
+        assert_eq!(from_hardware(scale, 1, Some(9), 0, 100), 9);
+        // Something else changed the monitor
+        assert_eq!(from_hardware(scale, 22, Some(10), 0, 100), 50);
+        // Without a position to keep, a read still lands on one that
+        // writes the same value back
+        for position in 0..=100 {
+            let value = to_hardware(scale, position, 0, 100);
+            let read = from_hardware(scale, value, None, 0, 100);
+            assert_eq!(to_hardware(scale, read, 0, 100), value);
+        }
+    }
+
+    #[test]
+    fn limits_stay_inside_hardware_range() {
+        assert_eq!(limit_range(0, 100, None), (0, 100));
+        assert_eq!(limit_range(0, 100, Some((30, 80))), (30, 80));
+        assert_eq!(limit_range(10, 90, Some((0, 100))), (10, 90));
+        assert_eq!(limit_range(0, 100, Some((70, 40))), (40, 40));
+        assert_eq!(limit_range(0, 50, Some((60, 80))), (50, 50));
+        assert_eq!(limit_range(0, 0, Some((30, 80))), (30, 80));
+    }
+
+    #[test]
+    fn degenerate_range_reads_as_zero() {
+        assert_eq!(range_to_percent(50, 50, 50), 0);
+    }
+
+    #[test]
+    fn rapid_writes_collapse_to_a_few() {
+        let mut limiter = WriteLimiter::new(2);
+        let start = Instant::now();
+        let mut written = Vec::new();
+
+        // 50 slider steps 5ms apart for monitor 0
+        for i in 0..50u32 {
+            let now = start + Duration::from_millis(5 * i as u64);
+            let due = limiter.take_due(&[Some(i), None], now);
+            assert_eq!(due[1], None);
+            written.extend(due[0]);
+        }
+        assert!(written.len() <= 4, "{written:?}");
+
+        // The last value still lands once the interval has passed
+        let later = start + Duration::from_millis(250) + WRITE_INTERVAL;
+        written.extend(limiter.take_due(&[None, None], later)[0]);
+        assert_eq!(written.first(), Some(&0));
+        assert_eq!(written.last(), Some(&49));
+        assert_eq!(limiter.next_due(later), None);
+    }
+
+    #[test]
+    fn limiter_is_per_monitor() {
+        let mut limiter = WriteLimiter::new(2);
+        let now = Instant::now();
+        assert_eq!(limiter.take_due(&[Some(10), None], now), [Some(10), None]);
+        // Monitor 0 is throttled, monitor 1 isn't
+        assert_eq!(
+            limiter.take_due(&[Some(20), Some(30)], now),
+            [None, Some(30)]
+        );
+        assert_eq!(limiter.next_due(now), Some(WRITE_INTERVAL));
+    }
+
+    #[test]
+    fn contention_needs_failures_after_success() {
+        let mut tracker = ContentionTracker::new(2);
+        // Never answered: DDC/CI is off, not contended
+        for _ in 0..5 {
+            assert_eq!(tracker.record(0, false), None);
+        }
+        assert_eq!(tracker.record(1, true), None);
+        assert_eq!(tracker.record(1, false), None);
+        assert_eq!(tracker.record(1, false), None);
+        assert_eq!(tracker.record(1, false), Some(true));
+        assert_eq!(tracker.record(1, false), None);
+        // One success clears it
+        assert_eq!(tracker.record(1, true), Some(false));
+        assert_eq!(tracker.record(1, false), None);
+    }
+
+    #[test]
+    fn presets_clamp_to_monitor_range() {
+        assert_eq!(clamp_presets(&[25, 50, 75, 100], 0, 100), [25, 50, 75, 100]);
+        assert_eq!(clamp_presets(&[25, 50, 75, 100], 0, 80), [25, 50, 75, 80]);
+        assert_eq!(clamp_presets(&[25, 50, 90, 100], 30, 80), [30, 50, 80]);
+    }
+
+    #[test]
+    fn vcp_dump_reads_every_listed_code_without_writing() {
+        let mut mon = FlakyMonitor {
+            failures: 0,
+            calls: 0,
+            writes: Vec::new(),
+        };
+        let dump = dump_vcp(&mut mon);
+        let codes: Vec<(u8, Vec<u8>)> = dump
+            .features
+            .iter()
+            .map(|f| (f.code, f.values.clone()))
+            .collect();
+        assert_eq!(codes, [(0x10, vec![]), (0x14, vec![0x05, 0x08])]);
+        assert!(dump.features.iter().all(|f| f.reply.is_err()));
+        assert!(mon.writes.is_empty());
+    }
+
+    #[test]
+    fn offsets_stop_at_the_range_edges() {
+        assert_eq!(offset_brightness(50, 10, (0, 100)), 60);
+        assert_eq!(offset_brightness(95, 10, (0, 100)), 100);
+        assert_eq!(offset_brightness(5, -10, (0, 100)), 0);
+        assert_eq!(offset_brightness(50, -20, (40, 80)), 40);
+        assert_eq!(offset_brightness(70, 20, (40, 80)), 80);
+    }
+}
@@ -1,10 +1,225 @@
 //! Platform abstraction layer for window visibility control.
 //!
-//! This module provides a cross-platform interface for showing/hiding
-//! the application window from the system tray.
+//! This module provides a cross-platform interface for discovering and
+//! controlling monitors, showing/hiding the application window from the
+//! system tray, and registering the app to launch at login.
 
+use eframe::egui;
 use raw_window_handle::RawWindowHandle;
 
+// ---------------------------------------------------------------------------
+// Monitor control abstraction
+// ---------------------------------------------------------------------------
+
+/// A single controllable display.
+///
+/// Continuous values are returned as `(current, min, max)`. Brightness is
+/// normalised to 0-100; the other features use the monitor's native
+/// range. Optional DDC features return an error (or an
+/// empty list) when the monitor doesn't support them so the UI can hide
+/// the corresponding control.
+pub trait MonitorHandle: Send {
+    fn name(&self) -> &str;
+
+    /// Identifier that stays the same across reconnects and reboots, for
+    /// settings that must follow a monitor even when several share a name.
+    /// Derived from the EDID (see [`crate::edid::stable_id`]) where the
+    /// platform exposes it, falling back to the connector, then the name.
+    fn id(&self) -> String;
+
+    /// False for DDC/CI displays that don't honour brightness (VCP 0x10),
+    /// such as TVs and capture devices, as found by
+    /// [`crate::monitors::probe_brightness`] during enumeration. The panel
+    /// shows them without a slider and they're never written to.
+    fn supports_brightness(&self) -> bool;
+
+    /// Brightness as `(current, min, max)`, the order every caller
+    /// destructures it in. Win32's `GetMonitorBrightness` fills
+    /// `(min, current, max)`, so the Windows backend reorders it.
+    fn poll_brightness(&mut self) -> anyhow::Result<(u32, u32, u32)>;
+
+    /// Backends that queue writes (Windows DDC/CI monitors each have a
+    /// writer thread) return once the value is queued, before it lands, so
+    /// `Ok` here is unconfirmed until [`Self::confirm_brightness`] says so.
+    fn set_brightness(&mut self, value: u32) -> anyhow::Result<()>;
+
+    /// Wait for the last [`Self::set_brightness`] to land and return how it
+    /// went. Only backends that queue writes have anything to wait for.
+    fn confirm_brightness(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Contrast via VCP 0x12, as `(current, min, max)` like brightness.
+    fn poll_contrast(&mut self) -> anyhow::Result<(u32, u32, u32)>;
+    fn set_contrast(&mut self, value: u32) -> anyhow::Result<()>;
+
+    /// Colour presets (VCP 0x14) listed in the monitor's capability string.
+    fn list_color_presets(&mut self) -> Vec<(u8, String)>;
+    fn poll_color_preset(&mut self) -> anyhow::Result<u8>;
+    fn set_color_preset(&mut self, preset: u8) -> anyhow::Result<()>;
+
+    /// Speaker volume via VCP 0x62, as `(current, min, max)`. Support is
+    /// probed once during enumeration so monitors without speakers aren't
+    /// queried again.
+    fn has_volume(&self) -> bool;
+    fn poll_volume(&mut self) -> anyhow::Result<(u32, u32, u32)>;
+    fn set_volume(&mut self, value: u32) -> anyhow::Result<()>;
+
+    /// Raw access to any VCP feature, for codes without a dedicated
+    /// control. Returns `(current, max)` as reported by the monitor;
+    /// non-continuous features may report a max of 0.
+    fn get_vcp(&mut self, code: u8) -> anyhow::Result<(u16, u16)>;
+    fn set_vcp(&mut self, code: u8, value: u16) -> anyhow::Result<()>;
+
+    /// Display power mode via VCP 0xD6, such as [`crate::vcp::POWER_OFF`]
+    /// to put the monitor to sleep or [`crate::vcp::POWER_ON`] to wake it.
+    /// Some monitors stop listening to DDC/CI while asleep and only wake on
+    /// input, so a successful wake write doesn't mean the screen came on.
+    fn set_power_mode(&mut self, mode: u8) -> anyhow::Result<()>;
+
+    /// The raw MCCS capability string, unparsed, for the debug panel and
+    /// bug reports. Fails for monitors without DDC/CI.
+    fn capabilities(&mut self) -> anyhow::Result<String>;
+
+    /// True when brightness is faked by dimming the picture (gamma ramp)
+    /// because the monitor offers no hardware control. The UI labels these
+    /// so users know the backlight itself isn't changing.
+    fn is_software(&self) -> bool;
+
+    /// True for the display the OS treats as primary (where the taskbar or
+    /// menu bar lives by default). The panel lists it first.
+    fn is_primary(&self) -> bool;
+
+    /// Peak luminance in nits from the EDID's HDR metadata (see
+    /// [`crate::edid::max_luminance`]), for HDR displays that state it.
+    /// Informational only.
+    fn max_luminance(&self) -> Option<u32>;
+
+    /// Current resolution and refresh rate, read at enumeration.
+    /// Informational only.
+    fn display_mode(&self) -> Option<DisplayMode>;
+}
+
+/// A monitor's active resolution and refresh rate, shown next to its name.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayMode {
+    pub width: u32,
+    pub height: u32,
+    /// `None` where the platform doesn't report it.
+    pub refresh_hz: Option<f32>,
+}
+
+impl std::fmt::Display for DisplayMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}×{}", self.width, self.height)?;
+        if let Some(hz) = self.refresh_hz {
+            // 59.94 stays fractional, but 143.998 is just 144
+            let hz = (hz * 100.0).round() / 100.0;
+            if (hz - hz.round()).abs() < 0.01 {
+                write!(f, " @ {} Hz", hz.round())?;
+            } else {
+                write!(f, " @ {hz} Hz")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Error for a monitor that can't be controlled until the user installs or
+/// enables something, such as ddcutil on Linux. The UI shows the message in
+/// place of the usual "not responding" row, without offering a retry.
+#[derive(Debug)]
+pub struct SetupRequired(pub &'static str);
+
+impl std::fmt::Display for SetupRequired {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl std::error::Error for SetupRequired {}
+
+/// Discovers the monitors available on this platform and releases any
+/// native resources they hold.
+pub trait MonitorProvider: Send + 'static {
+    type Monitor: MonitorHandle;
+
+    fn new() -> Self
+    where
+        Self: Sized;
+
+    fn get_monitors(&mut self) -> anyhow::Result<Vec<Self::Monitor>>;
+
+    /// Release native handles. Called once when the worker shuts down.
+    fn cleanup_monitors(&mut self, monitors: &mut Vec<Self::Monitor>);
+}
+
+/// A light sensor used for ambient auto-brightness.
+///
+/// Created on the worker thread that reads it, so implementations don't
+/// need to be `Send` (the Windows sensor is a COM object).
+pub trait AmbientSensor {
+    /// Find the machine's ambient light sensor, or `None` if it has none.
+    fn open() -> Option<Self>
+    where
+        Self: Sized;
+
+    /// Current illuminance in lux, or `None` if the read failed.
+    fn read_lux(&mut self) -> Option<f32>;
+}
+
+/// Tells whether a fullscreen app is in front, for the fullscreen
+/// brightness rule.
+pub trait FullscreenDetector {
+    /// A detector for this platform, or `None` where it can't tell.
+    fn open() -> Option<Self>
+    where
+        Self: Sized;
+
+    /// Whether the foreground window covers a whole monitor.
+    fn is_fullscreen(&mut self) -> bool;
+}
+
+/// The laptop battery, for capping brightness as it drains.
+pub trait Battery {
+    /// The machine's battery, or `None` if it has none (or it can't be
+    /// read here).
+    fn open() -> Option<Self>
+    where
+        Self: Sized;
+
+    /// Charge left in percent while running on battery. `None` on mains
+    /// power, or if the read failed.
+    fn charge_on_battery(&mut self) -> Option<u32>;
+}
+
+/// Reports when the machine wakes from sleep or switches between battery and
+/// mains power, after which some monitors forget their DDC brightness.
+pub trait PowerEvents {
+    /// Call `on_event` for every such event from now on, on whichever thread
+    /// the platform delivers them. Platforms without a listener ignore it.
+    fn watch(on_event: Box<dyn Fn() + Send + Sync>);
+}
+
+/// The keyboard's dedicated brightness keys, which macOS only applies to
+/// the built-in display.
+pub trait BrightnessKeys {
+    /// Call `on_key` with `true` for brightness up and `false` for down on
+    /// every press from now on, on whichever thread the platform delivers
+    /// them. The keys keep their usual effect. Fails with [`SetupRequired`]
+    /// until the user grants the permission the OS asks for, and with a
+    /// plain error on platforms that don't support it.
+    fn watch(on_key: Box<dyn Fn(bool) + Send + Sync>) -> Result<(), anyhow::Error>;
+}
+
+/// Brief desktop notifications, for feedback on changes made while the
+/// window is hidden.
+pub trait Notifier {
+    /// Pop up `message`, replacing the last notification if it's still
+    /// showing. Platforms without notifications ignore it.
+    fn notify(message: &str);
+}
+
 // ---------------------------------------------------------------------------
 // Window visibility abstraction
 // ---------------------------------------------------------------------------
@@ -16,7 +231,9 @@ use raw_window_handle::RawWindowHandle;
 pub trait WindowController: Send + Sync + 'static {
     /// Attempt to initialise from the raw handle provided by eframe.
     /// Returns `None` if the handle variant doesn't match this platform.
-    fn from_raw_handle(handle: RawWindowHandle) -> Option<Self>
+    /// `ctx` is for platforms that can only change visibility through
+    /// eframe's viewport commands.
+    fn from_raw_handle(handle: RawWindowHandle, ctx: &egui::Context) -> Option<Self>
     where
         Self: Sized;
 
@@ -29,16 +246,109 @@ pub trait WindowController: Send + Sync + 'static {
     fn set_visible(&self, visible: bool);
 }
 
+// ---------------------------------------------------------------------------
+// Launch-at-login abstraction
+// ---------------------------------------------------------------------------
+
+/// Argument login entries launch the app with, so the window starts hidden
+/// instead of flashing up before the tray takes over.
+pub const HIDDEN_ARG: &str = "--hidden";
+
+/// Registers or unregisters the app to start when the user logs in.
+///
+/// Windows uses the `HKCU\...\Run` registry key, Linux an XDG autostart
+/// `.desktop` file and macOS a LaunchAgent plist. Entries pass
+/// [`HIDDEN_ARG`].
+pub trait AutostartManager {
+    fn new() -> Self
+    where
+        Self: Sized;
+
+    /// True if a login entry exists and points at the running executable,
+    /// with or without [`HIDDEN_ARG`] (entries written by older versions
+    /// lack it).
+    fn is_startup_enabled(&self) -> bool;
+
+    /// Create or remove the login entry. Returns `false` if the change
+    /// couldn't be written.
+    fn set_startup_enabled(&self, enabled: bool) -> bool;
+}
+
 // ---------------------------------------------------------------------------
 // Platform selection — type aliases resolve to the concrete types for the
 // current OS so the rest of the codebase never names a platform directly.
 // ---------------------------------------------------------------------------
 
+#[cfg(target_os = "windows")]
+pub type PlatformMonitorProvider = crate::platform::WinMonitorProvider;
 #[cfg(target_os = "windows")]
 pub type PlatformWindow = crate::platform::WinWindowController;
+#[cfg(target_os = "windows")]
+pub type PlatformAutostart = crate::platform::WinAutostartManager;
+#[cfg(target_os = "windows")]
+pub type PlatformAmbientSensor = crate::platform::WinAmbientSensor;
+#[cfg(target_os = "windows")]
+pub type PlatformPowerEvents = crate::platform::WinPowerEvents;
+#[cfg(target_os = "windows")]
+pub type PlatformFullscreenDetector = crate::platform::WinFullscreenDetector;
+#[cfg(target_os = "windows")]
+pub type PlatformBattery = crate::platform::WinBattery;
+#[cfg(target_os = "windows")]
+pub type PlatformBrightnessKeys = crate::platform::WinBrightnessKeys;
+#[cfg(target_os = "windows")]
+pub type PlatformNotifier = crate::platform::WinNotifier;
 
+#[cfg(target_os = "linux")]
+pub type PlatformMonitorProvider = crate::platform::LinuxMonitorProvider;
 #[cfg(target_os = "linux")]
 pub type PlatformWindow = crate::platform::LinuxWindowController;
+#[cfg(target_os = "linux")]
+pub type PlatformAutostart = crate::platform::LinuxAutostartManager;
+#[cfg(target_os = "linux")]
+pub type PlatformAmbientSensor = crate::platform::LinuxAmbientSensor;
+#[cfg(target_os = "linux")]
+pub type PlatformPowerEvents = crate::platform::LinuxPowerEvents;
+#[cfg(target_os = "linux")]
+pub type PlatformFullscreenDetector = crate::platform::LinuxFullscreenDetector;
+#[cfg(target_os = "linux")]
+pub type PlatformBattery = crate::platform::LinuxBattery;
+#[cfg(target_os = "linux")]
+pub type PlatformBrightnessKeys = crate::platform::LinuxBrightnessKeys;
+#[cfg(target_os = "linux")]
+pub type PlatformNotifier = crate::platform::LinuxNotifier;
 
+#[cfg(target_os = "macos")]
+pub type PlatformMonitorProvider = crate::platform::MacMonitorProvider;
 #[cfg(target_os = "macos")]
 pub type PlatformWindow = crate::platform::MacWindowController;
+#[cfg(target_os = "macos")]
+pub type PlatformAutostart = crate::platform::MacAutostartManager;
+#[cfg(target_os = "macos")]
+pub type PlatformAmbientSensor = crate::platform::MacAmbientSensor;
+#[cfg(target_os = "macos")]
+pub type PlatformPowerEvents = crate::platform::MacPowerEvents;
+#[cfg(target_os = "macos")]
+pub type PlatformFullscreenDetector = crate::platform::MacFullscreenDetector;
+#[cfg(target_os = "macos")]
+pub type PlatformBattery = crate::platform::MacBattery;
+#[cfg(target_os = "macos")]
+pub type PlatformBrightnessKeys = crate::platform::MacBrightnessKeys;
+#[cfg(target_os = "macos")]
+pub type PlatformNotifier = crate::platform::MacNotifier;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_mode_rounds_near_whole_rates() {
+        let mode = |refresh_hz| DisplayMode {
+            width: 2560,
+            height: 1440,
+            refresh_hz,
+        };
+        assert_eq!(mode(Some(143.998)).to_string(), "2560×1440 @ 144 Hz");
+        assert_eq!(mode(Some(59.94)).to_string(), "2560×1440 @ 59.94 Hz");
+        assert_eq!(mode(None).to_string(), "2560×1440");
+    }
+}
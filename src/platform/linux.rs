@@ -1,112 +1,662 @@
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::PathBuf;
-use std::process::Command;
-use std::sync::Mutex;
+use std::io::{ErrorKind, Read, Write};
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
 
+use ddc::{
+    Ddc, DdcCommand, DdcCommandMarker, DdcCommandRaw, DdcCommandRawMarker, DdcHost, Delay,
+    ErrorCode,
+};
+use eframe::egui;
 use raw_window_handle::RawWindowHandle;
 
-use crate::os::WindowController;
+use crate::config::DdcBackend;
+use crate::edid;
+use crate::monitors::{
+    gamma_percent, gamma_ramp, percent_of_range, probe_brightness, range_to_percent,
+    retry_with_backoff,
+};
+use crate::os::{
+    AmbientSensor, AutostartManager, Battery, BrightnessKeys, DisplayMode, FullscreenDetector,
+    HIDDEN_ARG, MonitorHandle, MonitorProvider, Notifier, PowerEvents, SetupRequired,
+    WindowController,
+};
+use crate::vcp;
+
+/// VCP feature code for luminance (brightness).
+const VCP_BRIGHTNESS: u8 = 0x10;
+
+/// VCP feature code for contrast.
+const VCP_CONTRAST: u8 = 0x12;
+
+/// VCP feature code for the colour preset selector.
+const VCP_COLOR_PRESET: u8 = vcp::COLOR_PRESET;
+
+/// VCP feature code for the built-in speaker volume.
+const VCP_VOLUME: u8 = 0x62;
+
+/// Shown on external monitors when ddcutil isn't installed.
+const DDCUTIL_MISSING: &str = "Install ddcutil for external monitor control";
+
+/// Shown on external monitors when `ddc_backend = "i2c"` can't open their
+/// I2C bus.
+const I2C_NO_ACCESS: &str = "Can't open /dev/i2c-*: load the i2c-dev module and join the i2c group \
+     (sudo usermod -aG i2c $USER), then log in again";
 
 enum MonitorBackend {
     /// Laptop backlight via /sys/class/backlight/
     Backlight { path: PathBuf },
     /// External monitor via DDC/CI (ddcutil)
-    Ddc { display_number: u32 },
+    Ddc { target: DdcTarget },
+    /// External monitor via DDC/CI on `/dev/i2c-{bus}` directly (see
+    /// [`I2cDdc`])
+    I2c { bus: u32 },
+    /// X11 output dimmed through its gamma ramp, when nothing else can
+    /// control it (see [`get_gamma_monitors`])
+    Gamma { output: String },
+}
+
+impl MonitorBackend {
+    /// Read a continuous VCP feature as `(current, max)` over whichever
+    /// DDC/CI transport this monitor uses.
+    fn getvcp(&self, code: u8) -> Result<(u32, u32), anyhow::Error> {
+        match self {
+            MonitorBackend::Ddc { target } => ddc_getvcp(*target, code),
+            MonitorBackend::I2c { bus } => {
+                let value = I2cDdc::open(*bus)?.get_vcp_feature(code)?;
+                Ok((value.value() as u32, value.maximum() as u32))
+            }
+            MonitorBackend::Backlight { .. } | MonitorBackend::Gamma { .. } => Err(not_ddc()),
+        }
+    }
+
+    /// Read a non-continuous VCP feature.
+    fn getvcp_nc(&self, code: u8) -> Result<u8, anyhow::Error> {
+        match self {
+            MonitorBackend::Ddc { target } => ddc_getvcp_nc(*target, code),
+            MonitorBackend::I2c { bus } => {
+                Ok(I2cDdc::open(*bus)?.get_vcp_feature(code)?.value() as u8)
+            }
+            MonitorBackend::Backlight { .. } | MonitorBackend::Gamma { .. } => Err(not_ddc()),
+        }
+    }
+
+    /// Write a VCP feature.
+    fn setvcp(&self, code: u8, value: u32) -> Result<(), anyhow::Error> {
+        match self {
+            MonitorBackend::Ddc { target } => ddc_setvcp(*target, code, value),
+            MonitorBackend::I2c { bus } => I2cDdc::open(*bus)?.set_vcp_feature(code, value as u16),
+            MonitorBackend::Backlight { .. } | MonitorBackend::Gamma { .. } => Err(not_ddc()),
+        }
+    }
+}
+
+fn not_ddc() -> anyhow::Error {
+    anyhow::anyhow!("Not a DDC/CI monitor")
+}
+
+/// How ddcutil is pointed at a monitor. Addressing it by I2C bus skips the
+/// display scan ddcutil otherwise runs on every `--display` call, which
+/// takes most of the time of a read.
+#[derive(Clone, Copy, Debug)]
+struct DdcTarget {
+    display_number: u32,
+    bus: Option<u32>,
 }
 
 pub struct Monitor {
     pub name: String,
+    /// See [`ddc_monitor_id`] for DDC monitors; the sysfs device name for
+    /// backlights; the EDID or X11 output name for gamma-dimmed outputs.
+    id: String,
+    /// Serial number and I2C bus (`/dev/i2c-4`) reported by `ddcutil detect`.
+    serial: Option<String>,
+    i2c_bus: Option<String>,
+    /// Raw hardware range (100 for backlights, which are scaled on write).
+    /// Callers only ever see 0-100.
     pub min_brightness: Option<u32>,
+    /// Last known brightness as a 0-100 percentage.
     pub current_brightness: Option<u32>,
     pub max_brightness: Option<u32>,
+    /// Raw MCCS capability string, fetched lazily on first use.
+    capabilities: Option<String>,
+    /// See [`MonitorHandle::supports_brightness`]. Always true for
+    /// anything but DDC/CI monitors.
+    supports_brightness: bool,
+    /// Whether VCP 0x62 (speaker volume) answered when probed.
+    has_volume: bool,
+    /// Peak luminance from the connector's EDID, for HDR displays.
+    max_luminance: Option<u32>,
+    /// Mode of the X11 output it's on (see [`output_modes`]).
+    display_mode: Option<DisplayMode>,
+    /// Connected to X11's primary output (see [`primary_output`]).
+    primary: bool,
     backend: MonitorBackend,
 }
 
 impl Monitor {
-    pub fn poll_brightness_values(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+    fn capabilities_string(&mut self) -> Result<String, anyhow::Error> {
+        if let Some(ref caps) = self.capabilities {
+            return Ok(caps.clone());
+        }
+
+        let caps = match &self.backend {
+            MonitorBackend::Ddc { target } => ddc_capabilities(*target)?,
+            MonitorBackend::I2c { bus } => {
+                String::from_utf8_lossy(&I2cDdc::open(*bus)?.capabilities_string()?).to_string()
+            }
+            MonitorBackend::Backlight { .. } | MonitorBackend::Gamma { .. } => {
+                return Err(anyhow::anyhow!(
+                    "Only DDC/CI monitors have a capability string"
+                ));
+            }
+        };
+
+        self.capabilities = Some(caps.clone());
+        Ok(caps)
+    }
+
+    fn poll_backlight(&mut self, path: PathBuf) -> Result<(u32, u32, u32), anyhow::Error> {
+        let max_raw = fs::read_to_string(path.join("max_brightness"))?
+            .trim()
+            .parse::<u32>()?;
+        let current_raw = fs::read_to_string(path.join("brightness"))?
+            .trim()
+            .parse::<u32>()?;
+
+        // Normalize to 0-100 range
+        let current = range_to_percent(current_raw, 0, max_raw);
+
+        self.min_brightness = Some(0);
+        self.current_brightness = Some(current);
+        self.max_brightness = Some(100);
+
+        Ok((current, 0, 100))
+    }
+
+    fn read_volume(&self) -> Result<(u32, u32, u32), anyhow::Error> {
+        match &self.backend {
+            MonitorBackend::Backlight { .. } | MonitorBackend::Gamma { .. } => {
+                Err(anyhow::anyhow!("Volume is not supported without DDC/CI"))
+            }
+            MonitorBackend::Ddc { .. } | MonitorBackend::I2c { .. } => {
+                let (current, max) = self.backend.getvcp(VCP_VOLUME)?;
+                if max == 0 {
+                    return Err(anyhow::anyhow!("Monitor does not report a volume range"));
+                }
+                Ok((current, 0, max))
+            }
+        }
+    }
+
+    fn poll_ddc(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+        let (current_raw, max) = self.backend.getvcp(VCP_BRIGHTNESS)?;
+
+        // Normalize to 0-100 range; the raw max is kept for writes
+        let current = range_to_percent(current_raw, 0, max);
+
+        self.min_brightness = Some(0);
+        self.current_brightness = Some(current);
+        self.max_brightness = Some(max);
+
+        Ok((current, 0, 100))
+    }
+}
+
+impl MonitorHandle for Monitor {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn poll_brightness(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
         match &self.backend {
             MonitorBackend::Backlight { path } => self.poll_backlight(path.clone()),
-            MonitorBackend::Ddc { display_number } => self.poll_ddc(*display_number),
+            MonitorBackend::Ddc { .. } => {
+                // Retrying can't make ddcutil appear, so don't wait for it
+                require_ddcutil()?;
+                retry_with_backoff(|| self.poll_ddc())
+            }
+            MonitorBackend::I2c { bus } => {
+                // Nor can it grant access to the bus
+                I2cDdc::open(*bus)?;
+                retry_with_backoff(|| self.poll_ddc())
+            }
+            MonitorBackend::Gamma { output } => {
+                let current = read_gamma(output)?;
+                self.current_brightness = Some(current);
+                Ok((current, 0, 100))
+            }
         }
     }
 
-    pub fn set_brightness(&mut self, value: u32) -> Result<(), anyhow::Error> {
+    fn set_brightness(&mut self, value: u32) -> Result<(), anyhow::Error> {
         let max = self.max_brightness.unwrap_or(100);
         let min = self.min_brightness.unwrap_or(0);
-        let clamped = value.clamp(min, max);
+        let clamped = value.min(100);
 
         match &self.backend {
             MonitorBackend::Backlight { path } => {
                 // For backlight, convert from our 0-100 range to the device's raw range
-                let max_raw = fs::read_to_string(path.join("max_brightness"))?.trim().parse::<u32>()?;
-                let raw_value = (clamped as u64 * max_raw as u64 / 100) as u32;
+                let max_raw = fs::read_to_string(path.join("max_brightness"))?
+                    .trim()
+                    .parse::<u32>()?;
+                let raw_value = percent_of_range(0, max_raw, clamped);
                 fs::write(path.join("brightness"), raw_value.to_string())?;
             }
-            MonitorBackend::Ddc { display_number } => {
-                let output = Command::new("ddcutil")
-                    .args(["setvcp", "10", &clamped.to_string(), "--display", &display_number.to_string()])
-                    .output()?;
+            MonitorBackend::Ddc { .. } | MonitorBackend::I2c { .. } => {
+                let raw_value = percent_of_range(min, max, clamped);
+                self.backend.setvcp(VCP_BRIGHTNESS, raw_value)?;
+            }
+            MonitorBackend::Gamma { output } => write_gamma(output, clamped)?,
+        }
 
-                if !output.status.success() {
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-                    return Err(anyhow::anyhow!("ddcutil setvcp failed: {}", stderr.trim()));
+        self.current_brightness = Some(clamped);
+        Ok(())
+    }
+
+    fn poll_contrast(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+        match &self.backend {
+            MonitorBackend::Backlight { .. } | MonitorBackend::Gamma { .. } => {
+                Err(anyhow::anyhow!("Contrast is not supported without DDC/CI"))
+            }
+            MonitorBackend::Ddc { .. } | MonitorBackend::I2c { .. } => {
+                let (current, max) = self.backend.getvcp(VCP_CONTRAST)?;
+                if max == 0 {
+                    return Err(anyhow::anyhow!("Monitor does not report a contrast range"));
                 }
+                Ok((current, 0, max))
             }
         }
+    }
 
-        self.current_brightness = Some(clamped);
+    fn set_contrast(&mut self, value: u32) -> Result<(), anyhow::Error> {
+        match &self.backend {
+            MonitorBackend::Backlight { .. } | MonitorBackend::Gamma { .. } => {
+                Err(anyhow::anyhow!("Contrast is not supported without DDC/CI"))
+            }
+            MonitorBackend::Ddc { .. } | MonitorBackend::I2c { .. } => {
+                self.backend.setvcp(VCP_CONTRAST, value)
+            }
+        }
+    }
+
+    fn list_color_presets(&mut self) -> Vec<(u8, String)> {
+        self.capabilities_string()
+            .map(|caps| vcp::color_presets(&caps))
+            .unwrap_or_default()
+    }
+
+    fn poll_color_preset(&mut self) -> Result<u8, anyhow::Error> {
+        match &self.backend {
+            MonitorBackend::Backlight { .. } | MonitorBackend::Gamma { .. } => Err(
+                anyhow::anyhow!("Color presets are not supported without DDC/CI"),
+            ),
+            MonitorBackend::Ddc { .. } | MonitorBackend::I2c { .. } => {
+                self.backend.getvcp_nc(VCP_COLOR_PRESET)
+            }
+        }
+    }
+
+    fn set_color_preset(&mut self, preset: u8) -> Result<(), anyhow::Error> {
+        match &self.backend {
+            MonitorBackend::Backlight { .. } | MonitorBackend::Gamma { .. } => Err(
+                anyhow::anyhow!("Color presets are not supported without DDC/CI"),
+            ),
+            MonitorBackend::Ddc { .. } | MonitorBackend::I2c { .. } => {
+                self.backend.setvcp(VCP_COLOR_PRESET, preset as u32)
+            }
+        }
+    }
+
+    fn supports_brightness(&self) -> bool {
+        self.supports_brightness
+    }
+
+    fn has_volume(&self) -> bool {
+        self.has_volume
+    }
+
+    fn poll_volume(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+        if !self.has_volume {
+            return Err(anyhow::anyhow!("Monitor does not support volume"));
+        }
+        self.read_volume()
+    }
+
+    fn set_volume(&mut self, value: u32) -> Result<(), anyhow::Error> {
+        match &self.backend {
+            MonitorBackend::Backlight { .. } | MonitorBackend::Gamma { .. } => {
+                Err(anyhow::anyhow!("Volume is not supported without DDC/CI"))
+            }
+            MonitorBackend::Ddc { .. } | MonitorBackend::I2c { .. } => {
+                self.backend.setvcp(VCP_VOLUME, value)
+            }
+        }
+    }
+
+    fn capabilities(&mut self) -> Result<String, anyhow::Error> {
+        self.capabilities_string()
+    }
+
+    fn get_vcp(&mut self, code: u8) -> Result<(u16, u16), anyhow::Error> {
+        match &self.backend {
+            MonitorBackend::Backlight { .. } | MonitorBackend::Gamma { .. } => {
+                Err(anyhow::anyhow!("VCP is not supported without DDC/CI"))
+            }
+            MonitorBackend::Ddc { target } => {
+                // ddcutil's brief output differs for non-continuous features
+                match ddc_getvcp(*target, code) {
+                    Ok((current, max)) => Ok((current as u16, max as u16)),
+                    Err(_) => Ok((ddc_getvcp_nc(*target, code)? as u16, 0)),
+                }
+            }
+            MonitorBackend::I2c { bus } => {
+                let value = I2cDdc::open(*bus)?.get_vcp_feature(code)?;
+                Ok((value.value(), value.maximum()))
+            }
+        }
+    }
+
+    fn set_vcp(&mut self, code: u8, value: u16) -> Result<(), anyhow::Error> {
+        match &self.backend {
+            MonitorBackend::Backlight { .. } | MonitorBackend::Gamma { .. } => {
+                Err(anyhow::anyhow!("VCP is not supported without DDC/CI"))
+            }
+            MonitorBackend::Ddc { .. } | MonitorBackend::I2c { .. } => {
+                self.backend.setvcp(code, value as u32)
+            }
+        }
+    }
+
+    fn set_power_mode(&mut self, mode: u8) -> Result<(), anyhow::Error> {
+        match &self.backend {
+            MonitorBackend::Backlight { .. } | MonitorBackend::Gamma { .. } => {
+                Err(anyhow::anyhow!("Power mode needs DDC/CI"))
+            }
+            MonitorBackend::Ddc { .. } | MonitorBackend::I2c { .. } => {
+                self.backend.setvcp(vcp::POWER_MODE, mode as u32)
+            }
+        }
+    }
+
+    fn is_software(&self) -> bool {
+        matches!(self.backend, MonitorBackend::Gamma { .. })
+    }
+
+    fn is_primary(&self) -> bool {
+        self.primary
+    }
+
+    fn max_luminance(&self) -> Option<u32> {
+        self.max_luminance
+    }
+
+    fn display_mode(&self) -> Option<DisplayMode> {
+        self.display_mode
+    }
+}
+
+/// Whether ddcutil can be run. Checked once, the first time it's needed.
+fn ddcutil_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        let available = Command::new("ddcutil").arg("--version").output().is_ok();
+        if !available {
+            log::warn!("ddcutil not found; external monitors can't be controlled");
+        }
+        available
+    })
+}
+
+fn require_ddcutil() -> Result<(), anyhow::Error> {
+    if ddcutil_available() {
         Ok(())
+    } else {
+        Err(SetupRequired(DDCUTIL_MISSING).into())
     }
+}
 
-    fn poll_backlight(&mut self, path: PathBuf) -> Result<(u32, u32, u32), anyhow::Error> {
-        let max_raw = fs::read_to_string(path.join("max_brightness"))?.trim().parse::<u32>()?;
-        let current_raw = fs::read_to_string(path.join("brightness"))?.trim().parse::<u32>()?;
+/// Run ddcutil with `args`, or fail with [`SetupRequired`] if it isn't installed.
+fn ddcutil(args: &[&str]) -> Result<Output, anyhow::Error> {
+    require_ddcutil()?;
+    let started = Instant::now();
+    let output = Command::new("ddcutil").args(args).output()?;
+    log::trace!("ddcutil {} took {:?}", args.join(" "), started.elapsed());
+    Ok(output)
+}
 
-        // Normalize to 0-100 range
-        let current = if max_raw > 0 {
-            (current_raw as u64 * 100 / max_raw as u64) as u32
-        } else {
-            0
-        };
+/// Run a ddcutil command against one monitor: by bus when it's known, and
+/// by display number if that fails, as before buses were tracked.
+fn ddcutil_on(target: DdcTarget, args: &[&str]) -> Result<Output, anyhow::Error> {
+    if let Some(bus) = target.bus {
+        let bus = bus.to_string();
+        let output = ddcutil(&[args, &["--bus", &bus]].concat())?;
+        if output.status.success() {
+            return Ok(output);
+        }
+        log::debug!(
+            "ddcutil failed on bus {bus}, retrying as display {}",
+            target.display_number
+        );
+    }
+    let display = target.display_number.to_string();
+    ddcutil(&[args, &["--display", &display]].concat())
+}
 
-        self.min_brightness = Some(0);
-        self.current_brightness = Some(current);
-        self.max_brightness = Some(100);
+/// Raw MCCS capability string via ddcutil.
+fn ddc_capabilities(target: DdcTarget) -> Result<String, anyhow::Error> {
+    let output = ddcutil_on(target, &["capabilities", "--verbose"])?;
 
-        Ok((current, 0, 100))
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow::anyhow!(
+            "ddcutil capabilities failed: {}",
+            stderr.trim()
+        ));
+    }
+
+    // Verbose output echoes the raw string on a line like
+    // "Unparsed capabilities string: (prot(monitor)type(LCD)...)"
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .lines()
+        .find_map(|line| {
+            line.split_once("capabilities string:")
+                .map(|(_, rest)| rest.trim().to_string())
+        })
+        .ok_or_else(|| anyhow::anyhow!("ddcutil did not report a capability string"))
+}
+
+/// Read a continuous VCP feature via ddcutil, returning `(current, max)`.
+fn ddc_getvcp(target: DdcTarget, code: u8) -> Result<(u32, u32), anyhow::Error> {
+    let output = ddcutil_on(target, &["getvcp", &format!("{code:02x}"), "--brief"])?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow::anyhow!("ddcutil getvcp failed: {}", stderr.trim()));
+    }
+
+    // --brief format: "VCP 10 C 50 100" (code, type, current, max)
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let parts: Vec<&str> = stdout.split_whitespace().collect();
+
+    if parts.len() < 5 {
+        return Err(anyhow::anyhow!(
+            "Unexpected ddcutil output: {}",
+            stdout.trim()
+        ));
+    }
+
+    let current: u32 = parts[3].parse()?;
+    let max: u32 = parts[4].parse()?;
+
+    Ok((current, max))
+}
+
+/// Read a non-continuous VCP feature via ddcutil.
+fn ddc_getvcp_nc(target: DdcTarget, code: u8) -> Result<u8, anyhow::Error> {
+    let output = ddcutil_on(target, &["getvcp", &format!("{code:02x}"), "--brief"])?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow::anyhow!("ddcutil getvcp failed: {}", stderr.trim()));
+    }
+
+    // --brief format: "VCP 14 SNC x05" (code, type, hex value)
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let value = stdout
+        .split_whitespace()
+        .nth(3)
+        .and_then(|v| v.strip_prefix('x'))
+        .ok_or_else(|| anyhow::anyhow!("Unexpected ddcutil output: {}", stdout.trim()))?;
+
+    Ok(u8::from_str_radix(value, 16)?)
+}
+
+/// Write a VCP feature via ddcutil.
+fn ddc_setvcp(target: DdcTarget, code: u8, value: u32) -> Result<(), anyhow::Error> {
+    let output = ddcutil_on(
+        target,
+        &["setvcp", &format!("{code:02x}"), &value.to_string()],
+    )?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow::anyhow!("ddcutil setvcp failed: {}", stderr.trim()));
     }
 
-    fn poll_ddc(&mut self, display_number: u32) -> Result<(u32, u32, u32), anyhow::Error> {
-        let output = Command::new("ddcutil")
-            .args(["getvcp", "10", "--display", &display_number.to_string(), "--brief"])
-            .output()?;
+    Ok(())
+}
+
+/// `I2C_SLAVE` from `<linux/i2c-dev.h>`: address later reads and writes
+/// on an I2C device node to one slave.
+const I2C_SLAVE: libc::Ioctl = 0x0703;
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow::anyhow!("ddcutil getvcp failed: {}", stderr.trim()));
+/// When each I2C bus may next be spoken to, so the gap DDC/CI asks for
+/// between commands holds across the short-lived [`I2cDdc`] handles.
+static I2C_READY_AT: Mutex<BTreeMap<u32, Instant>> = Mutex::new(BTreeMap::new());
+
+/// DDC/CI over a `/dev/i2c-N` node, without ddcutil. Opened for each
+/// read or write, which is cheap next to the DDC/CI delays themselves.
+struct I2cDdc {
+    bus: u32,
+    file: fs::File,
+    delay: Delay,
+}
+
+impl I2cDdc {
+    /// Open `/dev/i2c-{bus}` addressed to the monitor's DDC/CI slave. Fails
+    /// with [`SetupRequired`] if the node can't be opened for lack of the
+    /// `i2c` group or the `i2c-dev` module.
+    fn open(bus: u32) -> Result<Self, anyhow::Error> {
+        let path = format!("/dev/i2c-{bus}");
+        let file = match fs::OpenOptions::new().read(true).write(true).open(&path) {
+            Ok(file) => file,
+            Err(e) if matches!(e.kind(), ErrorKind::PermissionDenied | ErrorKind::NotFound) => {
+                log::debug!("Can't open {path}: {e}");
+                return Err(SetupRequired(I2C_NO_ACCESS).into());
+            }
+            Err(e) => return Err(anyhow::anyhow!("Can't open {path}: {e}")),
+        };
+        let address = ddc::I2C_ADDRESS_DDC_CI as libc::c_ulong;
+        if unsafe { libc::ioctl(file.as_raw_fd(), I2C_SLAVE, address) } < 0 {
+            return Err(anyhow::anyhow!(
+                "Can't address DDC/CI on {path}: {}",
+                std::io::Error::last_os_error()
+            ));
         }
 
-        // --brief format: "VCP 10 C 50 100" (code, type, current, max)
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let parts: Vec<&str> = stdout.trim().split_whitespace().collect();
+        let ready_at = I2C_READY_AT.lock().unwrap().get(&bus).copied();
+        let delay = ready_at.map_or_else(Delay::default, |at| {
+            Delay::new(at.saturating_duration_since(Instant::now()))
+        });
+        Ok(Self { bus, file, delay })
+    }
+}
+
+impl Drop for I2cDdc {
+    fn drop(&mut self) {
+        let ready_at = Instant::now() + self.delay.remaining();
+        I2C_READY_AT.lock().unwrap().insert(self.bus, ready_at);
+    }
+}
+
+impl DdcHost for I2cDdc {
+    type Error = anyhow::Error;
+
+    fn sleep(&mut self) {
+        self.delay.sleep()
+    }
+}
 
-        if parts.len() < 5 {
-            return Err(anyhow::anyhow!("Unexpected ddcutil output: {}", stdout.trim()));
+impl DdcCommandRaw for I2cDdc {
+    fn execute_raw<'a>(
+        &mut self,
+        data: &[u8],
+        out: &'a mut [u8],
+        response_delay: Duration,
+    ) -> Result<&'a mut [u8], anyhow::Error> {
+        // Commands are at most 36 bytes (see `DdcCommand::execute`)
+        let mut packet = [0u8; 36 + 3];
+        let packet = Self::encode_command(data, &mut packet);
+
+        self.delay.sleep();
+        self.file.write_all(packet)?;
+        if out.is_empty() {
+            return Ok(out);
         }
 
-        let current: u32 = parts[3].parse()?;
-        let max: u32 = parts[4].parse()?;
+        std::thread::sleep(response_delay);
+        let read = self.file.read(out)?;
+        ddc_reply(&mut out[..read])
+    }
+}
 
-        self.min_brightness = Some(0);
-        self.current_brightness = Some(current);
-        self.max_brightness = Some(max);
+impl DdcCommandRawMarker for I2cDdc {
+    fn set_sleep_delay(&mut self, delay: Delay) {
+        self.delay = delay;
+    }
+}
+
+impl DdcCommandMarker for I2cDdc {}
 
-        Ok((current, 0, max))
+/// Payload of a DDC/CI reply as read off the bus: the monitor's address
+/// (0x6E), a length byte with its top bit set, the payload, then a checksum
+/// over all of it seeded with the host's address (0x50, as replies are
+/// addressed to it).
+fn ddc_reply(reply: &mut [u8]) -> Result<&mut [u8], anyhow::Error> {
+    let [source, length, ..] = *reply else {
+        return Err(ErrorCode::InvalidLength.into());
+    };
+    if source != (ddc::I2C_ADDRESS_DDC_CI as u8) << 1 || length & 0x80 == 0 {
+        return Err(ErrorCode::InvalidData.into());
+    }
+    let len = (length & 0x7f) as usize;
+    if reply.len() < len + 3 {
+        return Err(ErrorCode::InvalidLength.into());
     }
+    let checksum = reply[..len + 2].iter().fold(0x50, |sum, byte| sum ^ byte);
+    if checksum != reply[len + 2] {
+        return Err(ErrorCode::InvalidChecksum.into());
+    }
+    Ok(&mut reply[2..len + 2])
 }
 
 /// Discover backlight devices from /sys/class/backlight/
-fn get_backlight_monitors() -> Vec<Monitor> {
+/// Built-in panels from sysfs. They're primary when `primary_output` is a
+/// built-in connector.
+fn get_backlight_monitors(
+    primary_output: Option<&str>,
+    modes: &HashMap<String, DisplayMode>,
+) -> Vec<Monitor> {
     let mut monitors = Vec::new();
     let backlight_dir = PathBuf::from("/sys/class/backlight");
 
@@ -119,12 +669,28 @@ fn get_backlight_monitors() -> Vec<Monitor> {
         let path = entry.path();
         // Verify it has the expected brightness files
         if path.join("brightness").exists() && path.join("max_brightness").exists() {
-            let name = entry.file_name().to_string_lossy().to_string();
+            // The id stays the sysfs name so settings keyed by it carry over
+            let id = entry.file_name().to_string_lossy().to_string();
+            let connector = backlight_connector(&path);
+            let edid = connector.as_deref().and_then(connector_edid);
+            let name = edid
+                .as_deref()
+                .and_then(edid::model_name)
+                .unwrap_or_else(|| id.clone());
             monitors.push(Monitor {
+                id,
                 name,
+                serial: None,
+                i2c_bus: None,
                 min_brightness: None,
                 current_brightness: None,
                 max_brightness: None,
+                capabilities: None,
+                supports_brightness: true,
+                has_volume: false,
+                max_luminance: edid.as_deref().and_then(edid::max_luminance),
+                display_mode: connector_mode(connector.as_deref(), modes),
+                primary: primary_output.is_some_and(is_internal_output),
                 backend: MonitorBackend::Backlight { path },
             });
         }
@@ -133,83 +699,953 @@ fn get_backlight_monitors() -> Vec<Monitor> {
     monitors
 }
 
-/// Discover external monitors via ddcutil
-fn get_ddc_monitors() -> Vec<Monitor> {
-    let mut monitors = Vec::new();
-
-    let output = match Command::new("ddcutil").args(["detect"]).output() {
-        Ok(output) => output,
-        Err(_) => return monitors,
+/// Discover external monitors via ddcutil. Without ddcutil, connected
+/// external outputs are listed anyway so the panel can say what's missing.
+fn get_ddc_monitors(
+    primary_output: Option<&str>,
+    modes: &HashMap<String, DisplayMode>,
+) -> Vec<Monitor> {
+    if !ddcutil_available() {
+        return get_undetected_monitors(primary_output, modes);
+    }
+    let output = match ddcutil(&["detect"]) {
+        Ok(output) if output.status.success() => output,
+        _ => return Vec::new(),
     };
 
-    if !output.status.success() {
-        return monitors;
+    parse_ddc_detect(&String::from_utf8_lossy(&output.stdout))
+        .into_iter()
+        .map(|display| {
+            let name = ddc_monitor_name(
+                display.model.clone(),
+                display.connector.as_deref(),
+                display.number,
+            );
+            let bus = bus_number(display.i2c_bus.as_deref());
+            let target = DdcTarget {
+                display_number: display.number,
+                bus,
+            };
+            let edid = display.connector.as_deref().and_then(connector_edid);
+            Monitor {
+                id: ddc_monitor_id(&display, &name),
+                name,
+                serial: display.serial(),
+                i2c_bus: display.i2c_bus,
+                min_brightness: None,
+                current_brightness: None,
+                max_brightness: None,
+                capabilities: None,
+                supports_brightness: true,
+                has_volume: false,
+                max_luminance: edid.as_deref().and_then(edid::max_luminance),
+                display_mode: connector_mode(display.connector.as_deref(), modes),
+                primary: is_connector_for(display.connector.as_deref(), primary_output),
+                backend: MonitorBackend::Ddc { target },
+            }
+        })
+        .collect()
+}
+
+/// One `Display N` block from `ddcutil detect`.
+#[derive(Debug, Default, PartialEq)]
+struct DetectedDisplay {
+    number: u32,
+    /// e.g. `/dev/i2c-4`
+    i2c_bus: Option<String>,
+    /// DRM connector such as `card0-DP-1`
+    connector: Option<String>,
+    /// PnP manufacturer code such as `DEL`
+    manufacturer: Option<String>,
+    model: Option<String>,
+    serial_text: Option<String>,
+    serial_number: Option<u32>,
+    /// Older ddcutil's one-line EDID summary: `DEL:DELL U2720Q:ABC123`
+    summary: Option<String>,
+}
+
+impl DetectedDisplay {
+    /// The serial string when the monitor reports one, else its non-zero
+    /// binary serial number.
+    fn serial(&self) -> Option<String> {
+        self.serial_text.clone().or_else(|| {
+            self.serial_number
+                .filter(|&n| n != 0)
+                .map(|n| n.to_string())
+        })
     }
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut current_display: Option<u32> = None;
-    let mut current_model: Option<String> = None;
+/// Split `ddcutil detect` output into its valid displays. Blocks headed
+/// "Invalid display", "Display not found" and the like are skipped, fields
+/// and all, so they never turn into monitors.
+fn parse_ddc_detect(stdout: &str) -> Vec<DetectedDisplay> {
+    let mut displays = Vec::new();
+    let mut current: Option<DetectedDisplay> = None;
 
     for line in stdout.lines() {
-        let trimmed = line.trim();
-
-        if let Some(rest) = trimmed.strip_prefix("Display ") {
-            // Save previous display if we have one
-            if let (Some(num), Some(model)) = (current_display.take(), current_model.take()) {
-                monitors.push(Monitor {
-                    name: model,
-                    min_brightness: None,
-                    current_brightness: None,
-                    max_brightness: None,
-                    backend: MonitorBackend::Ddc { display_number: num },
+        // Block headers are the only unindented lines
+        if !line.starts_with(char::is_whitespace) && !line.trim().is_empty() {
+            displays.extend(current.take());
+            current = line
+                .trim()
+                .strip_prefix("Display ")
+                .and_then(|n| n.parse().ok())
+                .map(|number| DetectedDisplay {
+                    number,
+                    ..Default::default()
                 });
+            continue;
+        }
+
+        let Some(display) = current.as_mut() else {
+            continue;
+        };
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if value.is_empty() {
+            continue;
+        }
+        match key.trim() {
+            "I2C bus" => display.i2c_bus = Some(value.to_string()),
+            "DRM connector" => display.connector = Some(value.to_string()),
+            // "DEL - Dell Inc."
+            "Mfg id" => display.manufacturer = value.split_whitespace().next().map(str::to_string),
+            "Model" => display.model = Some(value.to_string()),
+            "Serial number" => display.serial_text = Some(value.to_string()),
+            // "1234567 (0x0012d687)"
+            "Binary serial number" => {
+                display.serial_number = value.split_whitespace().next().and_then(|n| n.parse().ok())
+            }
+            "Monitor" => display.summary = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    displays.extend(current);
+    displays
+}
+
+/// Connected external outputs from /sys/class/drm/, as DDC monitors that
+/// fail with [`DDCUTIL_MISSING`] when read.
+fn get_undetected_monitors(
+    primary_output: Option<&str>,
+    modes: &HashMap<String, DisplayMode>,
+) -> Vec<Monitor> {
+    external_connectors()
+        .into_iter()
+        .zip(1..)
+        .map(|(connector, num)| {
+            let name = ddc_monitor_name(None, Some(&connector), num);
+            let primary = is_connector_for(Some(&connector), primary_output);
+            let display_mode = connector_mode(Some(&connector), modes);
+            let display = DetectedDisplay {
+                number: num,
+                connector: Some(connector),
+                ..Default::default()
+            };
+            Monitor {
+                id: ddc_monitor_id(&display, &name),
+                name,
+                serial: None,
+                i2c_bus: None,
+                min_brightness: None,
+                current_brightness: None,
+                max_brightness: None,
+                capabilities: None,
+                supports_brightness: true,
+                has_volume: false,
+                max_luminance: None,
+                display_mode,
+                primary,
+                backend: MonitorBackend::Ddc {
+                    target: DdcTarget {
+                        display_number: num,
+                        bus: None,
+                    },
+                },
+            }
+        })
+        .collect()
+}
+
+/// External monitors on the I2C buses of connected DRM connectors, for
+/// `ddc_backend = "i2c"`. Connectors whose bus doesn't answer a brightness
+/// read are dropped, except when the bus couldn't be opened at all, so the
+/// panel can say what's missing.
+fn get_i2c_monitors(
+    primary_output: Option<&str>,
+    modes: &HashMap<String, DisplayMode>,
+) -> Vec<Monitor> {
+    external_connectors()
+        .into_iter()
+        .filter_map(|connector| Some((connector_bus(&connector)?, connector)))
+        .zip(1..)
+        .filter_map(|((bus, connector), number)| {
+            if let Err(e) = (MonitorBackend::I2c { bus }).getvcp(VCP_BRIGHTNESS)
+                && !e.is::<SetupRequired>()
+            {
+                log::debug!("No DDC/CI on {connector} (i2c-{bus}): {e}");
+                return None;
             }
+            let name = ddc_monitor_name(None, Some(&connector), number);
+            let edid = connector_edid(&connector);
+            let primary = is_connector_for(Some(&connector), primary_output);
+            let display_mode = connector_mode(Some(&connector), modes);
+            let i2c_bus = format!("/dev/i2c-{bus}");
+            let display = DetectedDisplay {
+                number,
+                i2c_bus: Some(i2c_bus.clone()),
+                connector: Some(connector),
+                ..Default::default()
+            };
+            Some(Monitor {
+                id: ddc_monitor_id(&display, &name),
+                name,
+                serial: None,
+                i2c_bus: Some(i2c_bus),
+                min_brightness: None,
+                current_brightness: None,
+                max_brightness: None,
+                capabilities: None,
+                supports_brightness: true,
+                has_volume: false,
+                max_luminance: edid.as_deref().and_then(edid::max_luminance),
+                display_mode,
+                primary,
+                backend: MonitorBackend::I2c { bus },
+            })
+        })
+        .collect()
+}
+
+/// Connected external DRM connectors (`card0-DP-1`) from /sys/class/drm/,
+/// sorted by name. Built-in panels are driven through the backlight.
+fn external_connectors() -> Vec<String> {
+    connected_connectors(false)
+}
+
+/// Connected DRM connectors from /sys/class/drm/ that are built-in panels
+/// when `internal` is set, else external ones, sorted by name.
+fn connected_connectors(internal: bool) -> Vec<String> {
+    let Ok(entries) = fs::read_dir("/sys/class/drm") else {
+        return Vec::new();
+    };
+    let mut connectors: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|connector| {
+            let Some((_, output)) = connector.split_once('-') else {
+                return false;
+            };
+            let status = PathBuf::from("/sys/class/drm")
+                .join(connector)
+                .join("status");
+            is_internal_output(output) == internal
+                && fs::read_to_string(status).is_ok_and(|s| s.trim() == "connected")
+        })
+        .collect();
+    connectors.sort();
+    connectors
+}
+
+/// DRM connector (`card0-eDP-1`) of the panel a backlight device in
+/// /sys/class/backlight/ dims. i915 registers the device under its
+/// connector; amdgpu and others register it under the GPU, so there it's
+/// the built-in connector when only one is connected.
+fn backlight_connector(path: &Path) -> Option<String> {
+    let device = fs::canonicalize(path).ok()?;
+    let parent = device.parent()?;
+    if parent.join("edid").exists() {
+        return Some(parent.file_name()?.to_string_lossy().to_string());
+    }
+    let mut internal = connected_connectors(true);
+    (internal.len() == 1).then(|| internal.remove(0))
+}
+
+/// I2C bus a DRM connector's monitor is reached on: the adapter its `ddc`
+/// link points to, else (DisplayPort AUX) an `i2c-N` entry of its own.
+fn connector_bus(connector: &str) -> Option<u32> {
+    let dir = PathBuf::from("/sys/class/drm").join(connector);
+    let adapter = fs::read_link(dir.join("ddc"))
+        .ok()
+        .and_then(|link| Some(link.file_name()?.to_string_lossy().to_string()))
+        .or_else(|| {
+            fs::read_dir(&dir)
+                .ok()?
+                .flatten()
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .find(|name| name.starts_with("i2c-"))
+        })?;
+    bus_number(Some(&adapter))
+}
+
+/// Whether an output or connector name (`eDP-1`) is a built-in panel.
+fn is_internal_output(output: &str) -> bool {
+    ["eDP", "LVDS", "DSI"]
+        .iter()
+        .any(|kind| output.starts_with(kind))
+}
+
+/// Whether DRM `connector` (`card0-DP-1`) drives X11 `output` (`DP-1`). Like
+/// [`drm_connector`], only matches drivers that name outputs after connectors.
+fn is_connector_for(connector: Option<&str>, output: Option<&str>) -> bool {
+    let name = connector
+        .and_then(|connector| connector.split_once('-'))
+        .map(|(_, name)| name);
+    name.is_some() && name == output
+}
+
+/// Mode of the X11 output DRM `connector` (`card0-DP-1`) drives, matched the
+/// same way as [`is_connector_for`].
+fn connector_mode(
+    connector: Option<&str>,
+    modes: &HashMap<String, DisplayMode>,
+) -> Option<DisplayMode> {
+    let (_, output) = connector?.split_once('-')?;
+    modes.get(output).copied()
+}
+
+/// EDID block the kernel read from a DRM connector such as `card0-DP-1`.
+fn connector_edid(connector: &str) -> Option<Vec<u8>> {
+    fs::read(PathBuf::from("/sys/class/drm").join(connector).join("edid")).ok()
+}
 
-            current_display = rest.parse::<u32>().ok();
-            current_model = None;
-        } else if let Some(model) = trimmed.strip_prefix("Model:") {
-            current_model = Some(model.trim().to_string());
+/// Name for a monitor found by ddcutil: its model, else vendor and product
+/// from the EDID, else its display number.
+fn ddc_monitor_name(model: Option<String>, connector: Option<&str>, display_number: u32) -> String {
+    model
+        .filter(|model| !model.is_empty())
+        .or_else(|| {
+            let edid = connector.and_then(connector_edid)?;
+            edid::edid_device(&edid).map(|device| device.display_name())
+        })
+        .unwrap_or_else(|| format!("Display {display_number}"))
+}
+
+/// Stable id for a monitor found by ddcutil: parsed from the connector's
+/// EDID in sysfs, else ddcutil's summary of it (or the same built from the
+/// separate fields), else the DRM connector name, else the monitor's name.
+fn ddc_monitor_id(display: &DetectedDisplay, name: &str) -> String {
+    let from_fields = match (&display.manufacturer, &display.model) {
+        (Some(manufacturer), Some(model)) => Some(match display.serial() {
+            Some(serial) => format!("{manufacturer}:{model}:{serial}"),
+            None => format!("{manufacturer}:{model}"),
+        }),
+        _ => None,
+    };
+    display
+        .connector
+        .as_deref()
+        .and_then(connector_edid)
+        .as_deref()
+        .and_then(edid::stable_id)
+        .or_else(|| display.summary.clone())
+        .or(from_fields)
+        .or_else(|| display.connector.clone())
+        .unwrap_or_else(|| name.to_string())
+}
+
+/// Number monitors that share a name, e.g. two "DELL U2720Q" become
+/// "DELL U2720Q (#1)" and "DELL U2720Q (#2)". They're numbered in I2C bus
+/// order so the same physical monitor keeps its number from run to run.
+fn disambiguate_names(monitors: &mut [Monitor]) {
+    let mut order: Vec<usize> = (0..monitors.len()).collect();
+    order.sort_by_key(|&i| bus_number(monitors[i].i2c_bus.as_deref()).unwrap_or(u32::MAX));
+
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for &i in &order {
+        let total = monitors
+            .iter()
+            .filter(|m| m.name == monitors[i].name)
+            .count();
+        if total < 2 {
+            continue;
         }
+        let count = seen.entry(monitors[i].name.clone()).or_default();
+        *count += 1;
+        log::debug!(
+            "{} is #{count} (bus {:?}, serial {:?})",
+            monitors[i].name,
+            monitors[i].i2c_bus,
+            monitors[i].serial
+        );
+        monitors[i].name = format!("{} (#{count})", monitors[i].name);
     }
+}
 
-    // Don't forget the last display
-    if let (Some(num), Some(model)) = (current_display, current_model) {
-        monitors.push(Monitor {
-            name: model,
-            min_brightness: None,
-            current_brightness: None,
-            max_brightness: None,
-            backend: MonitorBackend::Ddc { display_number: num },
+/// `4` for `/dev/i2c-4`.
+fn bus_number(bus: Option<&str>) -> Option<u32> {
+    bus?.rsplit('-').next()?.parse().ok()
+}
+
+/// Connected X11 outputs, dimmed through their gamma ramps. Only used when
+/// nothing has hardware control. Wayland compositors don't let clients set
+/// gamma (XWayland takes a ramp and ignores it), so a Wayland session gets
+/// none.
+fn get_gamma_monitors(
+    primary_output: Option<&str>,
+    modes: &HashMap<String, DisplayMode>,
+) -> Vec<Monitor> {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        log::info!("No software brightness under Wayland");
+        return Vec::new();
+    }
+    let Some(display) = XDisplay::open() else {
+        return Vec::new();
+    };
+
+    connected_outputs(&display)
+        .into_iter()
+        .filter(|&(_, crtc)| unsafe { x11::xrandr::XRRGetCrtcGammaSize(display.0, crtc) } > 0)
+        .map(|(output, _)| {
+            let edid = drm_connector(&output).and_then(|connector| connector_edid(&connector));
+            let name = edid
+                .as_deref()
+                .and_then(edid::edid_device)
+                .map_or_else(|| output.clone(), |device| device.display_name());
+            Monitor {
+                id: edid
+                    .as_deref()
+                    .and_then(edid::stable_id)
+                    .unwrap_or_else(|| output.clone()),
+                name,
+                serial: None,
+                i2c_bus: None,
+                min_brightness: None,
+                current_brightness: None,
+                max_brightness: None,
+                capabilities: None,
+                supports_brightness: true,
+                has_volume: false,
+                max_luminance: edid.as_deref().and_then(edid::max_luminance),
+                display_mode: modes.get(&output).copied(),
+                primary: primary_output == Some(output.as_str()),
+                backend: MonitorBackend::Gamma { output },
+            }
+        })
+        .collect()
+}
+
+/// DRM connector (`card0-eDP-1`) for an X11 output name. Only the modesetting
+/// and Intel drivers name outputs after their connectors; others don't match.
+fn drm_connector(output: &str) -> Option<String> {
+    fs::read_dir("/sys/class/drm")
+        .ok()?
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .find(|connector| {
+            connector
+                .split_once('-')
+                .is_some_and(|(_, name)| name == output)
+        })
+}
+
+/// An Xlib connection for one round of gamma calls, closed on drop. Opened
+/// per call since a `Display` can't follow the monitor between threads.
+struct XDisplay(*mut x11::xlib::Display);
+
+impl XDisplay {
+    fn open() -> Option<Self> {
+        let display = unsafe { x11::xlib::XOpenDisplay(std::ptr::null()) };
+        (!display.is_null()).then_some(Self(display))
+    }
+}
+
+impl Drop for XDisplay {
+    fn drop(&mut self) {
+        unsafe { x11::xlib::XCloseDisplay(self.0) };
+    }
+}
+
+/// Name and CRTC of every connected output that's lit. Looked up afresh for
+/// each call: Xlib's default error handler exits the process, so a CRTC that
+/// went away since enumeration must never be passed in.
+fn connected_outputs(display: &XDisplay) -> Vec<(String, x11::xrandr::RRCrtc)> {
+    let mut outputs = Vec::new();
+    unsafe {
+        let root = x11::xlib::XDefaultRootWindow(display.0);
+        let resources = x11::xrandr::XRRGetScreenResourcesCurrent(display.0, root);
+        if resources.is_null() {
+            return outputs;
+        }
+        for i in 0..(*resources).noutput.max(0) as usize {
+            let info =
+                x11::xrandr::XRRGetOutputInfo(display.0, resources, *(*resources).outputs.add(i));
+            if info.is_null() {
+                continue;
+            }
+            if (*info).connection == x11::xrandr::RR_Connected as u16 && (*info).crtc != 0 {
+                let name = std::slice::from_raw_parts(
+                    (*info).name.cast::<u8>(),
+                    (*info).nameLen.max(0) as usize,
+                );
+                outputs.push((String::from_utf8_lossy(name).to_string(), (*info).crtc));
+            }
+            x11::xrandr::XRRFreeOutputInfo(info);
+        }
+        x11::xrandr::XRRFreeScreenResources(resources);
+    }
+    outputs
+}
+
+/// Name of X11's primary output (`DP-1`). `None` without an X server or
+/// when no output is marked primary, as under most Wayland compositors.
+fn primary_output() -> Option<String> {
+    let display = XDisplay::open()?;
+    unsafe {
+        let root = x11::xlib::XDefaultRootWindow(display.0);
+        let primary = x11::xrandr::XRRGetOutputPrimary(display.0, root);
+        if primary == 0 {
+            return None;
+        }
+        let resources = x11::xrandr::XRRGetScreenResourcesCurrent(display.0, root);
+        if resources.is_null() {
+            return None;
+        }
+        let info = x11::xrandr::XRRGetOutputInfo(display.0, resources, primary);
+        let mut name = None;
+        if !info.is_null() {
+            let bytes = std::slice::from_raw_parts(
+                (*info).name.cast::<u8>(),
+                (*info).nameLen.max(0) as usize,
+            );
+            name = Some(String::from_utf8_lossy(bytes).to_string());
+            x11::xrandr::XRRFreeOutputInfo(info);
+        }
+        x11::xrandr::XRRFreeScreenResources(resources);
+        name
+    }
+}
+
+/// Resolution and refresh rate of every lit X11 output, keyed by output
+/// name (`DP-1`). Empty without an X server.
+fn output_modes() -> HashMap<String, DisplayMode> {
+    let mut modes = HashMap::new();
+    let Some(display) = XDisplay::open() else {
+        return modes;
+    };
+    let outputs = connected_outputs(&display);
+    unsafe {
+        let root = x11::xlib::XDefaultRootWindow(display.0);
+        let resources = x11::xrandr::XRRGetScreenResourcesCurrent(display.0, root);
+        if resources.is_null() {
+            return modes;
+        }
+        let infos = if (*resources).modes.is_null() {
+            &[][..]
+        } else {
+            std::slice::from_raw_parts((*resources).modes, (*resources).nmode.max(0) as usize)
+        };
+        for (output, crtc) in outputs {
+            let info = x11::xrandr::XRRGetCrtcInfo(display.0, resources, crtc);
+            if info.is_null() {
+                continue;
+            }
+            let refresh_hz = infos
+                .iter()
+                .find(|mode| mode.id == (*info).mode)
+                .and_then(refresh_rate);
+            modes.insert(
+                output,
+                DisplayMode {
+                    width: (*info).width,
+                    height: (*info).height,
+                    refresh_hz,
+                },
+            );
+            x11::xrandr::XRRFreeCrtcInfo(info);
+        }
+        x11::xrandr::XRRFreeScreenResources(resources);
+    }
+    modes
+}
+
+/// Vertical refresh of an XRandR mode, worked out the way `xrandr` does.
+fn refresh_rate(mode: &x11::xrandr::XRRModeInfo) -> Option<f32> {
+    let mut lines = f64::from(mode.vTotal);
+    if mode.modeFlags & x11::xrandr::RR_DoubleScan as std::os::raw::c_ulong != 0 {
+        lines *= 2.0;
+    }
+    if mode.modeFlags & x11::xrandr::RR_Interlace as std::os::raw::c_ulong != 0 {
+        lines /= 2.0;
+    }
+    let dots = f64::from(mode.hTotal) * lines;
+    (dots > 0.0).then(|| (mode.dotClock as f64 / dots) as f32)
+}
+
+/// CRTC currently driving `output`, or an error if it's been unplugged or
+/// switched off.
+fn output_crtc(display: &XDisplay, output: &str) -> Result<x11::xrandr::RRCrtc, anyhow::Error> {
+    connected_outputs(display)
+        .into_iter()
+        .find_map(|(name, crtc)| (name == output).then_some(crtc))
+        .ok_or_else(|| anyhow::anyhow!("{output} is no longer connected"))
+}
+
+/// Brightness the gamma ramp of `output` is set to, as a 0-100 percentage.
+fn read_gamma(output: &str) -> Result<u32, anyhow::Error> {
+    let display = XDisplay::open().ok_or_else(|| anyhow::anyhow!("Can't open the X display"))?;
+    let crtc = output_crtc(&display, output)?;
+    unsafe {
+        let gamma = x11::xrandr::XRRGetCrtcGamma(display.0, crtc);
+        if gamma.is_null() {
+            return Err(anyhow::anyhow!("Can't read the gamma ramp of {output}"));
+        }
+        let size = (*gamma).size.max(0) as usize;
+        let top = (size > 0).then(|| *(*gamma).green.add(size - 1));
+        x11::xrandr::XRRFreeGamma(gamma);
+        top.map(gamma_percent)
+            .ok_or_else(|| anyhow::anyhow!("{output} has no gamma ramp"))
+    }
+}
+
+/// Dim `output` to `percent` through its CRTC's gamma ramp.
+fn write_gamma(output: &str, percent: u32) -> Result<(), anyhow::Error> {
+    let display = XDisplay::open().ok_or_else(|| anyhow::anyhow!("Can't open the X display"))?;
+    let crtc = output_crtc(&display, output)?;
+    unsafe {
+        let size = x11::xrandr::XRRGetCrtcGammaSize(display.0, crtc);
+        if size <= 0 {
+            return Err(anyhow::anyhow!("{output} has no gamma ramp"));
+        }
+        let gamma = x11::xrandr::XRRAllocGamma(size);
+        if gamma.is_null() {
+            return Err(anyhow::anyhow!("Can't allocate a gamma ramp"));
+        }
+        let ramp = gamma_ramp(size as usize, percent);
+        for channel in [(*gamma).red, (*gamma).green, (*gamma).blue] {
+            std::ptr::copy_nonoverlapping(ramp.as_ptr(), channel, ramp.len());
+        }
+        x11::xrandr::XRRSetCrtcGamma(display.0, crtc, gamma);
+        x11::xrandr::XRRFreeGamma(gamma);
+        x11::xlib::XSync(display.0, x11::xlib::False);
+    }
+    Ok(())
+}
+
+/// Laptop backlights from sysfs plus external monitors via ddcutil (or
+/// `/dev/i2c-*`, per the config's `ddc_backend`), falling back to software
+/// brightness when neither finds anything
+pub struct LinuxMonitorProvider {
+    ddc_backend: DdcBackend,
+}
+
+impl MonitorProvider for LinuxMonitorProvider {
+    type Monitor = Monitor;
+
+    fn new() -> Self {
+        Self {
+            ddc_backend: crate::config::Config::load().ddc_backend,
+        }
+    }
+
+    /// Get all available monitors (backlight + DDC, else gamma)
+    fn get_monitors(&mut self) -> Result<Vec<Monitor>, anyhow::Error> {
+        let primary = primary_output();
+        let primary = primary.as_deref();
+        let modes = output_modes();
+        let mut monitors = get_backlight_monitors(primary, &modes);
+        monitors.extend(match self.ddc_backend {
+            DdcBackend::Ddcutil => get_ddc_monitors(primary, &modes),
+            DdcBackend::I2c => get_i2c_monitors(primary, &modes),
         });
+        if monitors.is_empty() {
+            monitors = get_gamma_monitors(primary, &modes);
+        }
+        disambiguate_names(&mut monitors);
+
+        if monitors.is_empty() {
+            return Err(anyhow::anyhow!(
+                "No monitors found. Ensure /sys/class/backlight/ has entries or ddcutil is installed and can detect displays."
+            ));
+        }
+
+        // Probe optional features once so the UI doesn't keep asking
+        // monitors that don't have them
+        for monitor in &mut monitors {
+            monitor.supports_brightness = probe_brightness(monitor, &[]);
+            monitor.has_volume = monitor.read_volume().is_ok();
+        }
+
+        Ok(monitors)
     }
 
-    monitors
+    /// No-op on Linux (no handles to destroy)
+    fn cleanup_monitors(&mut self, _monitors: &mut Vec<Monitor>) {}
 }
 
-/// Get all available monitors (backlight + DDC)
-pub fn get_monitors() -> Result<Vec<Monitor>, anyhow::Error> {
-    let mut monitors = get_backlight_monitors();
-    monitors.extend(get_ddc_monitors());
+// =========================================================================
+// Ambient light (IIO sysfs)
+// =========================================================================
 
-    if monitors.is_empty() {
-        return Err(anyhow::anyhow!(
-            "No monitors found. Ensure /sys/class/backlight/ has entries or ddcutil is installed and can detect displays."
-        ));
+/// An IIO illuminance channel under /sys/bus/iio/devices/, as found on many
+/// laptops.
+pub struct LinuxAmbientSensor {
+    /// `in_illuminance_input` (already lux) or `in_illuminance_raw`.
+    path: PathBuf,
+    /// Converts raw readings to lux: `(raw + offset) * scale`.
+    offset: f32,
+    scale: f32,
+}
+
+impl AmbientSensor for LinuxAmbientSensor {
+    fn open() -> Option<Self> {
+        let entries = fs::read_dir("/sys/bus/iio/devices").ok()?;
+
+        for entry in entries.flatten() {
+            let dir = entry.path();
+            if !entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("iio:device")
+            {
+                continue;
+            }
+
+            let input = dir.join("in_illuminance_input");
+            if input.exists() {
+                return Some(Self {
+                    path: input,
+                    offset: 0.0,
+                    scale: 1.0,
+                });
+            }
+
+            let raw = dir.join("in_illuminance_raw");
+            if raw.exists() {
+                let read = |name: &str| {
+                    fs::read_to_string(dir.join(name))
+                        .ok()?
+                        .trim()
+                        .parse::<f32>()
+                        .ok()
+                };
+                return Some(Self {
+                    path: raw,
+                    offset: read("in_illuminance_offset").unwrap_or(0.0),
+                    scale: read("in_illuminance_scale").unwrap_or(1.0),
+                });
+            }
+        }
+
+        None
+    }
+
+    fn read_lux(&mut self) -> Option<f32> {
+        let raw: f32 = fs::read_to_string(&self.path).ok()?.trim().parse().ok()?;
+        Some((raw + self.offset) * self.scale)
+    }
+}
+
+// =========================================================================
+// Fullscreen detection
+// =========================================================================
+
+/// Not implemented on Linux yet, so the fullscreen brightness rule stays off.
+pub struct LinuxFullscreenDetector;
+
+impl FullscreenDetector for LinuxFullscreenDetector {
+    fn open() -> Option<Self> {
+        None
+    }
+
+    fn is_fullscreen(&mut self) -> bool {
+        false
+    }
+}
+
+// =========================================================================
+// Battery (power_supply sysfs)
+// =========================================================================
+
+/// The system battery under /sys/class/power_supply/. Wireless mice and
+/// the like list their batteries there too, with `scope` set to `Device`;
+/// those are skipped.
+pub struct LinuxBattery {
+    path: PathBuf,
+}
+
+impl Battery for LinuxBattery {
+    fn open() -> Option<Self> {
+        let mut supplies: Vec<PathBuf> = fs::read_dir("/sys/class/power_supply")
+            .ok()?
+            .flatten()
+            .map(|entry| entry.path())
+            .collect();
+        supplies.sort();
+
+        supplies
+            .into_iter()
+            .find(|dir| {
+                let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap_or_default();
+                read("type").trim() == "Battery"
+                    && read("scope").trim() != "Device"
+                    && dir.join("capacity").exists()
+            })
+            .map(|path| Self { path })
+    }
+
+    fn charge_on_battery(&mut self) -> Option<u32> {
+        // "Not charging" and "Full" mean mains power as much as "Charging"
+        let status = fs::read_to_string(self.path.join("status")).ok()?;
+        if status.trim() != "Discharging" {
+            return None;
+        }
+        fs::read_to_string(self.path.join("capacity"))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    }
+}
+
+// =========================================================================
+// Power events
+// =========================================================================
+
+/// Not implemented on Linux yet; monitors keep whatever brightness they
+/// come back from sleep with.
+pub struct LinuxPowerEvents;
+
+impl PowerEvents for LinuxPowerEvents {
+    fn watch(_on_event: Box<dyn Fn() + Send + Sync>) {}
+}
+
+// =========================================================================
+// Notifications
+// =========================================================================
+
+/// Not implemented on Linux yet.
+pub struct LinuxNotifier;
+
+impl Notifier for LinuxNotifier {
+    fn notify(_message: &str) {}
+}
+
+// =========================================================================
+// Brightness keys
+// =========================================================================
+
+/// Desktops handle brightness keys themselves; `--dbus` lets them drive
+/// this app instead.
+pub struct LinuxBrightnessKeys;
+
+impl BrightnessKeys for LinuxBrightnessKeys {
+    fn watch(_on_key: Box<dyn Fn(bool) + Send + Sync>) -> Result<(), anyhow::Error> {
+        Err(anyhow::anyhow!(
+            "brightness keys are only forwarded on macOS"
+        ))
     }
+}
 
-    Ok(monitors)
+// =========================================================================
+// Launch at login (XDG autostart)
+// =========================================================================
+
+pub struct LinuxAutostartManager {
+    /// `~/.config/autostart/tray-bright.desktop`, or `None` if neither
+    /// `XDG_CONFIG_HOME` nor `HOME` is set.
+    desktop_file: Option<PathBuf>,
+}
+
+impl LinuxAutostartManager {
+    /// `Exec=` value for the running binary, quoted per the desktop entry
+    /// spec when the path contains spaces.
+    fn exec_line() -> Option<String> {
+        let exe = std::env::current_exe().ok()?;
+        let exe = exe.to_string_lossy();
+        if exe.contains(' ') {
+            Some(format!("\"{exe}\""))
+        } else {
+            Some(exe.into_owned())
+        }
+    }
 }
 
-/// No-op on Linux (no handles to destroy)
-pub fn cleanup_monitors(_monitors: &mut Vec<Monitor>) {}
+impl AutostartManager for LinuxAutostartManager {
+    fn new() -> Self {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+
+        Self {
+            desktop_file: config_dir.map(|dir| dir.join("autostart").join("tray-bright.desktop")),
+        }
+    }
+
+    fn is_startup_enabled(&self) -> bool {
+        let (Some(path), Some(exec)) = (&self.desktop_file, Self::exec_line()) else {
+            return false;
+        };
+
+        fs::read_to_string(path).is_ok_and(|contents| {
+            contents
+                .lines()
+                .filter_map(|line| line.strip_prefix("Exec="))
+                .any(|v| v.trim().trim_end_matches(HIDDEN_ARG).trim_end() == exec)
+        })
+    }
+
+    fn set_startup_enabled(&self, enabled: bool) -> bool {
+        let Some(path) = &self.desktop_file else {
+            return false;
+        };
+
+        if !enabled {
+            return match fs::remove_file(path) {
+                Ok(()) => true,
+                Err(e) => e.kind() == std::io::ErrorKind::NotFound,
+            };
+        }
+
+        let Some(exec) = Self::exec_line() else {
+            return false;
+        };
+
+        if let Some(dir) = path.parent()
+            && fs::create_dir_all(dir).is_err()
+        {
+            return false;
+        }
+
+        let entry = format!(
+            "[Desktop Entry]\n\
+             Type=Application\n\
+             Name=Tray Bright\n\
+             Comment=System tray brightness control for external monitors\n\
+             Exec={exec} {HIDDEN_ARG}\n\
+             Icon=tray-bright\n\
+             Terminal=false\n\
+             X-GNOME-Autostart-enabled=true\n"
+        );
+
+        fs::write(path, entry).is_ok()
+    }
+}
 
 // =========================================================================
 // Window visibility (X11)
 // =========================================================================
 
+/// How the window is shown and hidden. Chosen from the raw handle eframe
+/// gives us, which reflects the session winit actually connected to (a
+/// Wayland session may still run us under XWayland).
+enum WindowBackend {
+    /// X11: map/unmap the window through our own Xlib connection.
+    Xlib {
+        display: *mut x11::xlib::Display,
+        window: std::ffi::c_ulong,
+    },
+    /// Wayland has no client-side map/unmap, so visibility goes through
+    /// eframe's viewport commands.
+    Wayland { ctx: egui::Context },
+}
+
 pub struct LinuxWindowController {
-    display: *mut x11::xlib::Display,
-    window: std::ffi::c_ulong,
+    backend: WindowBackend,
     visible: Mutex<bool>,
 }
 
@@ -217,25 +1653,44 @@ unsafe impl Send for LinuxWindowController {}
 unsafe impl Sync for LinuxWindowController {}
 
 impl WindowController for LinuxWindowController {
-    fn from_raw_handle(handle: RawWindowHandle) -> Option<Self> {
-        if let RawWindowHandle::Xlib(h) = handle {
-            let display = h.display.map_or(std::ptr::null_mut(), |p| p.as_ptr());
-            Some(Self {
-                display: display as *mut x11::xlib::Display,
-                window: h.window,
-                visible: Mutex::new(true),
-            })
-        } else {
-            None
-        }
+    fn from_raw_handle(handle: RawWindowHandle, ctx: &egui::Context) -> Option<Self> {
+        let backend = match handle {
+            RawWindowHandle::Xlib(h) => {
+                // The window handle doesn't carry the display connection, so open
+                // our own. Window IDs are server-side and valid on any connection.
+                let display = unsafe { x11::xlib::XOpenDisplay(std::ptr::null()) };
+                if display.is_null() {
+                    return None;
+                }
+                WindowBackend::Xlib {
+                    display,
+                    window: h.window,
+                }
+            }
+            RawWindowHandle::Wayland(_) => WindowBackend::Wayland { ctx: ctx.clone() },
+            _ => return None,
+        };
+
+        Some(Self {
+            backend,
+            visible: Mutex::new(true),
+        })
     }
 
     fn show(&self) {
         let mut vis = self.visible.lock().unwrap();
         if !*vis {
-            unsafe {
-                x11::xlib::XMapRaised(self.display, self.window);
-                x11::xlib::XFlush(self.display);
+            match &self.backend {
+                WindowBackend::Xlib { display, window } => unsafe {
+                    x11::xlib::XMapRaised(*display, *window);
+                    x11::xlib::XFlush(*display);
+                },
+                WindowBackend::Wayland { ctx } => {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
+                    // The event loop may be idle while hidden; wake it so the
+                    // command is processed now
+                    ctx.request_repaint();
+                }
             }
             *vis = true;
         }
@@ -244,9 +1699,15 @@ impl WindowController for LinuxWindowController {
     fn hide(&self) {
         let mut vis = self.visible.lock().unwrap();
         if *vis {
-            unsafe {
-                x11::xlib::XUnmapWindow(self.display, self.window);
-                x11::xlib::XFlush(self.display);
+            match &self.backend {
+                WindowBackend::Xlib { display, window } => unsafe {
+                    x11::xlib::XUnmapWindow(*display, *window);
+                    x11::xlib::XFlush(*display);
+                },
+                WindowBackend::Wayland { ctx } => {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Visible(false));
+                    ctx.request_repaint();
+                }
             }
             *vis = false;
         }
@@ -272,3 +1733,150 @@ impl WindowController for LinuxWindowController {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DETECT: &str = "\
+Display 1
+   I2C bus:  /dev/i2c-4
+   DRM connector:           card0-DP-1
+   EDID synopsis:
+      Mfg id:               DEL - Dell Inc.
+      Model:                DELL U2720Q
+      Product code:         41380  (0xa1a4)
+      Serial number:        7XYZ123
+      Binary serial number: 1234567 (0x0012d687)
+   VCP version:         2.1
+
+Invalid display
+   I2C bus:  /dev/i2c-5
+   DRM connector:           card0-DP-2
+   EDID synopsis:
+      Mfg id:               DEL - Dell Inc.
+      Model:                DELL U2720Q
+   DDC communication failed
+
+Display 2
+   I2C bus:  /dev/i2c-7
+   EDID synopsis:
+      Mfg id:               DEL - Dell Inc.
+      Model:                DELL U2720Q
+      Serial number:
+      Binary serial number: 7654321 (0x0074cbb1)
+";
+
+    #[test]
+    fn connectors_match_outputs_by_name() {
+        assert!(is_connector_for(Some("card0-DP-1"), Some("DP-1")));
+        assert!(!is_connector_for(Some("card0-DP-1"), Some("DP-2")));
+        assert!(!is_connector_for(Some("card0-DP-1"), None));
+        assert!(!is_connector_for(None, None));
+    }
+
+    #[test]
+    fn refresh_rate_follows_the_mode_timings() {
+        // CEA 1080p60
+        let mut mode: x11::xrandr::XRRModeInfo = unsafe { std::mem::zeroed() };
+        mode.dotClock = 148_500_000;
+        mode.hTotal = 2200;
+        mode.vTotal = 1125;
+        assert_eq!(refresh_rate(&mode), Some(60.0));
+        mode.modeFlags = x11::xrandr::RR_Interlace as std::os::raw::c_ulong;
+        assert_eq!(refresh_rate(&mode), Some(120.0));
+        mode.vTotal = 0;
+        assert_eq!(refresh_rate(&mode), None);
+    }
+
+    #[test]
+    fn backlight_reads_as_current_min_max() {
+        let path =
+            std::env::temp_dir().join(format!("tray-bright-backlight-{}", std::process::id()));
+        fs::create_dir_all(&path).unwrap();
+        fs::write(path.join("max_brightness"), "200\n").unwrap();
+        fs::write(path.join("brightness"), "50\n").unwrap();
+
+        let mut monitor = Monitor {
+            name: "intel_backlight".to_string(),
+            id: "intel_backlight".to_string(),
+            serial: None,
+            i2c_bus: None,
+            min_brightness: None,
+            current_brightness: None,
+            max_brightness: None,
+            capabilities: None,
+            supports_brightness: true,
+            has_volume: false,
+            max_luminance: None,
+            display_mode: None,
+            primary: false,
+            backend: MonitorBackend::Backlight { path: path.clone() },
+        };
+        let level = monitor.poll_brightness();
+        fs::remove_dir_all(&path).unwrap();
+
+        assert_eq!(level.unwrap(), (25, 0, 100));
+    }
+
+    #[test]
+    fn parses_detect_blocks_and_skips_invalid_ones() {
+        let displays = parse_ddc_detect(DETECT);
+        assert_eq!(displays.len(), 2);
+
+        assert_eq!(
+            displays[0],
+            DetectedDisplay {
+                number: 1,
+                i2c_bus: Some("/dev/i2c-4".to_string()),
+                connector: Some("card0-DP-1".to_string()),
+                manufacturer: Some("DEL".to_string()),
+                model: Some("DELL U2720Q".to_string()),
+                serial_text: Some("7XYZ123".to_string()),
+                serial_number: Some(1234567),
+                summary: None,
+            }
+        );
+        assert_eq!(displays[0].serial().as_deref(), Some("7XYZ123"));
+
+        assert_eq!(displays[1].number, 2);
+        assert_eq!(displays[1].connector, None);
+        assert_eq!(displays[1].serial().as_deref(), Some("7654321"));
+        assert_eq!(
+            ddc_monitor_id(&displays[1], "DELL U2720Q"),
+            "DEL:DELL U2720Q:7654321"
+        );
+    }
+
+    #[test]
+    fn no_displays_means_no_monitors() {
+        assert!(parse_ddc_detect("Display not found\n").is_empty());
+        assert!(parse_ddc_detect("").is_empty());
+    }
+
+    #[test]
+    fn parses_bus_number() {
+        assert_eq!(bus_number(Some("/dev/i2c-4")), Some(4));
+        assert_eq!(bus_number(Some("/dev/i2c-12")), Some(12));
+        assert_eq!(bus_number(Some("/dev/i2c-")), None);
+        assert_eq!(bus_number(None), None);
+    }
+
+    #[test]
+    fn ddc_replies_are_checked_before_their_payload_is_used() {
+        // Brightness 50 of 100
+        let mut reply = [
+            0x6e, 0x88, 0x02, 0x00, 0x10, 0x00, 0x00, 0x64, 0x00, 0x32, 0xf2, 0x00,
+        ];
+        assert_eq!(
+            ddc_reply(&mut reply).unwrap(),
+            [0x02, 0x00, 0x10, 0x00, 0x00, 0x64, 0x00, 0x32]
+        );
+
+        let mut corrupt = reply;
+        corrupt[9] = 0x33;
+        assert!(ddc_reply(&mut corrupt).is_err());
+        assert!(ddc_reply(&mut reply[..6]).is_err());
+        assert!(ddc_reply(&mut [0xff; 12]).is_err());
+    }
+}
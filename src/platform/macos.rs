@@ -1,10 +1,24 @@
-use std::sync::Mutex;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::fs;
+use std::path::PathBuf;
+use std::ptr::NonNull;
+use std::sync::{Mutex, mpsc};
 
 use ddc::Ddc;
 use ddc_macos::Monitor as DdcMonitor;
+use eframe::egui;
 use raw_window_handle::RawWindowHandle;
 
-use crate::os::WindowController;
+use crate::edid;
+use crate::monitors::{percent_of_range, probe_brightness, range_to_percent, retry_with_backoff};
+use crate::os::{
+    AmbientSensor, AutostartManager, Battery, BrightnessKeys, DisplayMode, FullscreenDetector,
+    HIDDEN_ARG, MonitorHandle, MonitorProvider, Notifier, PowerEvents, SetupRequired,
+    WindowController,
+};
+use crate::vcp;
 
 // =========================================================================
 // Monitor brightness (DDC/CI via IOKit)
@@ -13,11 +27,34 @@ use crate::os::WindowController;
 /// VCP feature code for luminance (brightness).
 const VCP_BRIGHTNESS: u8 = 0x10;
 
+/// VCP feature code for contrast.
+const VCP_CONTRAST: u8 = 0x12;
+
+/// VCP feature code for the built-in speaker volume.
+const VCP_VOLUME: u8 = 0x62;
+
 pub struct Monitor {
     pub name: String,
+    /// From the EDID, else `name:serial`, else the name.
+    id: String,
+    /// Raw hardware range from VCP 0x10. Callers only ever see 0-100.
     pub min_brightness: Option<u32>,
+    /// Last known brightness as a 0-100 percentage.
     pub current_brightness: Option<u32>,
     pub max_brightness: Option<u32>,
+    /// Raw MCCS capability string, shared with the provider's cache.
+    /// `None` if the monitor didn't return one.
+    capabilities: Option<String>,
+    /// VCP codes listed in the capability string.
+    features: Vec<u8>,
+    /// See [`MonitorHandle::supports_brightness`]. Probed on discovery.
+    supports_brightness: bool,
+    /// Whether VCP 0x62 (speaker volume) answered when probed.
+    has_volume: bool,
+    /// Peak luminance from the EDID, for HDR displays.
+    max_luminance: Option<u32>,
+    /// Read from CoreGraphics at enumeration.
+    display_mode: Option<DisplayMode>,
     ddc: DdcMonitor,
 }
 
@@ -26,74 +63,583 @@ unsafe impl Send for Monitor {}
 unsafe impl Sync for Monitor {}
 
 impl Monitor {
-    pub fn poll_brightness_values(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
-        let vcp = self.ddc.get_vcp_feature(VCP_BRIGHTNESS)?;
-        let current = vcp.value() as u32;
+    /// VCP feature codes the monitor advertises. Empty when it has no
+    /// usable capability string, in which case features have to be probed.
+    pub fn supported_features(&self) -> &[u8] {
+        &self.features
+    }
+
+    fn read_capabilities(ddc: &mut DdcMonitor) -> Option<String> {
+        match ddc.capabilities_string() {
+            Ok(raw) => Some(String::from_utf8_lossy(&raw).to_string()),
+            Err(e) => {
+                log::debug!("Capability string unavailable: {e}");
+                None
+            }
+        }
+    }
+
+    fn read_volume(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+        let vcp = self.ddc.get_vcp_feature(VCP_VOLUME)?;
         let max = vcp.maximum() as u32;
 
+        if max == 0 {
+            return Err(anyhow::anyhow!("Monitor does not report a volume range"));
+        }
+
+        Ok((vcp.value() as u32, 0, max))
+    }
+}
+
+impl MonitorHandle for Monitor {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn poll_brightness(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+        let vcp = retry_with_backoff(|| Ok(self.ddc.get_vcp_feature(VCP_BRIGHTNESS)?))?;
+        let max = vcp.maximum() as u32;
+
+        // Normalize to 0-100 range; the raw max is kept for writes
+        let current = range_to_percent(vcp.value() as u32, 0, max);
+
         self.min_brightness = Some(0);
         self.current_brightness = Some(current);
         self.max_brightness = Some(max);
 
-        Ok((current, 0, max))
+        Ok((current, 0, 100))
     }
 
-    pub fn set_brightness(&mut self, value: u32) -> Result<(), anyhow::Error> {
+    fn set_brightness(&mut self, value: u32) -> Result<(), anyhow::Error> {
         let max = self.max_brightness.unwrap_or(100);
         let min = self.min_brightness.unwrap_or(0);
-        let clamped = value.clamp(min, max);
+        let clamped = value.min(100);
+        let raw_value = percent_of_range(min, max, clamped);
 
-        self.ddc.set_vcp_feature(VCP_BRIGHTNESS, clamped as u16)?;
+        self.ddc.set_vcp_feature(VCP_BRIGHTNESS, raw_value as u16)?;
         self.current_brightness = Some(clamped);
         Ok(())
     }
+
+    fn poll_contrast(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+        let vcp = self.ddc.get_vcp_feature(VCP_CONTRAST)?;
+        let max = vcp.maximum() as u32;
+
+        if max == 0 {
+            return Err(anyhow::anyhow!("Monitor does not report a contrast range"));
+        }
+
+        Ok((vcp.value() as u32, 0, max))
+    }
+
+    fn set_contrast(&mut self, value: u32) -> Result<(), anyhow::Error> {
+        self.ddc.set_vcp_feature(VCP_CONTRAST, value as u16)?;
+        Ok(())
+    }
+
+    fn list_color_presets(&mut self) -> Vec<(u8, String)> {
+        self.capabilities
+            .as_deref()
+            .map(vcp::color_presets)
+            .unwrap_or_default()
+    }
+
+    fn poll_color_preset(&mut self) -> Result<u8, anyhow::Error> {
+        let vcp = self.ddc.get_vcp_feature(vcp::COLOR_PRESET)?;
+        Ok(vcp.value() as u8)
+    }
+
+    fn set_color_preset(&mut self, preset: u8) -> Result<(), anyhow::Error> {
+        self.ddc.set_vcp_feature(vcp::COLOR_PRESET, preset as u16)?;
+        Ok(())
+    }
+
+    fn supports_brightness(&self) -> bool {
+        self.supports_brightness
+    }
+
+    fn has_volume(&self) -> bool {
+        self.has_volume
+    }
+
+    fn poll_volume(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+        if !self.has_volume {
+            return Err(anyhow::anyhow!("Monitor does not support volume"));
+        }
+        self.read_volume()
+    }
+
+    fn set_volume(&mut self, value: u32) -> Result<(), anyhow::Error> {
+        self.ddc.set_vcp_feature(VCP_VOLUME, value as u16)?;
+        Ok(())
+    }
+
+    fn get_vcp(&mut self, code: u8) -> Result<(u16, u16), anyhow::Error> {
+        let vcp = self.ddc.get_vcp_feature(code)?;
+        Ok((vcp.value(), vcp.maximum()))
+    }
+
+    fn set_vcp(&mut self, code: u8, value: u16) -> Result<(), anyhow::Error> {
+        self.ddc.set_vcp_feature(code, value)?;
+        Ok(())
+    }
+
+    fn set_power_mode(&mut self, mode: u8) -> Result<(), anyhow::Error> {
+        self.ddc.set_vcp_feature(vcp::POWER_MODE, mode.into())?;
+        Ok(())
+    }
+
+    fn capabilities(&mut self) -> Result<String, anyhow::Error> {
+        self.capabilities
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Monitor did not report a capability string"))
+    }
+
+    fn is_software(&self) -> bool {
+        false
+    }
+
+    fn is_primary(&self) -> bool {
+        self.ddc.handle().id == objc2_core_graphics::CGMainDisplayID()
+    }
+
+    fn max_luminance(&self) -> Option<u32> {
+        self.max_luminance
+    }
+
+    fn display_mode(&self) -> Option<DisplayMode> {
+        self.display_mode
+    }
 }
 
-/// Discover DDC-capable external monitors.
-pub fn get_monitors() -> Result<Vec<Monitor>, anyhow::Error> {
-    // Ensure NSApplication is initialised before accessing CoreGraphics APIs.
-    // DdcMonitor::enumerate() calls CGDisplay::active_displays() internally,
-    // which requires the CGS window-server connection that NSApplication sets up.
-    // Without this, macOS fires: "Assertion Failed (CGAtomicGet), CGSConnectionByID".
-    {
-        use objc2::MainThreadMarker;
-        use objc2_app_kit::NSApplication;
-        if let Some(mtm) = MainThreadMarker::new() {
-            let _ = NSApplication::sharedApplication(mtm);
-        }
-    }
-
-    let ddc_monitors = DdcMonitor::enumerate()?;
-
-    if ddc_monitors.is_empty() {
-        return Err(anyhow::anyhow!(
-            "No DDC-capable monitors found. Built-in displays do not support DDC/CI — \
-             connect an external monitor that supports DDC."
-        ));
-    }
-
-    let monitors: Vec<Monitor> = ddc_monitors
-        .into_iter()
-        .enumerate()
-        .map(|(i, ddc)| {
-            let name = ddc
-                .product_name()
-                .unwrap_or_else(|| format!("Monitor {}", i + 1));
-            Monitor {
-                name,
-                min_brightness: None,
-                current_brightness: None,
-                max_brightness: None,
-                ddc,
+/// Current mode of a CoreGraphics display, in pixels rather than points.
+/// Built-in panels report a refresh rate of 0.
+fn display_mode(display: u32) -> Option<DisplayMode> {
+    use objc2_core_graphics::{CGDisplayCopyDisplayMode, CGDisplayMode};
+
+    let mode = CGDisplayCopyDisplayMode(display)?;
+    let refresh = CGDisplayMode::refresh_rate(Some(&mode));
+    Some(DisplayMode {
+        width: CGDisplayMode::pixel_width(Some(&mode)) as u32,
+        height: CGDisplayMode::pixel_height(Some(&mode)) as u32,
+        refresh_hz: (refresh > 0.0).then_some(refresh as f32),
+    })
+}
+
+/// DDC-capable external monitors enumerated through IOKit.
+pub struct MacMonitorProvider {
+    /// Capability strings keyed by CoreGraphics display ID. Fetching one
+    /// takes a second or more per monitor, so re-enumeration reuses them.
+    /// Failed reads are not cached and get retried on the next enumeration.
+    capabilities: HashMap<u32, String>,
+}
+
+impl MonitorProvider for MacMonitorProvider {
+    type Monitor = Monitor;
+
+    fn new() -> Self {
+        Self {
+            capabilities: HashMap::new(),
+        }
+    }
+
+    fn get_monitors(&mut self) -> Result<Vec<Monitor>, anyhow::Error> {
+        // Ensure NSApplication is initialised before accessing CoreGraphics APIs.
+        // DdcMonitor::enumerate() calls CGDisplay::active_displays() internally,
+        // which requires the CGS window-server connection that NSApplication sets up.
+        // Without this, macOS fires: "Assertion Failed (CGAtomicGet), CGSConnectionByID".
+        {
+            use objc2::MainThreadMarker;
+            use objc2_app_kit::NSApplication;
+            if let Some(mtm) = MainThreadMarker::new() {
+                let _ = NSApplication::sharedApplication(mtm);
+            }
+        }
+
+        let ddc_monitors = DdcMonitor::enumerate()?;
+
+        // Forget monitors that have been unplugged
+        let ids: Vec<u32> = ddc_monitors.iter().map(|ddc| ddc.handle().id).collect();
+        self.capabilities.retain(|id, _| ids.contains(id));
+
+        if ddc_monitors.is_empty() {
+            return Err(anyhow::anyhow!(
+                "No DDC-capable monitors found. Built-in displays do not support DDC/CI — \
+                 connect an external monitor that supports DDC."
+            ));
+        }
+
+        let mut monitors: Vec<Monitor> = ddc_monitors
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut ddc)| {
+                let edid = ddc.edid();
+                let name = ddc
+                    .product_name()
+                    .or_else(|| {
+                        edid.as_deref()
+                            .and_then(edid::edid_device)
+                            .map(|device| device.display_name())
+                    })
+                    .unwrap_or_else(|| format!("Monitor {}", i + 1));
+                let display_id = ddc.handle().id;
+                let capabilities = match self.capabilities.get(&display_id) {
+                    Some(caps) => Some(caps.clone()),
+                    None => {
+                        let caps = Monitor::read_capabilities(&mut ddc);
+                        if let Some(caps) = &caps {
+                            self.capabilities.insert(display_id, caps.clone());
+                        }
+                        caps
+                    }
+                };
+                let features = capabilities
+                    .as_deref()
+                    .map(vcp::parse_vcp_features)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(code, _)| code)
+                    .collect();
+                let id = edid
+                    .as_deref()
+                    .and_then(edid::stable_id)
+                    .unwrap_or_else(|| match ddc.serial_number() {
+                        Some(serial) if !serial.is_empty() => format!("{name}:{serial}"),
+                        _ => name.clone(),
+                    });
+                Monitor {
+                    name,
+                    id,
+                    min_brightness: None,
+                    current_brightness: None,
+                    max_brightness: None,
+                    capabilities,
+                    features,
+                    supports_brightness: true,
+                    has_volume: false,
+                    max_luminance: edid.as_deref().and_then(edid::max_luminance),
+                    display_mode: display_mode(ddc.handle().id),
+                    ddc,
+                }
+            })
+            .collect();
+
+        // Probe optional features once so the UI doesn't keep asking
+        // monitors that don't have them. Skip the probe when the capability
+        // string rules the feature out.
+        for monitor in &mut monitors {
+            let features = monitor.supported_features().to_vec();
+            monitor.supports_brightness = probe_brightness(monitor, &features);
+            monitor.has_volume = (features.is_empty() || features.contains(&VCP_VOLUME))
+                && monitor.read_volume().is_ok();
+        }
+
+        Ok(monitors)
+    }
+
+    /// No-op on macOS (no handles to destroy).
+    fn cleanup_monitors(&mut self, _monitors: &mut Vec<Monitor>) {}
+}
+
+// =========================================================================
+// Ambient light
+// =========================================================================
+
+/// macOS has no public API for the ambient light sensor, so ambient
+/// auto-brightness is left to the OS there.
+pub struct MacAmbientSensor;
+
+impl AmbientSensor for MacAmbientSensor {
+    fn open() -> Option<Self> {
+        None
+    }
+
+    fn read_lux(&mut self) -> Option<f32> {
+        None
+    }
+}
+
+// =========================================================================
+// Fullscreen detection
+// =========================================================================
+
+/// Not implemented on macOS yet, so the fullscreen brightness rule stays off.
+pub struct MacFullscreenDetector;
+
+impl FullscreenDetector for MacFullscreenDetector {
+    fn open() -> Option<Self> {
+        None
+    }
+
+    fn is_fullscreen(&mut self) -> bool {
+        false
+    }
+}
+
+// =========================================================================
+// Battery
+// =========================================================================
+
+/// Not implemented on macOS yet, so the battery cap stays off.
+pub struct MacBattery;
+
+impl Battery for MacBattery {
+    fn open() -> Option<Self> {
+        None
+    }
+
+    fn charge_on_battery(&mut self) -> Option<u32> {
+        None
+    }
+}
+
+// =========================================================================
+// Power events
+// =========================================================================
+
+/// Not implemented on macOS yet; monitors keep whatever brightness they
+/// come back from sleep with.
+pub struct MacPowerEvents;
+
+impl PowerEvents for MacPowerEvents {
+    fn watch(_on_event: Box<dyn Fn() + Send + Sync>) {}
+}
+
+// =========================================================================
+// Notifications
+// =========================================================================
+
+/// Not implemented on macOS yet.
+pub struct MacNotifier;
+
+impl Notifier for MacNotifier {
+    fn notify(_message: &str) {}
+}
+
+// =========================================================================
+// Brightness keys (CGEventTap)
+// =========================================================================
+
+/// Event type of media and brightness keys (`NX_SYSDEFINED`).
+const NX_SYSDEFINED: u32 = 14;
+
+/// `NSEvent` subtype for media and brightness keys
+/// (`NX_SUBTYPE_AUX_CONTROL_BUTTONS`).
+const AUX_CONTROL_BUTTONS: i16 = 8;
+
+/// Key codes in `data1` (`NX_KEYTYPE_BRIGHTNESS_UP`/`_DOWN`).
+const KEY_BRIGHTNESS_UP: isize = 2;
+const KEY_BRIGHTNESS_DOWN: isize = 3;
+
+/// Key state in `data1` for a press or auto-repeat, as opposed to a release.
+const KEY_STATE_DOWN: isize = 0xA;
+
+const INPUT_MONITORING_MISSING: &str =
+    "Allow Tray Bright under Privacy & Security > Input Monitoring to use the brightness keys";
+
+/// Listens to the keyboard's brightness keys with a listen-only event tap on
+/// a thread of its own. Listen-only taps need Input Monitoring access
+/// rather than full Accessibility, and can't swallow the key, so the
+/// built-in display still changes too.
+pub struct MacBrightnessKeys;
+
+/// The tap callback's context. Leaked, since the tap lives as long as the
+/// app.
+type KeyCallback = Box<dyn Fn(bool) + Send + Sync>;
+
+thread_local! {
+    /// The tap, on the thread running it, so the callback can turn it back
+    /// on when macOS disables it.
+    static TAP: RefCell<Option<objc2_core_foundation::CFRetained<objc2_core_foundation::CFMachPort>>> =
+        const { RefCell::new(None) };
+}
+
+impl BrightnessKeys for MacBrightnessKeys {
+    fn watch(on_key: Box<dyn Fn(bool) + Send + Sync>) -> Result<(), anyhow::Error> {
+        use objc2_core_foundation::{CFMachPort, CFRunLoop, kCFRunLoopCommonModes};
+        use objc2_core_graphics::{
+            CGEvent, CGEventTapLocation, CGEventTapOptions, CGEventTapPlacement,
+            CGPreflightListenEventAccess, CGRequestListenEventAccess,
+        };
+
+        if !CGPreflightListenEventAccess() {
+            // Adds the app to the list in System Settings, and prompts once
+            CGRequestListenEventAccess();
+            return Err(SetupRequired(INPUT_MONITORING_MISSING).into());
+        }
+
+        let context: &'static KeyCallback = Box::leak(Box::new(on_key));
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let port = unsafe {
+                CGEvent::tap_create(
+                    CGEventTapLocation::SessionEventTap,
+                    CGEventTapPlacement::HeadInsertEventTap,
+                    CGEventTapOptions::ListenOnly,
+                    1 << NX_SYSDEFINED,
+                    Some(on_tap_event),
+                    context as *const KeyCallback as *mut c_void,
+                )
+            };
+            let Some(port) = port else {
+                let _ = tx.send(Err(anyhow::anyhow!("CGEventTapCreate failed")));
+                return;
+            };
+            let Some(source) = CFMachPort::new_run_loop_source(None, Some(&port), 0) else {
+                let _ = tx.send(Err(anyhow::anyhow!(
+                    "can't add the event tap to a run loop"
+                )));
+                return;
+            };
+            let Some(run_loop) = CFRunLoop::current() else {
+                let _ = tx.send(Err(anyhow::anyhow!("no run loop for the event tap")));
+                return;
+            };
+            run_loop.add_source(Some(&source), unsafe { kCFRunLoopCommonModes });
+            TAP.with(|tap| *tap.borrow_mut() = Some(port));
+            let _ = tx.send(Ok(()));
+            CFRunLoop::run();
+        });
+        rx.recv()?
+    }
+}
+
+/// Forward brightness key presses, and re-enable the tap when macOS turns
+/// it off for being slow or on user request. Always passes the event on.
+unsafe extern "C-unwind" fn on_tap_event(
+    _proxy: objc2_core_graphics::CGEventTapProxy,
+    kind: objc2_core_graphics::CGEventType,
+    event: NonNull<objc2_core_graphics::CGEvent>,
+    user_info: *mut c_void,
+) -> *mut objc2_core_graphics::CGEvent {
+    use objc2_core_graphics::{CGEvent, CGEventType};
+
+    let on_key = unsafe { &*(user_info as *const KeyCallback) };
+    if kind == CGEventType::TapDisabledByTimeout || kind == CGEventType::TapDisabledByUserInput {
+        TAP.with(|tap| {
+            if let Some(port) = tap.borrow().as_ref() {
+                CGEvent::tap_enable(port, true);
             }
-        })
-        .collect();
+        });
+    } else if kind.0 == NX_SYSDEFINED
+        && let Some(up) = brightness_key(unsafe { event.as_ref() })
+    {
+        on_key(up);
+    }
+    event.as_ptr()
+}
+
+/// `Some(true)` for a brightness-up press, `Some(false)` for brightness
+/// down, `None` for any other system-defined event.
+fn brightness_key(event: &objc2_core_graphics::CGEvent) -> Option<bool> {
+    use objc2_app_kit::NSEvent;
+
+    let event = NSEvent::eventWithCGEvent(event)?;
+    if event.subtype().0 != AUX_CONTROL_BUTTONS {
+        return None;
+    }
+    let data = event.data1();
+    let key = (data >> 16) & 0xFFFF;
+    let state = (data >> 8) & 0xFF;
+    match (key, state) {
+        (KEY_BRIGHTNESS_UP, KEY_STATE_DOWN) => Some(true),
+        (KEY_BRIGHTNESS_DOWN, KEY_STATE_DOWN) => Some(false),
+        _ => None,
+    }
+}
+
+// =========================================================================
+// Launch at login (LaunchAgent plist)
+// =========================================================================
+
+/// LaunchAgent label; matches the bundle identifier used for packaging.
+const LAUNCH_AGENT_LABEL: &str = "com.saisandeepvaddi.traybright";
 
-    Ok(monitors)
+pub struct MacAutostartManager {
+    /// `~/Library/LaunchAgents/<label>.plist`, or `None` if `HOME` is unset.
+    plist: Option<PathBuf>,
 }
 
-/// No-op on macOS (no handles to destroy).
-pub fn cleanup_monitors(_monitors: &mut Vec<Monitor>) {}
+impl MacAutostartManager {
+    /// Running executable path, escaped for embedding in plist XML.
+    fn program_path() -> Option<String> {
+        let exe = std::env::current_exe().ok()?;
+        Some(
+            exe.to_string_lossy()
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;"),
+        )
+    }
+}
+
+impl AutostartManager for MacAutostartManager {
+    fn new() -> Self {
+        let plist = std::env::var_os("HOME").map(|home| {
+            PathBuf::from(home)
+                .join("Library/LaunchAgents")
+                .join(format!("{LAUNCH_AGENT_LABEL}.plist"))
+        });
+        Self { plist }
+    }
+
+    fn is_startup_enabled(&self) -> bool {
+        let (Some(path), Some(program)) = (&self.plist, Self::program_path()) else {
+            return false;
+        };
+
+        fs::read_to_string(path)
+            .is_ok_and(|contents| contents.contains(&format!("<string>{program}</string>")))
+    }
+
+    fn set_startup_enabled(&self, enabled: bool) -> bool {
+        let Some(path) = &self.plist else {
+            return false;
+        };
+
+        if !enabled {
+            return match fs::remove_file(path) {
+                Ok(()) => true,
+                Err(e) => e.kind() == std::io::ErrorKind::NotFound,
+            };
+        }
+
+        let Some(program) = Self::program_path() else {
+            return false;
+        };
+
+        if let Some(dir) = path.parent()
+            && fs::create_dir_all(dir).is_err()
+        {
+            return false;
+        }
+
+        let plist = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{LAUNCH_AGENT_LABEL}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{program}</string>
+        <string>{HIDDEN_ARG}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#
+        );
+
+        fs::write(path, plist).is_ok()
+    }
+}
 
 // =========================================================================
 // Window visibility (AppKit via objc2)
@@ -109,7 +655,7 @@ unsafe impl Send for MacWindowController {}
 unsafe impl Sync for MacWindowController {}
 
 impl WindowController for MacWindowController {
-    fn from_raw_handle(handle: RawWindowHandle) -> Option<Self> {
+    fn from_raw_handle(handle: RawWindowHandle, _ctx: &egui::Context) -> Option<Self> {
         if let RawWindowHandle::AppKit(h) = handle {
             Some(Self {
                 ns_view: h.ns_view.as_ptr(),
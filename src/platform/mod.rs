@@ -1,14 +1,24 @@
 #[cfg(target_os = "windows")]
 mod windows;
 #[cfg(target_os = "windows")]
-pub use self::windows::{cleanup_monitors, get_monitors, WinWindowController};
+pub use self::windows::{
+    WinAmbientSensor, WinAutostartManager, WinBattery, WinBrightnessKeys, WinFullscreenDetector,
+    WinMonitorProvider, WinNotifier, WinPowerEvents, WinWindowController,
+};
 
 #[cfg(target_os = "linux")]
 mod linux;
 #[cfg(target_os = "linux")]
-pub use self::linux::{Monitor, cleanup_monitors, get_monitors, LinuxWindowController};
+pub use self::linux::{
+    LinuxAmbientSensor, LinuxAutostartManager, LinuxBattery, LinuxBrightnessKeys,
+    LinuxFullscreenDetector, LinuxMonitorProvider, LinuxNotifier, LinuxPowerEvents,
+    LinuxWindowController,
+};
 
 #[cfg(target_os = "macos")]
 mod macos;
 #[cfg(target_os = "macos")]
-pub use self::macos::{MacWindowController, cleanup_monitors, get_monitors};
+pub use self::macos::{
+    MacAmbientSensor, MacAutostartManager, MacBattery, MacBrightnessKeys, MacFullscreenDetector,
+    MacMonitorProvider, MacNotifier, MacPowerEvents, MacWindowController,
+};
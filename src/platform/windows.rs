@@ -1,91 +1,408 @@
-use std::sync::Mutex;
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
 
+use eframe::egui;
 use raw_window_handle::RawWindowHandle;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use windows::Data::Xml::Dom::XmlDocument;
+use windows::UI::Notifications::{ToastNotification, ToastNotificationManager};
 use windows::Win32::Devices::Display::{
-    DestroyPhysicalMonitors, GetMonitorBrightness, GetNumberOfPhysicalMonitorsFromHMONITOR,
-    GetPhysicalMonitorsFromHMONITOR, PHYSICAL_MONITOR, SetMonitorBrightness,
+    CapabilitiesRequestAndCapabilitiesReply, DISPLAYCONFIG_DEVICE_INFO_GET_SOURCE_NAME,
+    DISPLAYCONFIG_DEVICE_INFO_GET_TARGET_NAME, DISPLAYCONFIG_DEVICE_INFO_HEADER,
+    DISPLAYCONFIG_MODE_INFO, DISPLAYCONFIG_MODE_INFO_TYPE_SOURCE,
+    DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DISPLAYPORT_EMBEDDED, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_INTERNAL,
+    DISPLAYCONFIG_OUTPUT_TECHNOLOGY_LVDS, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_UDI_EMBEDDED,
+    DISPLAYCONFIG_PATH_INFO, DISPLAYCONFIG_SOURCE_DEVICE_NAME, DISPLAYCONFIG_TARGET_DEVICE_NAME,
+    DISPLAYCONFIG_VIDEO_OUTPUT_TECHNOLOGY, DestroyPhysicalMonitors, DisplayConfigGetDeviceInfo,
+    GetCapabilitiesStringLength, GetDisplayConfigBufferSizes, GetMonitorBrightness,
+    GetMonitorContrast, GetNumberOfPhysicalMonitorsFromHMONITOR, GetPhysicalMonitorsFromHMONITOR,
+    GetVCPFeatureAndVCPFeatureReply, PHYSICAL_MONITOR, QDC_ONLY_ACTIVE_PATHS, QueryDisplayConfig,
+    SetMonitorBrightness, SetMonitorContrast, SetVCPFeature,
 };
-use windows::Win32::Foundation::{HWND, LPARAM, RECT};
-use windows::Win32::Graphics::Gdi::{EnumDisplayMonitors, HDC, HMONITOR};
-use windows::Win32::UI::WindowsAndMessaging::{ShowWindow, SW_HIDE, SW_SHOWDEFAULT};
-use windows::core::BOOL;
+use windows::Win32::Devices::Sensors::{
+    ISensor, ISensorManager, SENSOR_DATA_TYPE_LIGHT_LEVEL_LUX, SENSOR_TYPE_AMBIENT_LIGHT,
+    SensorManager,
+};
+use windows::Win32::Foundation::{ERROR_FILE_NOT_FOUND, ERROR_SUCCESS, HANDLE, HWND, LPARAM, RECT};
+use windows::Win32::Graphics::Gdi::{
+    CreateDCW, DeleteDC, EnumDisplayMonitors, GetMonitorInfoW, HDC, HMONITOR,
+    MONITOR_DEFAULTTONEAREST, MONITORINFO, MONITORINFOEXW, MonitorFromWindow,
+};
+use windows::Win32::System::Com::StructuredStorage::PropVariantToDouble;
+use windows::Win32::System::Com::{
+    CLSCTX_INPROC_SERVER, COINIT_MULTITHREADED, CoCreateInstance, CoInitializeEx,
+};
+use windows::Win32::System::Power::{
+    DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS, GetSystemPowerStatus,
+    PowerRegisterSuspendResumeNotification, PowerSettingRegisterNotification, SYSTEM_POWER_STATUS,
+};
+use windows::Win32::System::Registry::{
+    HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, REG_SZ, RRF_RT_REG_BINARY, RRF_RT_REG_SZ,
+    RegDeleteKeyValueW, RegGetValueW, RegSetKeyValueW,
+};
+use windows::Win32::System::SystemServices::GUID_ACDC_POWER_SOURCE;
+use windows::Win32::UI::ColorSystem::{GetDeviceGammaRamp, SetDeviceGammaRamp};
+use windows::Win32::UI::WindowsAndMessaging::{
+    DEVICE_NOTIFY_CALLBACK, GetClassNameW, GetForegroundWindow, GetShellWindow, GetWindowRect,
+    MONITORINFOF_PRIMARY, PBT_APMRESUMEAUTOMATIC, PBT_POWERSETTINGCHANGE, SW_HIDE, SW_SHOWDEFAULT,
+    SetForegroundWindow, ShowWindow,
+};
+use windows::core::{BOOL, HSTRING, PCWSTR, w};
 use wmi::WMIConnection;
 
-use crate::os::WindowController;
+use crate::edid;
+use crate::monitors::{
+    gamma_percent, gamma_ramp, percent_of_range, probe_brightness, range_to_percent,
+    retry_with_backoff,
+};
+use crate::os::{
+    AmbientSensor, AutostartManager, Battery, BrightnessKeys, DisplayMode, FullscreenDetector,
+    HIDDEN_ARG, MonitorHandle, MonitorProvider, Notifier, PowerEvents, WindowController,
+};
+use crate::vcp;
 
-// WMI Monitor data structure for getting real monitor names
-#[derive(Deserialize, Debug)]
-#[serde(rename = "WmiMonitorID")]
-#[serde(rename_all = "PascalCase")]
-struct WmiMonitorID {
-    user_friendly_name: Option<Vec<u16>>,
-}
+/// VCP feature code for the built-in speaker volume. Brightness and
+/// contrast have dedicated dxva2 calls; volume goes through raw VCP.
+const VCP_VOLUME: u8 = 0x62;
 
 // Windows-specific monitor implementation
 pub struct Monitor {
     pub name: String,
+    /// From the EDID, else the display target's device path, else the name.
+    id: String,
     pub handle: PHYSICAL_MONITOR,
+    /// Raw hardware range reported by `GetMonitorBrightness`. Callers only
+    /// ever see 0-100; these are used to convert back for DDC writes.
     pub min_brightness: Option<u32>,
+    /// Last known brightness as a 0-100 percentage.
     pub current_brightness: Option<u32>,
     pub max_brightness: Option<u32>,
+    /// Raw MCCS capability string, fetched lazily on first use.
+    capabilities: Option<String>,
+    /// See [`MonitorHandle::supports_brightness`]. Probed on discovery.
+    supports_brightness: bool,
+    /// Whether VCP 0x62 (speaker volume) answered when probed.
+    has_volume: bool,
+    /// Peak luminance from the EDID, for HDR displays.
+    max_luminance: Option<u32>,
+    /// Resolution and refresh rate of its display path.
+    display_mode: Option<DisplayMode>,
+    /// Shown on the primary display monitor.
+    primary: bool,
 }
 
 unsafe impl Send for Monitor {}
 unsafe impl Sync for Monitor {}
 
+// The handle is released here rather than in cleanup_monitors so it still
+// happens if the worker panics; otherwise it leaks until reboot.
+impl Drop for Monitor {
+    fn drop(&mut self) {
+        if let Err(e) = destroy_physical_monitor(self.handle.hPhysicalMonitor) {
+            log::warn!("Failed to clean up handle for {}: {e}", self.name);
+        }
+    }
+}
+
+#[cfg(not(test))]
+fn destroy_physical_monitor(handle: HANDLE) -> windows::core::Result<()> {
+    unsafe { windows::Win32::Devices::Display::DestroyPhysicalMonitor(handle) }
+}
+
+// Tests use made-up handles, so record them instead of handing them to dxva2
+#[cfg(test)]
+thread_local! {
+    static DESTROYED: std::cell::RefCell<Vec<HANDLE>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+#[cfg(test)]
+fn destroy_physical_monitor(handle: HANDLE) -> windows::core::Result<()> {
+    DESTROYED.with(|d| d.borrow_mut().push(handle));
+    Ok(())
+}
+
 impl Monitor {
-    fn new(name: String, handle: PHYSICAL_MONITOR) -> Self {
+    fn new(target: TargetInfo, handle: PHYSICAL_MONITOR, primary: bool) -> Self {
         Monitor {
-            name,
+            name: target.name,
+            id: target.id,
             handle,
             min_brightness: None,
             current_brightness: None,
             max_brightness: None,
+            capabilities: None,
+            supports_brightness: true,
+            has_volume: false,
+            max_luminance: target.max_luminance,
+            display_mode: target.display_mode,
+            primary,
         }
     }
 
-    pub fn poll_brightness_values(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+    fn capabilities_string(&mut self) -> Result<String, anyhow::Error> {
+        if let Some(ref caps) = self.capabilities {
+            return Ok(caps.clone());
+        }
+
+        unsafe {
+            let mut len: u32 = 0;
+            if GetCapabilitiesStringLength(self.handle.hPhysicalMonitor, &mut len) == 0 || len == 0
+            {
+                return Err(anyhow::anyhow!("GetCapabilitiesStringLength failed"));
+            }
+
+            let mut buf = vec![0u8; len as usize];
+            if CapabilitiesRequestAndCapabilitiesReply(self.handle.hPhysicalMonitor, &mut buf) == 0
+            {
+                return Err(anyhow::anyhow!(
+                    "CapabilitiesRequestAndCapabilitiesReply failed"
+                ));
+            }
+
+            let caps = String::from_utf8_lossy(&buf)
+                .trim_end_matches('\0')
+                .to_string();
+            self.capabilities = Some(caps.clone());
+            Ok(caps)
+        }
+    }
+
+    fn read_volume(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
         unsafe {
-            let mut min: u32 = 0;
             let mut current: u32 = 0;
             let mut max: u32 = 0;
 
-            let result = GetMonitorBrightness(
+            let result = GetVCPFeatureAndVCPFeatureReply(
                 self.handle.hPhysicalMonitor,
-                &mut min,
+                VCP_VOLUME,
+                None,
                 &mut current,
-                &mut max,
+                Some(&raw mut max),
             );
 
+            if result == 0 || max == 0 {
+                return Err(anyhow::anyhow!("GetVCPFeatureAndVCPFeatureReply failed"));
+            }
+
+            Ok((current, 0, max))
+        }
+    }
+}
+
+impl MonitorHandle for Monitor {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn poll_brightness(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+        let handle = self.handle.hPhysicalMonitor;
+        let (current, min, max) = retry_with_backoff(|| unsafe {
+            let mut min: u32 = 0;
+            let mut current: u32 = 0;
+            let mut max: u32 = 0;
+
+            let result = GetMonitorBrightness(handle, &mut min, &mut current, &mut max);
+
             if result == 0 {
                 return Err(anyhow::anyhow!("GetMonitorBrightness failed"));
             }
 
-            self.min_brightness = Some(min);
-            self.current_brightness = Some(current);
-            self.max_brightness = Some(max);
-
             Ok((current, min, max))
-        }
+        })?;
+
+        // Normalize to 0-100 range so every monitor's slider means the same thing
+        let percent = range_to_percent(current, min, max);
+
+        self.min_brightness = Some(min);
+        self.current_brightness = Some(percent);
+        self.max_brightness = Some(max);
+
+        Ok((percent, 0, 100))
     }
 
-    pub fn set_brightness(&mut self, value: u32) -> Result<(), anyhow::Error> {
+    fn set_brightness(&mut self, value: u32) -> Result<(), anyhow::Error> {
         let max = self.max_brightness.unwrap_or(100);
         let min = self.min_brightness.unwrap_or(0);
-        let clamped_value = value.clamp(min, max);
+        let percent = value.min(100);
+        let raw_value = percent_of_range(min, max, percent);
 
         unsafe {
-            let result = SetMonitorBrightness(self.handle.hPhysicalMonitor, clamped_value);
+            let result = SetMonitorBrightness(self.handle.hPhysicalMonitor, raw_value);
 
             if result == 0 {
                 return Err(anyhow::anyhow!("SetMonitorBrightness failed"));
             }
         }
 
-        self.current_brightness = Some(clamped_value);
+        self.current_brightness = Some(percent);
+        Ok(())
+    }
+
+    fn poll_contrast(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+        unsafe {
+            let mut min: u32 = 0;
+            let mut current: u32 = 0;
+            let mut max: u32 = 0;
+
+            let result = GetMonitorContrast(
+                self.handle.hPhysicalMonitor,
+                &mut min,
+                &mut current,
+                &mut max,
+            );
+
+            if result == 0 || max <= min {
+                return Err(anyhow::anyhow!("GetMonitorContrast failed"));
+            }
+
+            Ok((current, min, max))
+        }
+    }
+
+    fn set_contrast(&mut self, value: u32) -> Result<(), anyhow::Error> {
+        unsafe {
+            let result = SetMonitorContrast(self.handle.hPhysicalMonitor, value);
+
+            if result == 0 {
+                return Err(anyhow::anyhow!("SetMonitorContrast failed"));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn list_color_presets(&mut self) -> Vec<(u8, String)> {
+        self.capabilities_string()
+            .map(|caps| vcp::color_presets(&caps))
+            .unwrap_or_default()
+    }
+
+    fn poll_color_preset(&mut self) -> Result<u8, anyhow::Error> {
+        unsafe {
+            let mut current: u32 = 0;
+
+            let result = GetVCPFeatureAndVCPFeatureReply(
+                self.handle.hPhysicalMonitor,
+                vcp::COLOR_PRESET,
+                None,
+                &mut current,
+                None,
+            );
+
+            if result == 0 {
+                return Err(anyhow::anyhow!("GetVCPFeatureAndVCPFeatureReply failed"));
+            }
+
+            Ok(current as u8)
+        }
+    }
+
+    fn set_color_preset(&mut self, preset: u8) -> Result<(), anyhow::Error> {
+        unsafe {
+            let result = SetVCPFeature(
+                self.handle.hPhysicalMonitor,
+                vcp::COLOR_PRESET,
+                preset as u32,
+            );
+
+            if result == 0 {
+                return Err(anyhow::anyhow!("SetVCPFeature failed"));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn supports_brightness(&self) -> bool {
+        self.supports_brightness
+    }
+
+    fn has_volume(&self) -> bool {
+        self.has_volume
+    }
+
+    fn poll_volume(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+        if !self.has_volume {
+            return Err(anyhow::anyhow!("Monitor does not support volume"));
+        }
+        self.read_volume()
+    }
+
+    fn set_volume(&mut self, value: u32) -> Result<(), anyhow::Error> {
+        unsafe {
+            let result = SetVCPFeature(self.handle.hPhysicalMonitor, VCP_VOLUME, value);
+
+            if result == 0 {
+                return Err(anyhow::anyhow!("SetVCPFeature failed"));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_vcp(&mut self, code: u8) -> Result<(u16, u16), anyhow::Error> {
+        unsafe {
+            let mut current: u32 = 0;
+            let mut max: u32 = 0;
+
+            let result = GetVCPFeatureAndVCPFeatureReply(
+                self.handle.hPhysicalMonitor,
+                code,
+                None,
+                &mut current,
+                Some(&raw mut max),
+            );
+
+            if result == 0 {
+                return Err(anyhow::anyhow!("GetVCPFeatureAndVCPFeatureReply failed"));
+            }
+
+            Ok((current as u16, max as u16))
+        }
+    }
+
+    fn set_vcp(&mut self, code: u8, value: u16) -> Result<(), anyhow::Error> {
+        unsafe {
+            let result = SetVCPFeature(self.handle.hPhysicalMonitor, code, value as u32);
+
+            if result == 0 {
+                return Err(anyhow::anyhow!("SetVCPFeature failed"));
+            }
+        }
+
         Ok(())
     }
+
+    fn set_power_mode(&mut self, mode: u8) -> Result<(), anyhow::Error> {
+        self.set_vcp(vcp::POWER_MODE, mode.into())
+    }
+
+    fn capabilities(&mut self) -> Result<String, anyhow::Error> {
+        self.capabilities_string()
+    }
+
+    fn is_software(&self) -> bool {
+        false
+    }
+
+    fn is_primary(&self) -> bool {
+        self.primary
+    }
+
+    fn max_luminance(&self) -> Option<u32> {
+        self.max_luminance
+    }
+
+    fn display_mode(&self) -> Option<DisplayMode> {
+        self.display_mode
+    }
 }
 
 // Callback for EnumDisplayMonitors to collect HMONITORs
@@ -102,33 +419,248 @@ unsafe extern "system" fn enum_display_monitors_callback(
     BOOL(1)
 }
 
-// Get monitor friendly names from WMI (EDID UserFriendlyName)
-fn get_wmi_monitor_names() -> Result<Vec<String>, anyhow::Error> {
-    let wmi_con = WMIConnection::with_namespace_path("ROOT\\WMI")?;
-    let results: Vec<WmiMonitorID> = wmi_con.query()?;
+/// A monitor at the end of an active display path.
+struct DisplayTarget {
+    /// Empty when the monitor doesn't report one (common for built-in panels).
+    name: String,
+    /// `\\?\DISPLAY#<PnP id>#<instance>#{guid}` — stable for a given monitor
+    /// on a given port, unlike the GDI name. Empty if unavailable.
+    device_path: String,
+    /// Connected internally, i.e. a laptop's built-in panel.
+    internal: bool,
+    /// Resolution of the source and refresh rate of the target.
+    mode: Option<DisplayMode>,
+}
 
-    let mut monitor_names = Vec::new();
+/// Output technologies used for built-in panels.
+const INTERNAL_OUTPUTS: [DISPLAYCONFIG_VIDEO_OUTPUT_TECHNOLOGY; 4] = [
+    DISPLAYCONFIG_OUTPUT_TECHNOLOGY_INTERNAL,
+    DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DISPLAYPORT_EMBEDDED,
+    DISPLAYCONFIG_OUTPUT_TECHNOLOGY_LVDS,
+    DISPLAYCONFIG_OUTPUT_TECHNOLOGY_UDI_EMBEDDED,
+];
 
-    for monitor in results.iter() {
-        if let Some(ref name_bytes) = monitor.user_friendly_name {
-            let name: String = name_bytes
-                .iter()
-                .copied()
-                .take_while(|&c| c != 0)
-                .filter_map(|c| if c > 0 { Some(c as u8 as char) } else { None })
-                .collect();
+/// Monitors on the active display paths, grouped by the GDI device name
+/// (`\\.\DISPLAY1`) of the source each path scans out from. Each target
+/// is looked up by its adapter + target id; a source drives several targets
+/// only when displays are cloned.
+fn get_display_targets() -> Result<HashMap<String, Vec<DisplayTarget>>, anyhow::Error> {
+    let mut targets: HashMap<String, Vec<DisplayTarget>> = HashMap::new();
 
-            if !name.is_empty() {
-                monitor_names.push(name);
+    unsafe {
+        let mut path_count: u32 = 0;
+        let mut mode_count: u32 = 0;
+        GetDisplayConfigBufferSizes(QDC_ONLY_ACTIVE_PATHS, &mut path_count, &mut mode_count)
+            .ok()?;
+
+        let mut paths = vec![DISPLAYCONFIG_PATH_INFO::default(); path_count as usize];
+        let mut modes = vec![DISPLAYCONFIG_MODE_INFO::default(); mode_count as usize];
+        QueryDisplayConfig(
+            QDC_ONLY_ACTIVE_PATHS,
+            &mut path_count,
+            paths.as_mut_ptr(),
+            &mut mode_count,
+            modes.as_mut_ptr(),
+            None,
+        )
+        .ok()?;
+        paths.truncate(path_count as usize);
+        modes.truncate(mode_count as usize);
+
+        for path in &paths {
+            let mut source = DISPLAYCONFIG_SOURCE_DEVICE_NAME {
+                header: DISPLAYCONFIG_DEVICE_INFO_HEADER {
+                    r#type: DISPLAYCONFIG_DEVICE_INFO_GET_SOURCE_NAME,
+                    size: std::mem::size_of::<DISPLAYCONFIG_SOURCE_DEVICE_NAME>() as u32,
+                    adapterId: path.sourceInfo.adapterId,
+                    id: path.sourceInfo.id,
+                },
+                ..Default::default()
+            };
+            if DisplayConfigGetDeviceInfo(&mut source.header) != ERROR_SUCCESS.0 as i32 {
+                continue;
             }
+
+            let mut target = DISPLAYCONFIG_TARGET_DEVICE_NAME {
+                header: DISPLAYCONFIG_DEVICE_INFO_HEADER {
+                    r#type: DISPLAYCONFIG_DEVICE_INFO_GET_TARGET_NAME,
+                    size: std::mem::size_of::<DISPLAYCONFIG_TARGET_DEVICE_NAME>() as u32,
+                    adapterId: path.targetInfo.adapterId,
+                    id: path.targetInfo.id,
+                },
+                ..Default::default()
+            };
+            let mode = path_mode(path, &modes);
+            let display_target =
+                if DisplayConfigGetDeviceInfo(&mut target.header) == ERROR_SUCCESS.0 as i32 {
+                    DisplayTarget {
+                        name: wide_to_string(&target.monitorFriendlyDeviceName),
+                        device_path: wide_to_string(&target.monitorDevicePath),
+                        internal: INTERNAL_OUTPUTS.contains(&target.outputTechnology),
+                        mode,
+                    }
+                } else {
+                    DisplayTarget {
+                        name: String::new(),
+                        device_path: String::new(),
+                        internal: false,
+                        mode,
+                    }
+                };
+
+            targets
+                .entry(wide_to_string(&source.viewGdiDeviceName))
+                .or_default()
+                .push(display_target);
         }
     }
 
-    Ok(monitor_names)
+    Ok(targets)
+}
+
+/// Resolution of the source `path` scans out from, from its entry in
+/// `modes`, and the refresh rate of its target. `None` when the path has no
+/// source mode.
+fn path_mode(
+    path: &DISPLAYCONFIG_PATH_INFO,
+    modes: &[DISPLAYCONFIG_MODE_INFO],
+) -> Option<DisplayMode> {
+    // Without QDC_VIRTUAL_MODE_AWARE the union holds a plain index
+    let mode = modes.get(unsafe { path.sourceInfo.Anonymous.modeInfoIdx } as usize)?;
+    if mode.infoType != DISPLAYCONFIG_MODE_INFO_TYPE_SOURCE {
+        return None;
+    }
+    let source = unsafe { mode.Anonymous.sourceMode };
+    let rate = path.targetInfo.refreshRate;
+    Some(DisplayMode {
+        width: source.width,
+        height: source.height,
+        refresh_hz: (rate.Numerator != 0 && rate.Denominator != 0)
+            .then(|| rate.Numerator as f32 / rate.Denominator as f32),
+    })
+}
+
+/// EDID block Windows cached for a monitor device instance
+/// (`DISPLAY\DEL41A4\5&1a2b3c&0&UID4353`).
+fn read_edid(instance: &str) -> Option<Vec<u8>> {
+    let key = HSTRING::from(format!(
+        r"SYSTEM\CurrentControlSet\Enum\{instance}\Device Parameters"
+    ));
+    let mut buf = vec![0u8; 1024];
+    let mut size = buf.len() as u32;
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            &key,
+            w!("EDID"),
+            RRF_RT_REG_BINARY,
+            None,
+            Some(buf.as_mut_ptr().cast()),
+            Some(&mut size),
+        )
+    };
+    if status != ERROR_SUCCESS {
+        return None;
+    }
+    buf.truncate(size as usize);
+    Some(buf)
+}
+
+/// What's known about the monitor on a display target before talking to it.
+struct TargetInfo {
+    name: String,
+    id: String,
+    max_luminance: Option<u32>,
+    display_mode: Option<DisplayMode>,
+}
+
+/// Name, id, peak luminance and display mode for the monitor on `target`,
+/// numbered `number` if nothing better is known. The id is from the EDID,
+/// else the device path, else the name.
+fn describe_target(target: Option<&DisplayTarget>, number: usize) -> TargetInfo {
+    let device_path = target
+        .map(|t| t.device_path.as_str())
+        .filter(|path| !path.is_empty());
+    // Without a friendly name, "Dell 41A4" from the device id still beats a
+    // bare number
+    let name = target
+        .map(|t| t.name.clone())
+        .filter(|name| !name.is_empty())
+        .or_else(|| {
+            device_path
+                .and_then(edid::parse_device_id)
+                .map(|id| id.display_name())
+        })
+        .unwrap_or_else(|| format!("Display {number}"));
+    let edid = device_path
+        .and_then(device_path_instance)
+        .and_then(|instance| read_edid(&instance));
+    let id = edid
+        .as_deref()
+        .and_then(edid::stable_id)
+        .or_else(|| device_path.map(str::to_string))
+        .unwrap_or_else(|| name.clone());
+    TargetInfo {
+        name,
+        id,
+        max_luminance: edid.as_deref().and_then(edid::max_luminance),
+        display_mode: target.and_then(|t| t.mode),
+    }
+}
+
+/// The display target each of a source's `count` physical handles scans out
+/// to; both come back in the same order. Some adapters report fewer targets
+/// than handles, and the extra handles get `None`, so a generic name, rather
+/// than being dropped.
+fn handle_targets(targets: Option<&[DisplayTarget]>, count: usize) -> Vec<Option<&DisplayTarget>> {
+    if let Some(targets) = targets
+        && targets.len() != count
+    {
+        log::warn!(
+            "{count} physical monitors but {} display targets on one output",
+            targets.len()
+        );
+    }
+    (0..count)
+        .map(|i| targets.and_then(|targets| targets.get(i)))
+        .collect()
+}
+
+/// Device instance of a display target's interface path:
+/// `\\?\DISPLAY#DEL41A4#5&1a2b3c&0&UID4353#{guid}` becomes
+/// `DISPLAY\DEL41A4\5&1a2b3c&0&UID4353`.
+fn device_path_instance(path: &str) -> Option<String> {
+    let (instance, _guid) = path.strip_prefix(r"\\?\")?.rsplit_once('#')?;
+    Some(instance.replace('#', "\\"))
+}
+
+/// GDI device name (`\\.\DISPLAY1`) of a display monitor.
+fn gdi_device_name(hmonitor: HMONITOR) -> Option<String> {
+    let mut info = MONITORINFOEXW::default();
+    info.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
+    let ok = unsafe { GetMonitorInfoW(hmonitor, &mut info.monitorInfo) };
+    ok.as_bool().then(|| wide_to_string(&info.szDevice))
 }
 
-// Get physical monitor handles (for brightness control via DDC/CI)
-fn get_physical_monitor_handles() -> Result<Vec<PHYSICAL_MONITOR>, anyhow::Error> {
+/// Whether `hmonitor` is the primary display, the one at the desktop origin.
+fn is_primary_monitor(hmonitor: HMONITOR) -> bool {
+    let mut info = MONITORINFO {
+        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    let ok = unsafe { GetMonitorInfoW(hmonitor, &mut info) };
+    ok.as_bool() && info.dwFlags & MONITORINFOF_PRIMARY != 0
+}
+
+fn wide_to_string(wide: &[u16]) -> String {
+    let len = wide.iter().position(|&c| c == 0).unwrap_or(wide.len());
+    String::from_utf16_lossy(&wide[..len])
+}
+
+// Get physical monitor handles (for brightness control via DDC/CI),
+// grouped by the display monitor they belong to. Display monitors without
+// any come back with an empty list.
+fn get_physical_monitor_handles() -> Result<Vec<(HMONITOR, Vec<PHYSICAL_MONITOR>)>, anyhow::Error> {
     let mut all_handles = Vec::new();
 
     unsafe {
@@ -144,125 +676,1381 @@ fn get_physical_monitor_handles() -> Result<Vec<PHYSICAL_MONITOR>, anyhow::Error
         for hm in hmons.iter() {
             let mut count: u32 = 0;
             if let Err(e) = GetNumberOfPhysicalMonitorsFromHMONITOR(*hm, &mut count) {
-                eprintln!("GetNumberOfPhysicalMonitorsFromHMONITOR failed: {e}");
+                log::warn!("GetNumberOfPhysicalMonitorsFromHMONITOR failed: {e}");
                 continue;
             }
+            // Still listed: the provider dims these in software
             if count == 0 {
+                all_handles.push((*hm, Vec::new()));
                 continue;
             }
 
             let mut phys: Vec<PHYSICAL_MONITOR> = vec![std::mem::zeroed(); count as usize];
             if let Err(e) = GetPhysicalMonitorsFromHMONITOR(*hm, &mut phys) {
-                eprintln!("GetPhysicalMonitorsFromHMONITOR failed: {e}");
+                log::warn!("GetPhysicalMonitorsFromHMONITOR failed: {e}");
                 continue;
             }
 
-            all_handles.extend(phys);
+            all_handles.push((*hm, phys));
         }
     }
 
     Ok(all_handles)
 }
 
-// Get complete monitor information (names + handles)
-pub fn get_monitors() -> Result<Vec<Monitor>, anyhow::Error> {
-    let names = get_wmi_monitor_names()?;
-    let handles = get_physical_monitor_handles()?;
+// =========================================================================
+// Per-monitor write queue (DDC/CI)
+// =========================================================================
 
-    // Match names to handles (assuming they're in the same order)
-    let monitors: Vec<Monitor> = names
-        .into_iter()
-        .zip(handles.into_iter().rev())
-        .map(|(name, handle)| Monitor::new(name, handle))
-        .collect();
-
-    // The above wmi monitor names may not be in correct order. Uncomment and allow this way in a settings view somewhere.
-    // let monitors: Vec<Monitor> = handles
-    //     .into_iter()
-    //     .map(|handle| {
-    //         let description = handle.szPhysicalMonitorDescription;
-    //         let name = String::from_utf16_lossy(&description)
-    //             .trim_matches('\0')
-    //             .to_string();
-    //         Monitor::new(name, handle)
-    //     })
-    //     .collect();
-
-    Ok(monitors)
-}
-
-// Clean up monitor handles when done
-pub fn cleanup_monitors(monitors: &mut Vec<Monitor>) {
-    let mut handles: Vec<PHYSICAL_MONITOR> = monitors.drain(..).map(|m| m.handle).collect();
-    unsafe {
-        if let Err(e) = DestroyPhysicalMonitors(&mut handles) {
-            eprintln!("Failed to clean up monitor handles: {}", e);
-        }
-    }
+/// Brightness write waiting for a monitor's writer thread.
+#[derive(Default)]
+struct WriteQueue {
+    /// Latest value asked for. A newer one replaces it if the thread hasn't
+    /// picked it up yet.
+    pending: Option<u32>,
+    /// The thread is writing a value it took from `pending`.
+    busy: bool,
+    /// Error from the last write, handed back by `confirm_brightness`, or
+    /// by the next `set_brightness` if nothing asked.
+    failed: Option<anyhow::Error>,
+    /// Set on drop; the thread finishes any pending write, then exits.
+    shutdown: bool,
 }
 
-// =========================================================================
-// Window visibility (ShowWindow API)
-// =========================================================================
+type SharedQueue = Arc<(Mutex<WriteQueue>, Condvar)>;
 
-pub struct WinWindowController {
-    hwnd: isize,
-    visible: Mutex<bool>,
+/// A DDC/CI monitor whose brightness writes run on its own thread.
+/// `SetMonitorBrightness` can block for over a second on some monitors, so
+/// `set_brightness` only queues the value and returns, and a slow monitor
+/// doesn't hold up the worker's commands to the others. Everything else
+/// waits for a queued write to land first, so a read never sees the value
+/// from before it.
+pub struct QueuedMonitor {
+    monitor: Arc<Mutex<Monitor>>,
+    queue: SharedQueue,
+    writer: Option<JoinHandle<()>>,
+    // Copied out so the getters below don't need the lock; none of these
+    // change after enumeration
+    name: String,
+    id: String,
+    supports_brightness: bool,
+    has_volume: bool,
+    max_luminance: Option<u32>,
+    display_mode: Option<DisplayMode>,
+    primary: bool,
 }
 
-impl WindowController for WinWindowController {
-    fn from_raw_handle(handle: RawWindowHandle) -> Option<Self> {
-        if let RawWindowHandle::Win32(h) = handle {
-            let hwnd: isize = h.hwnd.into();
-            Some(Self {
-                hwnd,
-                visible: Mutex::new(true),
-            })
-        } else {
-            None
+impl QueuedMonitor {
+    fn new(monitor: Monitor) -> Self {
+        let name = monitor.name.clone();
+        let id = monitor.id.clone();
+        let supports_brightness = monitor.supports_brightness;
+        let (has_volume, max_luminance, primary) =
+            (monitor.has_volume, monitor.max_luminance, monitor.primary);
+        let display_mode = monitor.display_mode;
+        let monitor = Arc::new(Mutex::new(monitor));
+        let queue: SharedQueue = Arc::default();
+        let writer = {
+            let (monitor, queue) = (monitor.clone(), queue.clone());
+            std::thread::spawn(move || Self::write_loop(&monitor, &queue))
+        };
+
+        QueuedMonitor {
+            monitor,
+            queue,
+            writer: Some(writer),
+            name,
+            id,
+            supports_brightness,
+            has_volume,
+            max_luminance,
+            display_mode,
+            primary,
         }
     }
 
-    fn show(&self) {
-        let mut vis = self.visible.lock().unwrap();
-        if !*vis {
-            let hwnd = HWND(self.hwnd as *mut core::ffi::c_void);
-            unsafe {
-                let _ = ShowWindow(hwnd, SW_SHOWDEFAULT);
+    /// Body of the writer thread: write each value as it's queued until the
+    /// monitor is dropped.
+    fn write_loop(monitor: &Mutex<Monitor>, queue: &(Mutex<WriteQueue>, Condvar)) {
+        let (lock, ready) = queue;
+        loop {
+            let value = {
+                let idle = |q: &mut WriteQueue| q.pending.is_none() && !q.shutdown;
+                let mut state = ready.wait_while(lock.lock().unwrap(), idle).unwrap();
+                let Some(value) = state.pending.take() else {
+                    return;
+                };
+                state.busy = true;
+                value
+            };
+
+            let result = monitor.lock().unwrap().set_brightness(value);
+
+            let mut state = lock.lock().unwrap();
+            state.busy = false;
+            if let Err(e) = result {
+                log::warn!("Brightness write failed: {e}");
+                state.failed = Some(e);
             }
-            *vis = true;
+            ready.notify_all();
         }
     }
 
-    fn hide(&self) {
-        let mut vis = self.visible.lock().unwrap();
-        if *vis {
-            let hwnd = HWND(self.hwnd as *mut core::ffi::c_void);
-            unsafe {
-                let _ = ShowWindow(hwnd, SW_HIDE);
-            }
-            *vis = false;
-        }
+    /// The monitor, once any queued write has landed. Only the owner
+    /// queues writes, so none can start while the guard is held.
+    fn settled(&mut self) -> MutexGuard<'_, Monitor> {
+        let (lock, ready) = &*self.queue;
+        drop(
+            ready
+                .wait_while(lock.lock().unwrap(), |q| q.pending.is_some() || q.busy)
+                .unwrap(),
+        );
+        self.monitor.lock().unwrap()
     }
+}
 
-    fn toggle(&self) {
-        let vis = *self.visible.lock().unwrap();
-        if vis {
-            self.hide();
-        } else {
-            self.show();
+// Joining here means the writer's copy of the monitor is gone by the time
+// this one drops, so the physical handle is destroyed exactly once, here.
+impl Drop for QueuedMonitor {
+    fn drop(&mut self) {
+        let (lock, ready) = &*self.queue;
+        lock.lock().unwrap().shutdown = true;
+        ready.notify_all();
+        if let Some(writer) = self.writer.take()
+            && writer.join().is_err()
+        {
+            log::warn!("Brightness writer for {} panicked", self.name);
         }
     }
+}
 
-    fn is_visible(&self) -> bool {
-        *self.visible.lock().unwrap()
+impl MonitorHandle for QueuedMonitor {
+    fn name(&self) -> &str {
+        &self.name
     }
 
-    fn set_visible(&self, visible: bool) {
-        if visible {
-            self.show();
-        } else {
-            self.hide();
-        }
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn poll_brightness(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+        self.settled().poll_brightness()
+    }
+
+    /// Queue `value` for the writer thread and return straight away. A
+    /// failed write is reported by `confirm_brightness`, or by the next call
+    /// if that's never asked.
+    fn set_brightness(&mut self, value: u32) -> Result<(), anyhow::Error> {
+        let (lock, ready) = &*self.queue;
+        let mut state = lock.lock().unwrap();
+        state.pending = Some(value);
+        ready.notify_all();
+        state.failed.take().map_or(Ok(()), Err)
+    }
+
+    fn confirm_brightness(&mut self) -> Result<(), anyhow::Error> {
+        let (lock, ready) = &*self.queue;
+        let mut state = ready
+            .wait_while(lock.lock().unwrap(), |q| q.pending.is_some() || q.busy)
+            .unwrap();
+        state.failed.take().map_or(Ok(()), Err)
+    }
+
+    fn poll_contrast(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+        self.settled().poll_contrast()
+    }
+
+    fn set_contrast(&mut self, value: u32) -> Result<(), anyhow::Error> {
+        self.settled().set_contrast(value)
+    }
+
+    fn list_color_presets(&mut self) -> Vec<(u8, String)> {
+        self.settled().list_color_presets()
+    }
+
+    fn poll_color_preset(&mut self) -> Result<u8, anyhow::Error> {
+        self.settled().poll_color_preset()
+    }
+
+    fn set_color_preset(&mut self, preset: u8) -> Result<(), anyhow::Error> {
+        self.settled().set_color_preset(preset)
+    }
+
+    fn supports_brightness(&self) -> bool {
+        self.supports_brightness
+    }
+
+    fn has_volume(&self) -> bool {
+        self.has_volume
+    }
+
+    fn poll_volume(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+        self.settled().poll_volume()
+    }
+
+    fn set_volume(&mut self, value: u32) -> Result<(), anyhow::Error> {
+        self.settled().set_volume(value)
+    }
+
+    fn get_vcp(&mut self, code: u8) -> Result<(u16, u16), anyhow::Error> {
+        self.settled().get_vcp(code)
+    }
+
+    fn set_vcp(&mut self, code: u8, value: u16) -> Result<(), anyhow::Error> {
+        self.settled().set_vcp(code, value)
+    }
+
+    fn set_power_mode(&mut self, mode: u8) -> Result<(), anyhow::Error> {
+        self.settled().set_power_mode(mode)
+    }
+
+    fn capabilities(&mut self) -> Result<String, anyhow::Error> {
+        self.settled().capabilities()
+    }
+
+    fn is_software(&self) -> bool {
+        false
+    }
+
+    fn is_primary(&self) -> bool {
+        self.primary
+    }
+
+    fn max_luminance(&self) -> Option<u32> {
+        self.max_luminance
+    }
+
+    fn display_mode(&self) -> Option<DisplayMode> {
+        self.display_mode
+    }
+}
+
+// =========================================================================
+// Built-in panel backlight (WMI)
+// =========================================================================
+
+// WMI brightness state of a built-in panel (ROOT\WMI)
+#[derive(Deserialize, Debug)]
+#[serde(rename = "WmiMonitorBrightness")]
+#[serde(rename_all = "PascalCase")]
+struct WmiMonitorBrightness {
+    active: bool,
+    current_brightness: u8,
+    /// Brightness values the panel supports, in percent.
+    level: Vec<u8>,
+    instance_name: String,
+}
+
+// Object the WmiSetBrightness method is called on
+#[derive(Deserialize, Debug)]
+#[serde(rename = "WmiMonitorBrightnessMethods")]
+#[serde(rename_all = "PascalCase")]
+struct WmiMonitorBrightnessMethods {
+    #[serde(rename = "__Path")]
+    path: String,
+    instance_name: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct WmiSetBrightnessParams {
+    /// Seconds before the change reverts; 0 keeps it.
+    timeout: u32,
+    brightness: u8,
+}
+
+/// A laptop's built-in panel. These don't speak DDC/CI; brightness goes
+/// through WMI instead, and nothing else is adjustable.
+pub struct WinWmiBacklightMonitor {
+    name: String,
+    /// From the panel's EDID, else the WMI instance name.
+    id: String,
+    instance_name: String,
+    /// Supported brightness values in percent, ascending.
+    levels: Vec<u8>,
+    /// Peak luminance from the panel's EDID, for HDR panels.
+    max_luminance: Option<u32>,
+    /// Set by the provider from the internal display path.
+    display_mode: Option<DisplayMode>,
+    /// Set by the provider when the built-in panel is the primary display.
+    primary: bool,
+}
+
+impl WinWmiBacklightMonitor {
+    // WMIConnection isn't Send, so each call opens its own
+    fn connection() -> Result<WMIConnection, anyhow::Error> {
+        Ok(WMIConnection::with_namespace_path("ROOT\\WMI")?)
+    }
+
+    fn unsupported<T>(feature: &str) -> Result<T, anyhow::Error> {
+        Err(anyhow::anyhow!(
+            "{feature} is not supported on the built-in display"
+        ))
+    }
+}
+
+impl MonitorHandle for WinWmiBacklightMonitor {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn poll_brightness(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+        let results: Vec<WmiMonitorBrightness> = Self::connection()?.query()?;
+        let state = results
+            .into_iter()
+            .find(|b| b.instance_name == self.instance_name)
+            .ok_or_else(|| anyhow::anyhow!("Built-in display no longer reports brightness"))?;
+
+        // Already a percentage
+        Ok((u32::from(state.current_brightness).min(100), 0, 100))
+    }
+
+    fn set_brightness(&mut self, value: u32) -> Result<(), anyhow::Error> {
+        // Snap to the nearest level the panel supports
+        let percent = value.min(100) as u8;
+        let brightness = self
+            .levels
+            .iter()
+            .copied()
+            .min_by_key(|level| level.abs_diff(percent))
+            .unwrap_or(percent);
+
+        let wmi = Self::connection()?;
+        let methods: Vec<WmiMonitorBrightnessMethods> = wmi.query()?;
+        let target = methods
+            .iter()
+            .find(|m| m.instance_name == self.instance_name)
+            .ok_or_else(|| anyhow::anyhow!("Built-in display no longer accepts brightness"))?;
+
+        wmi.exec_instance_method::<WmiMonitorBrightnessMethods, ()>(
+            &target.path,
+            "WmiSetBrightness",
+            WmiSetBrightnessParams {
+                timeout: 0,
+                brightness,
+            },
+        )?;
+        Ok(())
+    }
+
+    fn poll_contrast(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+        Self::unsupported("Contrast")
+    }
+
+    fn set_contrast(&mut self, _value: u32) -> Result<(), anyhow::Error> {
+        Self::unsupported("Contrast")
+    }
+
+    fn list_color_presets(&mut self) -> Vec<(u8, String)> {
+        Vec::new()
+    }
+
+    fn poll_color_preset(&mut self) -> Result<u8, anyhow::Error> {
+        Self::unsupported("Color presets")
+    }
+
+    fn set_color_preset(&mut self, _preset: u8) -> Result<(), anyhow::Error> {
+        Self::unsupported("Color presets")
+    }
+
+    fn supports_brightness(&self) -> bool {
+        true
+    }
+
+    fn has_volume(&self) -> bool {
+        false
+    }
+
+    fn poll_volume(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+        Self::unsupported("Volume")
+    }
+
+    fn set_volume(&mut self, _value: u32) -> Result<(), anyhow::Error> {
+        Self::unsupported("Volume")
+    }
+
+    fn get_vcp(&mut self, _code: u8) -> Result<(u16, u16), anyhow::Error> {
+        Self::unsupported("VCP")
+    }
+
+    fn set_vcp(&mut self, _code: u8, _value: u16) -> Result<(), anyhow::Error> {
+        Self::unsupported("VCP")
+    }
+
+    fn set_power_mode(&mut self, _mode: u8) -> Result<(), anyhow::Error> {
+        Self::unsupported("Power mode")
+    }
+
+    fn capabilities(&mut self) -> Result<String, anyhow::Error> {
+        Self::unsupported("A capability string")
+    }
+
+    fn is_software(&self) -> bool {
+        false
+    }
+
+    fn is_primary(&self) -> bool {
+        self.primary
+    }
+
+    fn max_luminance(&self) -> Option<u32> {
+        self.max_luminance
+    }
+
+    fn display_mode(&self) -> Option<DisplayMode> {
+        self.display_mode
+    }
+}
+
+/// Built-in panels whose brightness WMI can control. Desktops have no
+/// active `WmiMonitorBrightness` instances (the query fails or comes back
+/// empty), so they get none.
+fn get_wmi_backlight_monitors() -> Vec<WinWmiBacklightMonitor> {
+    let results: Vec<WmiMonitorBrightness> =
+        match WinWmiBacklightMonitor::connection().and_then(|wmi| Ok(wmi.query()?)) {
+            Ok(results) => results,
+            Err(_) => return Vec::new(),
+        };
+
+    let active: Vec<WmiMonitorBrightness> = results.into_iter().filter(|b| b.active).collect();
+    let count = active.len();
+    active
+        .into_iter()
+        .enumerate()
+        .map(|(i, state)| {
+            let mut levels = state.level;
+            levels.sort_unstable();
+            let name = if count > 1 {
+                format!("Built-in Display {}", i + 1)
+            } else {
+                "Built-in Display".to_string()
+            };
+            // WMI instance names are the device instance plus a "_0" suffix
+            let instance = state
+                .instance_name
+                .rsplit_once('_')
+                .map_or(state.instance_name.as_str(), |(i, _)| i);
+            let edid = read_edid(instance);
+            let id = edid
+                .as_deref()
+                .and_then(edid::stable_id)
+                .unwrap_or_else(|| state.instance_name.clone());
+            let max_luminance = edid.as_deref().and_then(edid::max_luminance);
+            let instance_name = state.instance_name;
+            WinWmiBacklightMonitor {
+                name,
+                id,
+                instance_name,
+                levels,
+                max_luminance,
+                display_mode: None,
+                primary: false,
+            }
+        })
+        .collect()
+}
+
+// =========================================================================
+// Software brightness (gamma ramp)
+// =========================================================================
+
+/// Gamma ramp in the layout GDI takes: 256 entries each for red, green and
+/// blue.
+type GdiGammaRamp = [[u16; 256]; 3];
+
+/// A display Windows gives no physical monitor handle for, so neither
+/// DDC/CI nor WMI can reach it, dimmed through its gamma ramp instead.
+/// Windows refuses ramps far from the identity unless the
+/// `GdiIcmGammaRange` registry value is raised, so the lowest levels can
+/// fail to apply.
+pub struct WinGammaMonitor {
+    name: String,
+    id: String,
+    /// GDI device name (`\\.\DISPLAY1`).
+    device: String,
+    /// Peak luminance from the EDID, for HDR displays.
+    max_luminance: Option<u32>,
+    display_mode: Option<DisplayMode>,
+    primary: bool,
+}
+
+impl WinGammaMonitor {
+    /// Run `f` with a device context for this display.
+    fn with_dc<T>(
+        &self,
+        f: impl FnOnce(HDC) -> Result<T, anyhow::Error>,
+    ) -> Result<T, anyhow::Error> {
+        let device = HSTRING::from(self.device.as_str());
+        let hdc = unsafe { CreateDCW(w!("DISPLAY"), &device, PCWSTR::null(), None) };
+        if hdc.is_invalid() {
+            return Err(anyhow::anyhow!("{} is no longer connected", self.name));
+        }
+        let result = f(hdc);
+        unsafe {
+            let _ = DeleteDC(hdc);
+        }
+        result
+    }
+
+    fn unsupported<T>(feature: &str) -> Result<T, anyhow::Error> {
+        Err(anyhow::anyhow!("{feature} is not supported without DDC/CI"))
+    }
+}
+
+impl MonitorHandle for WinGammaMonitor {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn poll_brightness(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+        let mut ramp: GdiGammaRamp = [[0; 256]; 3];
+        self.with_dc(|hdc| {
+            if !unsafe { GetDeviceGammaRamp(hdc, ramp.as_mut_ptr().cast()) }.as_bool() {
+                return Err(anyhow::anyhow!("GetDeviceGammaRamp failed"));
+            }
+            Ok(())
+        })?;
+        Ok((gamma_percent(ramp[1][255]), 0, 100))
+    }
+
+    fn set_brightness(&mut self, value: u32) -> Result<(), anyhow::Error> {
+        let channel: [u16; 256] = gamma_ramp(256, value)
+            .try_into()
+            .expect("gamma_ramp returns the requested size");
+        let ramp: GdiGammaRamp = [channel; 3];
+        self.with_dc(|hdc| {
+            if !unsafe { SetDeviceGammaRamp(hdc, ramp.as_ptr().cast()) }.as_bool() {
+                return Err(anyhow::anyhow!(
+                    "Windows refused the gamma ramp for {value}%"
+                ));
+            }
+            Ok(())
+        })
+    }
+
+    fn poll_contrast(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+        Self::unsupported("Contrast")
+    }
+
+    fn set_contrast(&mut self, _value: u32) -> Result<(), anyhow::Error> {
+        Self::unsupported("Contrast")
+    }
+
+    fn list_color_presets(&mut self) -> Vec<(u8, String)> {
+        Vec::new()
+    }
+
+    fn poll_color_preset(&mut self) -> Result<u8, anyhow::Error> {
+        Self::unsupported("Color presets")
+    }
+
+    fn set_color_preset(&mut self, _preset: u8) -> Result<(), anyhow::Error> {
+        Self::unsupported("Color presets")
+    }
+
+    fn supports_brightness(&self) -> bool {
+        true
+    }
+
+    fn has_volume(&self) -> bool {
+        false
+    }
+
+    fn poll_volume(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+        Self::unsupported("Volume")
+    }
+
+    fn set_volume(&mut self, _value: u32) -> Result<(), anyhow::Error> {
+        Self::unsupported("Volume")
+    }
+
+    fn get_vcp(&mut self, _code: u8) -> Result<(u16, u16), anyhow::Error> {
+        Self::unsupported("VCP")
+    }
+
+    fn set_vcp(&mut self, _code: u8, _value: u16) -> Result<(), anyhow::Error> {
+        Self::unsupported("VCP")
+    }
+
+    fn set_power_mode(&mut self, _mode: u8) -> Result<(), anyhow::Error> {
+        Self::unsupported("Power mode")
+    }
+
+    fn capabilities(&mut self) -> Result<String, anyhow::Error> {
+        Self::unsupported("A capability string")
+    }
+
+    fn is_software(&self) -> bool {
+        true
+    }
+
+    fn is_primary(&self) -> bool {
+        self.primary
+    }
+
+    fn max_luminance(&self) -> Option<u32> {
+        self.max_luminance
+    }
+
+    fn display_mode(&self) -> Option<DisplayMode> {
+        self.display_mode
+    }
+}
+
+// =========================================================================
+// Provider
+// =========================================================================
+
+/// Any monitor the Windows provider can control.
+pub enum WinMonitor {
+    Ddc(QueuedMonitor),
+    Backlight(WinWmiBacklightMonitor),
+    Software(WinGammaMonitor),
+}
+
+impl WinMonitor {
+    fn inner(&self) -> &dyn MonitorHandle {
+        match self {
+            WinMonitor::Ddc(m) => m,
+            WinMonitor::Backlight(m) => m,
+            WinMonitor::Software(m) => m,
+        }
+    }
+
+    fn inner_mut(&mut self) -> &mut dyn MonitorHandle {
+        match self {
+            WinMonitor::Ddc(m) => m,
+            WinMonitor::Backlight(m) => m,
+            WinMonitor::Software(m) => m,
+        }
+    }
+}
+
+impl MonitorHandle for WinMonitor {
+    fn name(&self) -> &str {
+        self.inner().name()
+    }
+
+    fn id(&self) -> String {
+        self.inner().id()
+    }
+
+    fn poll_brightness(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+        self.inner_mut().poll_brightness()
+    }
+
+    fn set_brightness(&mut self, value: u32) -> Result<(), anyhow::Error> {
+        self.inner_mut().set_brightness(value)
+    }
+
+    fn confirm_brightness(&mut self) -> Result<(), anyhow::Error> {
+        self.inner_mut().confirm_brightness()
+    }
+
+    fn poll_contrast(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+        self.inner_mut().poll_contrast()
+    }
+
+    fn set_contrast(&mut self, value: u32) -> Result<(), anyhow::Error> {
+        self.inner_mut().set_contrast(value)
+    }
+
+    fn list_color_presets(&mut self) -> Vec<(u8, String)> {
+        self.inner_mut().list_color_presets()
+    }
+
+    fn poll_color_preset(&mut self) -> Result<u8, anyhow::Error> {
+        self.inner_mut().poll_color_preset()
+    }
+
+    fn set_color_preset(&mut self, preset: u8) -> Result<(), anyhow::Error> {
+        self.inner_mut().set_color_preset(preset)
+    }
+
+    fn supports_brightness(&self) -> bool {
+        self.inner().supports_brightness()
+    }
+
+    fn has_volume(&self) -> bool {
+        self.inner().has_volume()
+    }
+
+    fn poll_volume(&mut self) -> Result<(u32, u32, u32), anyhow::Error> {
+        self.inner_mut().poll_volume()
+    }
+
+    fn set_volume(&mut self, value: u32) -> Result<(), anyhow::Error> {
+        self.inner_mut().set_volume(value)
+    }
+
+    fn get_vcp(&mut self, code: u8) -> Result<(u16, u16), anyhow::Error> {
+        self.inner_mut().get_vcp(code)
+    }
+
+    fn set_vcp(&mut self, code: u8, value: u16) -> Result<(), anyhow::Error> {
+        self.inner_mut().set_vcp(code, value)
+    }
+
+    fn set_power_mode(&mut self, mode: u8) -> Result<(), anyhow::Error> {
+        self.inner_mut().set_power_mode(mode)
+    }
+
+    fn capabilities(&mut self) -> Result<String, anyhow::Error> {
+        self.inner_mut().capabilities()
+    }
+
+    fn is_software(&self) -> bool {
+        self.inner().is_software()
+    }
+
+    fn is_primary(&self) -> bool {
+        self.inner().is_primary()
+    }
+
+    fn max_luminance(&self) -> Option<u32> {
+        self.inner().max_luminance()
+    }
+
+    fn display_mode(&self) -> Option<DisplayMode> {
+        self.inner().display_mode()
+    }
+}
+
+// DDC/CI monitors discovered through the Win32 physical-monitor API, plus
+// the built-in panel through WMI on laptops
+pub struct WinMonitorProvider;
+
+impl MonitorProvider for WinMonitorProvider {
+    type Monitor = WinMonitor;
+
+    fn new() -> Self {
+        Self
+    }
+
+    // Get complete monitor information (names + handles)
+    fn get_monitors(&mut self) -> Result<Vec<WinMonitor>, anyhow::Error> {
+        let targets = get_display_targets().unwrap_or_else(|e| {
+            log::warn!("QueryDisplayConfig failed: {e}");
+            HashMap::new()
+        });
+        let mut backlights = get_wmi_backlight_monitors();
+        let mut internal_primary = false;
+
+        // Pair each physical monitor with the display path its HMONITOR scans
+        // out to. Anything that can't be matched gets a generic name rather
+        // than a guess that could label the wrong slider.
+        let mut monitors: Vec<Monitor> = Vec::new();
+        let mut software: Vec<WinGammaMonitor> = Vec::new();
+        let mut skipped: Vec<PHYSICAL_MONITOR> = Vec::new();
+        for (hmonitor, handles) in get_physical_monitor_handles()? {
+            let device = gdi_device_name(hmonitor);
+            let source_targets = device.as_ref().and_then(|device| targets.get(device));
+            let primary = is_primary_monitor(hmonitor);
+
+            // Nothing to talk DDC/CI to, so dim it in software as a last
+            // resort — unless it's the built-in panel WMI already covers
+            if handles.is_empty() {
+                let target = source_targets.and_then(|targets| targets.first());
+                let covered = !backlights.is_empty() && target.is_some_and(|t| t.internal);
+                internal_primary |= covered && primary;
+                if let Some(device) = device
+                    && !covered
+                {
+                    let number = backlights.len() + monitors.len() + software.len() + 1;
+                    let TargetInfo {
+                        name,
+                        id,
+                        max_luminance,
+                        display_mode,
+                    } = describe_target(target, number);
+                    software.push(WinGammaMonitor {
+                        name,
+                        id,
+                        device,
+                        max_luminance,
+                        display_mode,
+                        primary,
+                    });
+                }
+                continue;
+            }
+
+            let handle_targets = handle_targets(source_targets.map(Vec::as_slice), handles.len());
+            for (handle, target) in handles.into_iter().zip(handle_targets) {
+                // The built-in panel shows up here too but doesn't speak
+                // DDC/CI — WMI already covers it
+                if !backlights.is_empty() && target.is_some_and(|t| t.internal) {
+                    internal_primary |= primary;
+                    skipped.push(handle);
+                    continue;
+                }
+
+                let number = backlights.len() + monitors.len() + software.len() + 1;
+                monitors.push(Monitor::new(
+                    describe_target(target, number),
+                    handle,
+                    primary,
+                ));
+            }
+        }
+
+        // WMI doesn't say which display a panel is; laptops have just the one
+        if let Some(backlight) = backlights.first_mut() {
+            backlight.primary = internal_primary;
+            backlight.display_mode = targets
+                .values()
+                .flatten()
+                .find(|t| t.internal)
+                .and_then(|t| t.mode);
+        }
+
+        if !skipped.is_empty() {
+            unsafe {
+                let _ = DestroyPhysicalMonitors(&skipped);
+            }
+        }
+
+        // Probe optional features once so the UI doesn't keep asking
+        // monitors that don't have them
+        for monitor in &mut monitors {
+            monitor.supports_brightness = probe_brightness(monitor, &[]);
+            monitor.has_volume = monitor.read_volume().is_ok();
+        }
+
+        Ok(backlights
+            .into_iter()
+            .map(WinMonitor::Backlight)
+            .chain(
+                monitors
+                    .into_iter()
+                    .map(|m| WinMonitor::Ddc(QueuedMonitor::new(m))),
+            )
+            .chain(software.into_iter().map(WinMonitor::Software))
+            .collect())
+    }
+
+    // Dropping a DDC/CI monitor destroys its handle
+    fn cleanup_monitors(&mut self, monitors: &mut Vec<WinMonitor>) {
+        monitors.clear();
+    }
+}
+
+// =========================================================================
+// Ambient light (Sensor API)
+// =========================================================================
+
+/// The first ambient light sensor reported by the Windows Sensor API.
+pub struct WinAmbientSensor {
+    sensor: ISensor,
+}
+
+impl AmbientSensor for WinAmbientSensor {
+    fn open() -> Option<Self> {
+        unsafe {
+            // Already initialised (e.g. by WMI) is fine too
+            let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+
+            let manager: ISensorManager =
+                CoCreateInstance(&SensorManager, None, CLSCTX_INPROC_SERVER).ok()?;
+            let sensors = manager.GetSensorsByType(&SENSOR_TYPE_AMBIENT_LIGHT).ok()?;
+            if sensors.GetCount().ok()? == 0 {
+                return None;
+            }
+
+            Some(Self {
+                sensor: sensors.GetAt(0).ok()?,
+            })
+        }
+    }
+
+    fn read_lux(&mut self) -> Option<f32> {
+        unsafe {
+            let report = self.sensor.GetData().ok()?;
+            let value = report
+                .GetSensorValue(&SENSOR_DATA_TYPE_LIGHT_LEVEL_LUX)
+                .ok()?;
+            PropVariantToDouble(&value).ok().map(|lux| lux as f32)
+        }
+    }
+}
+
+// =========================================================================
+// Fullscreen detection (foreground window vs. its monitor)
+// =========================================================================
+
+pub struct WinFullscreenDetector;
+
+impl FullscreenDetector for WinFullscreenDetector {
+    fn open() -> Option<Self> {
+        Some(Self)
+    }
+
+    fn is_fullscreen(&mut self) -> bool {
+        unsafe {
+            let hwnd = GetForegroundWindow();
+            if hwnd.is_invalid() || hwnd == GetShellWindow() {
+                return false;
+            }
+
+            // The desktop itself covers the monitor too
+            let mut class = [0u16; 64];
+            let len = GetClassNameW(hwnd, &mut class).max(0) as usize;
+            if matches!(
+                String::from_utf16_lossy(&class[..len]).as_str(),
+                "Progman" | "WorkerW"
+            ) {
+                return false;
+            }
+
+            let mut window = RECT::default();
+            if GetWindowRect(hwnd, &mut window).is_err() {
+                return false;
+            }
+            let mut info = MONITORINFO {
+                cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+                ..Default::default()
+            };
+            let hmonitor = MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST);
+            if !GetMonitorInfoW(hmonitor, &mut info).as_bool() {
+                return false;
+            }
+
+            let monitor = info.rcMonitor;
+            window.left <= monitor.left
+                && window.top <= monitor.top
+                && window.right >= monitor.right
+                && window.bottom >= monitor.bottom
+        }
+    }
+}
+
+// =========================================================================
+// Battery (GetSystemPowerStatus)
+// =========================================================================
+
+/// `BatteryFlag` bit set on machines without a battery.
+const NO_SYSTEM_BATTERY: u8 = 128;
+/// `BatteryLifePercent` when the charge isn't known.
+const UNKNOWN_CHARGE: u8 = 255;
+
+/// The battery as `GetSystemPowerStatus` sums it up, across all of them.
+pub struct WinBattery;
+
+impl Battery for WinBattery {
+    fn open() -> Option<Self> {
+        let status = system_power_status()?;
+        (status.BatteryFlag & NO_SYSTEM_BATTERY == 0).then_some(Self)
+    }
+
+    fn charge_on_battery(&mut self) -> Option<u32> {
+        let status = system_power_status()?;
+        // ACLineStatus is 1 on mains power and 255 when it isn't known
+        (status.ACLineStatus == 0 && status.BatteryLifePercent != UNKNOWN_CHARGE)
+            .then(|| status.BatteryLifePercent.into())
+    }
+}
+
+fn system_power_status() -> Option<SYSTEM_POWER_STATUS> {
+    let mut status = SYSTEM_POWER_STATUS::default();
+    unsafe { GetSystemPowerStatus(&mut status) }.ok()?;
+    Some(status)
+}
+
+// =========================================================================
+// Power events (suspend/resume and power-source notifications)
+// =========================================================================
+
+/// Watches for resume and AC/battery switches through power notification
+/// callbacks, so no window or message loop is needed.
+pub struct WinPowerEvents;
+
+/// Handed to the notification callbacks as their context. Leaked, since the
+/// registrations last as long as the process.
+struct PowerWatch {
+    on_event: Box<dyn Fn() + Send + Sync>,
+    /// The power-source registration reports the current source straight
+    /// away; only later changes count.
+    source_reported: AtomicBool,
+}
+
+unsafe extern "system" fn power_callback(
+    context: *const c_void,
+    kind: u32,
+    _setting: *const c_void,
+) -> u32 {
+    let watch = unsafe { &*(context as *const PowerWatch) };
+    match kind {
+        PBT_APMRESUMEAUTOMATIC => (watch.on_event)(),
+        PBT_POWERSETTINGCHANGE if watch.source_reported.swap(true, Ordering::Relaxed) => {
+            (watch.on_event)()
+        }
+        _ => {}
+    }
+    ERROR_SUCCESS.0
+}
+
+impl PowerEvents for WinPowerEvents {
+    fn watch(on_event: Box<dyn Fn() + Send + Sync>) {
+        let watch = Box::leak(Box::new(PowerWatch {
+            on_event,
+            source_reported: AtomicBool::new(false),
+        }));
+        let params = Box::leak(Box::new(DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS {
+            Callback: Some(power_callback),
+            Context: watch as *mut PowerWatch as *mut c_void,
+        }));
+        let recipient = HANDLE(params as *mut DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS as *mut c_void);
+
+        let mut handle = std::ptr::null_mut();
+        let status = unsafe {
+            PowerRegisterSuspendResumeNotification(DEVICE_NOTIFY_CALLBACK, recipient, &mut handle)
+        };
+        if status != ERROR_SUCCESS {
+            log::warn!("Can't watch for resume from sleep: error {}", status.0);
+        }
+
+        let mut handle = std::ptr::null_mut();
+        let status = unsafe {
+            PowerSettingRegisterNotification(
+                &GUID_ACDC_POWER_SOURCE,
+                DEVICE_NOTIFY_CALLBACK,
+                recipient,
+                &mut handle,
+            )
+        };
+        if status != ERROR_SUCCESS {
+            log::warn!("Can't watch for power source changes: error {}", status.0);
+        }
+    }
+}
+
+// =========================================================================
+// Brightness keys
+// =========================================================================
+
+/// Windows already sends brightness keys to monitors that support it, so
+/// there's nothing to forward.
+pub struct WinBrightnessKeys;
+
+impl BrightnessKeys for WinBrightnessKeys {
+    fn watch(_on_key: Box<dyn Fn(bool) + Send + Sync>) -> Result<(), anyhow::Error> {
+        Err(anyhow::anyhow!(
+            "brightness keys are only forwarded on macOS"
+        ))
+    }
+}
+
+// =========================================================================
+// Notifications (WinRT toasts)
+// =========================================================================
+
+/// AppUserModelID toasts are shown under. Apps that aren't packaged need
+/// it registered under `HKCU\Software\Classes\AppUserModelId` with a
+/// display name before Windows will show their toasts.
+const TOAST_APP_ID: &str = "TrayBright";
+const TOAST_APP_KEY: PCWSTR = w!("Software\\Classes\\AppUserModelId\\TrayBright");
+
+/// Tag every toast shares, so each replaces the last instead of piling up
+/// in the notification centre.
+const TOAST_TAG: &str = "brightness";
+
+/// Shows short toasts through the WinRT notification API.
+pub struct WinNotifier;
+
+impl WinNotifier {
+    fn register_app_id() {
+        static REGISTERED: std::sync::Once = std::sync::Once::new();
+        REGISTERED.call_once(|| {
+            let name: Vec<u16> = "Tray Bright"
+                .encode_utf16()
+                .chain(std::iter::once(0))
+                .collect();
+            let status = unsafe {
+                RegSetKeyValueW(
+                    HKEY_CURRENT_USER,
+                    TOAST_APP_KEY,
+                    w!("DisplayName"),
+                    REG_SZ.0,
+                    Some(name.as_ptr().cast()),
+                    std::mem::size_of_val(name.as_slice()) as u32,
+                )
+            };
+            if status != ERROR_SUCCESS {
+                log::warn!("Failed to register the notification app id: {status:?}");
+            }
+        });
+    }
+
+    fn show(message: &str) -> windows::core::Result<()> {
+        Self::register_app_id();
+        let xml = XmlDocument::new()?;
+        xml.LoadXml(&HSTRING::from(format!(
+            "<toast duration=\"short\"><visual><binding template=\"ToastGeneric\">\
+             <text>{}</text></binding></visual></toast>",
+            xml_escape(message)
+        )))?;
+        let toast = ToastNotification::CreateToastNotification(&xml)?;
+        toast.SetTag(&HSTRING::from(TOAST_TAG))?;
+        ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(TOAST_APP_ID))?
+            .Show(&toast)
+    }
+}
+
+impl Notifier for WinNotifier {
+    fn notify(message: &str) {
+        if let Err(e) = Self::show(message) {
+            log::warn!("Failed to show notification: {e}");
+        }
+    }
+}
+
+/// `text` with the characters XML gives meaning to escaped, for monitor
+/// names inside toast markup.
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// =========================================================================
+// Launch at login (HKCU Run key)
+// =========================================================================
+
+const RUN_KEY: PCWSTR = w!("Software\\Microsoft\\Windows\\CurrentVersion\\Run");
+const RUN_VALUE: PCWSTR = w!("TrayBright");
+
+pub struct WinAutostartManager;
+
+impl WinAutostartManager {
+    /// Quoted path to the running executable, as stored in the Run key.
+    fn run_command() -> Option<String> {
+        let exe = std::env::current_exe().ok()?;
+        Some(format!("\"{}\"", exe.display()))
+    }
+}
+
+impl AutostartManager for WinAutostartManager {
+    fn new() -> Self {
+        Self
+    }
+
+    fn is_startup_enabled(&self) -> bool {
+        let Some(expected) = Self::run_command() else {
+            return false;
+        };
+
+        let mut buf = [0u16; 1024];
+        let mut size = std::mem::size_of_val(&buf) as u32;
+        let status = unsafe {
+            RegGetValueW(
+                HKEY_CURRENT_USER,
+                RUN_KEY,
+                RUN_VALUE,
+                RRF_RT_REG_SZ,
+                None,
+                Some(buf.as_mut_ptr().cast()),
+                Some(&mut size),
+            )
+        };
+
+        if status != ERROR_SUCCESS {
+            return false;
+        }
+
+        let len = buf.iter().position(|&c| c == 0).unwrap_or(buf.len());
+        let command = String::from_utf16_lossy(&buf[..len]);
+        command
+            .trim_end_matches(HIDDEN_ARG)
+            .trim_end()
+            .eq_ignore_ascii_case(&expected)
+    }
+
+    fn set_startup_enabled(&self, enabled: bool) -> bool {
+        if !enabled {
+            let status = unsafe { RegDeleteKeyValueW(HKEY_CURRENT_USER, RUN_KEY, RUN_VALUE) };
+            return status == ERROR_SUCCESS || status == ERROR_FILE_NOT_FOUND;
+        }
+
+        let Some(command) = Self::run_command() else {
+            return false;
+        };
+        let command = format!("{command} {HIDDEN_ARG}");
+        let wide: Vec<u16> = command.encode_utf16().chain(std::iter::once(0)).collect();
+
+        let status = unsafe {
+            RegSetKeyValueW(
+                HKEY_CURRENT_USER,
+                RUN_KEY,
+                RUN_VALUE,
+                REG_SZ.0,
+                Some(wide.as_ptr().cast()),
+                std::mem::size_of_val(wide.as_slice()) as u32,
+            )
+        };
+
+        status == ERROR_SUCCESS
+    }
+}
+
+// =========================================================================
+// Window visibility (ShowWindow API)
+// =========================================================================
+
+pub struct WinWindowController {
+    hwnd: isize,
+    visible: Mutex<bool>,
+    /// The window that had focus when we were shown, handed focus back on
+    /// hide. `None` until we've shown the window ourselves.
+    previous: Mutex<Option<isize>>,
+}
+
+impl WindowController for WinWindowController {
+    fn from_raw_handle(handle: RawWindowHandle, _ctx: &egui::Context) -> Option<Self> {
+        if let RawWindowHandle::Win32(h) = handle {
+            let hwnd: isize = h.hwnd.into();
+            Some(Self {
+                hwnd,
+                visible: Mutex::new(true),
+                previous: Mutex::new(None),
+            })
+        } else {
+            None
+        }
+    }
+
+    fn show(&self) {
+        let mut vis = self.visible.lock().unwrap();
+        if !*vis {
+            let hwnd = HWND(self.hwnd as *mut core::ffi::c_void);
+            unsafe {
+                let foreground = GetForegroundWindow();
+                *self.previous.lock().unwrap() =
+                    (!foreground.is_invalid() && foreground != hwnd).then(|| foreground.0 as isize);
+                let _ = ShowWindow(hwnd, SW_SHOWDEFAULT);
+            }
+            *vis = true;
+        }
+    }
+
+    fn hide(&self) {
+        let mut vis = self.visible.lock().unwrap();
+        if *vis {
+            let hwnd = HWND(self.hwnd as *mut core::ffi::c_void);
+            let previous = self.previous.lock().unwrap().take();
+            unsafe {
+                // Only while we still have focus; if the user already moved
+                // on, handing it back would pull them away again
+                let focused = GetForegroundWindow() == hwnd;
+                let _ = ShowWindow(hwnd, SW_HIDE);
+                if let Some(previous) = previous
+                    && focused
+                {
+                    let _ = SetForegroundWindow(HWND(previous as *mut core::ffi::c_void));
+                }
+            }
+            *vis = false;
+        }
+    }
+
+    fn toggle(&self) {
+        let vis = *self.visible.lock().unwrap();
+        if vis {
+            self.hide();
+        } else {
+            self.show();
+        }
+    }
+
+    fn is_visible(&self) -> bool {
+        *self.visible.lock().unwrap()
+    }
+
+    fn set_visible(&self, visible: bool) {
+        if visible {
+            self.show();
+        } else {
+            self.hide();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ddc_monitor(handle: usize) -> WinMonitor {
+        let target = TargetInfo {
+            name: "Test".to_string(),
+            id: "test".to_string(),
+            max_luminance: None,
+            display_mode: None,
+        };
+        let physical = PHYSICAL_MONITOR {
+            hPhysicalMonitor: HANDLE(handle as *mut c_void),
+            ..Default::default()
+        };
+        WinMonitor::Ddc(QueuedMonitor::new(Monitor::new(target, physical, false)))
+    }
+
+    fn destroyed() -> Vec<usize> {
+        DESTROYED.with(|d| d.take().into_iter().map(|h| h.0 as usize).collect())
+    }
+
+    #[test]
+    fn handles_without_a_target_still_get_a_name() {
+        let target = |name: &str| DisplayTarget {
+            name: name.to_string(),
+            device_path: String::new(),
+            internal: false,
+            mode: None,
+        };
+        let targets = [target("DELL U2720Q"), target("LG HDR 4K")];
+        let names: Vec<String> = handle_targets(Some(&targets), 3)
+            .into_iter()
+            .enumerate()
+            .map(|(i, target)| describe_target(target, i + 1).name)
+            .collect();
+        assert_eq!(names, ["DELL U2720Q", "LG HDR 4K", "Display 3"]);
+    }
+
+    #[test]
+    fn dropping_a_monitor_destroys_its_handle() {
+        drop(ddc_monitor(7));
+        assert_eq!(destroyed(), vec![7]);
+    }
+
+    #[test]
+    fn dropping_a_monitor_with_a_queued_write_joins_its_writer_first() {
+        let mut monitor = ddc_monitor(9);
+        let _ = monitor.set_brightness(50);
+        drop(monitor);
+        // Recorded on this thread, so the writer no longer held the handle
+        assert_eq!(destroyed(), vec![9]);
+    }
+
+    #[test]
+    fn cleanup_destroys_each_handle_once() {
+        let mut monitors = vec![ddc_monitor(1), ddc_monitor(2)];
+        WinMonitorProvider.cleanup_monitors(&mut monitors);
+        assert!(monitors.is_empty());
+        assert_eq!(destroyed(), vec![1, 2]);
+        drop(monitors);
+        assert!(destroyed().is_empty());
     }
 }
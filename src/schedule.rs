@@ -0,0 +1,317 @@
+//! Time-of-day brightness schedule ("night mode").
+//!
+//! Configured under `[schedule]` in the config file, e.g.
+//!
+//! ```toml
+//! [schedule]
+//! enabled = true
+//! manual_override_minutes = 30
+//!
+//! [[schedule.entries]]
+//! time = "22:00"
+//! brightness = 20
+//!
+//! [[schedule.entries]]
+//! time = "07:00"
+//! brightness = 80
+//! monitors = { "DEL:41A4:ABC123" = 70 }
+//! ```
+//!
+//! Per-monitor targets are keyed by monitor id; a display name still works
+//! for configs written before ids existed.
+//!
+//! The entry whose time most recently passed is the active one; the worker
+//! applies it once per monitor each time the active entry changes.
+//!
+//! For a gradual change instead of steps, `[curve]` takes keyframes and
+//! brightness moves linearly between them, wrapping past midnight:
+//!
+//! ```toml
+//! [curve]
+//! enabled = true
+//!
+//! [[curve.points]]
+//! time = "07:00"
+//! brightness = 80
+//!
+//! [[curve.points]]
+//! time = "23:00"
+//! brightness = 20
+//! ```
+//!
+//! The worker re-applies the curve every `interval_minutes`.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use chrono::Timelike;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Schedule {
+    /// Off by default so brightness only changes when the user asks.
+    pub enabled: bool,
+    /// A monitor the user adjusted within this many minutes is left alone
+    /// until the window passes.
+    pub manual_override_minutes: u32,
+    pub entries: Vec<ScheduleEntry>,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            manual_override_minutes: 30,
+            entries: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleEntry {
+    /// Local wall-clock time as `HH:MM`.
+    pub time: String,
+    /// Brightness (0-100) for monitors without their own target.
+    pub brightness: u32,
+    /// Per-monitor targets keyed by monitor id, or by display name in
+    /// older configs.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub monitors: BTreeMap<String, u32>,
+}
+
+/// Minutes since midnight for an `HH:MM` time, or `None` if it isn't one.
+fn parse_time(time: &str) -> Option<u32> {
+    let (hours, minutes) = time.trim().split_once(':')?;
+    let (hours, minutes) = (hours.parse::<u32>().ok()?, minutes.parse::<u32>().ok()?);
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+impl ScheduleEntry {
+    /// Minutes since midnight, or `None` if `time` isn't a valid `HH:MM`.
+    pub fn minutes(&self) -> Option<u32> {
+        parse_time(&self.time)
+    }
+
+    /// Target brightness for a monitor, looked up by id and then by name.
+    pub fn target_for(&self, id: &str, name: &str) -> u32 {
+        self.monitors
+            .get(id)
+            .or_else(|| self.monitors.get(name))
+            .copied()
+            .unwrap_or(self.brightness)
+    }
+}
+
+impl Schedule {
+    /// Index of the entry in effect at `now` (minutes since midnight): the
+    /// latest entry at or before `now`, wrapping to yesterday's last entry.
+    /// Entries with an unparseable time are ignored.
+    pub fn active_entry(&self, now: u32) -> Option<usize> {
+        let timed = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| entry.minutes().map(|m| (m, i)));
+
+        let mut latest_before: Option<(u32, usize)> = None;
+        let mut latest_overall: Option<(u32, usize)> = None;
+        for (minutes, i) in timed {
+            if minutes <= now && latest_before.is_none_or(|(m, _)| minutes >= m) {
+                latest_before = Some((minutes, i));
+            }
+            if latest_overall.is_none_or(|(m, _)| minutes >= m) {
+                latest_overall = Some((minutes, i));
+            }
+        }
+
+        latest_before.or(latest_overall).map(|(_, i)| i)
+    }
+}
+
+/// Minutes in a day, where the curve wraps around.
+const DAY_MINUTES: u32 = 24 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Curve {
+    /// Off by default so brightness only changes when the user asks.
+    pub enabled: bool,
+    /// How often the curve is re-applied.
+    pub interval_minutes: u32,
+    /// A monitor the user adjusted within this many minutes is left alone
+    /// until the window passes.
+    pub manual_override_minutes: u32,
+    pub points: Vec<CurvePoint>,
+}
+
+impl Default for Curve {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_minutes: 5,
+            manual_override_minutes: 30,
+            points: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurvePoint {
+    /// Local wall-clock time as `HH:MM`.
+    pub time: String,
+    /// Brightness (0-100) at that time.
+    pub brightness: u32,
+}
+
+impl Curve {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_minutes.max(1) as u64 * 60)
+    }
+
+    /// Brightness at `now` (minutes since midnight), interpolated linearly
+    /// between the keyframes either side of it. The last keyframe of the
+    /// day leads into the first one of the next. Points with an unparseable
+    /// time are ignored; `None` if none are left.
+    pub fn brightness_at(&self, now: u32) -> Option<u32> {
+        let mut points: Vec<(u32, u32)> = self
+            .points
+            .iter()
+            .filter_map(|p| parse_time(&p.time).map(|m| (m, p.brightness)))
+            .collect();
+        points.sort_by_key(|&(minutes, _)| minutes);
+        let first = *points.first()?;
+        let last = *points.last()?;
+
+        let now = now % DAY_MINUTES;
+        let before = points
+            .iter()
+            .rev()
+            .find(|&&(m, _)| m <= now)
+            .copied()
+            .unwrap_or(last);
+        let after = points
+            .iter()
+            .find(|&&(m, _)| m > now)
+            .copied()
+            .unwrap_or(first);
+
+        // Minutes from `before` to `after` and to now, across midnight
+        let span = (after.0 + DAY_MINUTES - before.0) % DAY_MINUTES;
+        if span == 0 {
+            return Some(before.1);
+        }
+        let elapsed = (now + DAY_MINUTES - before.0) % DAY_MINUTES;
+        let t = elapsed as f32 / span as f32;
+        Some((before.1 as f32 + (after.1 as f32 - before.1 as f32) * t).round() as u32)
+    }
+}
+
+/// Current local time as minutes since midnight.
+pub fn local_minutes() -> u32 {
+    let now = chrono::Local::now();
+    now.hour() * 60 + now.minute()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(time: &str, brightness: u32) -> ScheduleEntry {
+        ScheduleEntry {
+            time: time.to_string(),
+            brightness,
+            monitors: BTreeMap::new(),
+        }
+    }
+
+    fn schedule(entries: Vec<ScheduleEntry>) -> Schedule {
+        Schedule {
+            enabled: true,
+            entries,
+            ..Schedule::default()
+        }
+    }
+
+    #[test]
+    fn picks_latest_entry_before_now() {
+        let s = schedule(vec![entry("22:00", 20), entry("07:00", 80)]);
+        assert_eq!(s.active_entry(12 * 60), Some(1));
+        assert_eq!(s.active_entry(22 * 60), Some(0));
+        assert_eq!(s.active_entry(23 * 60 + 59), Some(0));
+    }
+
+    #[test]
+    fn wraps_to_previous_day() {
+        let s = schedule(vec![entry("22:00", 20), entry("07:00", 80)]);
+        assert_eq!(s.active_entry(3 * 60), Some(0));
+    }
+
+    #[test]
+    fn ignores_invalid_times() {
+        let s = schedule(vec![
+            entry("25:00", 20),
+            entry("soon", 10),
+            entry("07:00", 80),
+        ]);
+        assert_eq!(s.active_entry(3 * 60), Some(2));
+        assert_eq!(schedule(vec![entry("bad", 1)]).active_entry(0), None);
+    }
+
+    fn curve(points: &[(&str, u32)]) -> Curve {
+        Curve {
+            enabled: true,
+            points: points
+                .iter()
+                .map(|&(time, brightness)| CurvePoint {
+                    time: time.to_string(),
+                    brightness,
+                })
+                .collect(),
+            ..Curve::default()
+        }
+    }
+
+    #[test]
+    fn curve_hits_keyframes_exactly() {
+        let c = curve(&[("07:00", 80), ("12:00", 100), ("20:00", 50), ("23:00", 20)]);
+        assert_eq!(c.brightness_at(7 * 60), Some(80));
+        assert_eq!(c.brightness_at(12 * 60), Some(100));
+        assert_eq!(c.brightness_at(20 * 60), Some(50));
+        assert_eq!(c.brightness_at(23 * 60), Some(20));
+    }
+
+    #[test]
+    fn curve_interpolates_between_keyframes() {
+        let c = curve(&[("12:00", 100), ("07:00", 80), ("20:00", 50)]);
+        assert_eq!(c.brightness_at(9 * 60 + 30), Some(90));
+        assert_eq!(c.brightness_at(16 * 60), Some(75));
+    }
+
+    #[test]
+    fn curve_wraps_past_midnight() {
+        let c = curve(&[("07:00", 80), ("23:00", 20)]);
+        // 23:00 -> 07:00 is 8 hours, 20% -> 80%
+        assert_eq!(c.brightness_at(60), Some(35));
+        assert_eq!(c.brightness_at(3 * 60), Some(50));
+        assert_eq!(c.brightness_at(23 * 60 + 30), Some(24));
+        assert_eq!(c.brightness_at(6 * 60 + 59), Some(80));
+    }
+
+    #[test]
+    fn curve_with_one_or_no_valid_points() {
+        assert_eq!(curve(&[("21:00", 40)]).brightness_at(5 * 60), Some(40));
+        assert_eq!(curve(&[("bad", 40)]).brightness_at(5 * 60), None);
+        assert_eq!(curve(&[]).brightness_at(0), None);
+    }
+
+    #[test]
+    fn per_monitor_targets_override_default() {
+        let mut e = entry("07:00", 80);
+        e.monitors.insert("DEL:41A4:ABC123".to_string(), 70);
+        e.monitors.insert("LG ULTRAFINE".to_string(), 60);
+        assert_eq!(e.target_for("DEL:41A4:ABC123", "DELL U2720Q"), 70);
+        assert_eq!(e.target_for("DEL:41A4:XYZ789", "DELL U2720Q"), 80);
+        assert_eq!(e.target_for("GSM:5B7F:1", "LG ULTRAFINE"), 60);
+    }
+}
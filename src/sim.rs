@@ -0,0 +1,258 @@
+//! Fake monitors for `--simulate`, so the whole UI and worker can run on any
+//! machine, without DDC/CI or even a second display.
+//!
+//! Each simulated monitor keeps its brightness, contrast, volume, colour
+//! preset and raw VCP values in memory. They live in the provider rather
+//! than the handles, so re-enumeration hands back monitors that remember
+//! what was last written, like real hardware would.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use crate::os::{DisplayMode, MonitorHandle, MonitorProvider};
+use crate::vcp;
+
+/// Monitors `--simulate` creates when no count is given.
+pub const DEFAULT_COUNT: usize = 2;
+
+/// VCP codes of the features with their own controls.
+const VCP_BRIGHTNESS: u8 = 0x10;
+const VCP_CONTRAST: u8 = 0x12;
+const VCP_VOLUME: u8 = 0x62;
+
+/// Colour presets every simulated monitor offers.
+const COLOR_PRESETS: [(u8, &str); 3] = [(0x05, "6500 K"), (0x06, "7500 K"), (0x0b, "User 1")];
+
+/// What a simulated monitor would hold in its own memory.
+#[derive(Debug)]
+struct SimState {
+    brightness: u32,
+    contrast: u32,
+    volume: u32,
+    color_preset: u8,
+    /// VCP codes written through [`MonitorHandle::set_vcp`] that have no
+    /// dedicated field.
+    vcp: BTreeMap<u8, u16>,
+}
+
+impl Default for SimState {
+    fn default() -> Self {
+        Self {
+            brightness: 50,
+            contrast: 50,
+            volume: 30,
+            color_preset: COLOR_PRESETS[0].0,
+            vcp: BTreeMap::new(),
+        }
+    }
+}
+
+pub struct SimMonitorProvider {
+    states: Vec<Arc<Mutex<SimState>>>,
+}
+
+impl SimMonitorProvider {
+    /// A provider that finds `count` monitors, each starting at 50%.
+    pub fn with_count(count: usize) -> Self {
+        Self {
+            states: (0..count).map(|_| Arc::default()).collect(),
+        }
+    }
+}
+
+impl MonitorProvider for SimMonitorProvider {
+    type Monitor = SimMonitor;
+
+    fn new() -> Self {
+        Self::with_count(DEFAULT_COUNT)
+    }
+
+    fn get_monitors(&mut self) -> anyhow::Result<Vec<SimMonitor>> {
+        Ok(self
+            .states
+            .iter()
+            .enumerate()
+            .map(|(i, state)| SimMonitor {
+                index: i + 1,
+                name: format!("Simulated {}", i + 1),
+                state: state.clone(),
+            })
+            .collect())
+    }
+
+    fn cleanup_monitors(&mut self, monitors: &mut Vec<SimMonitor>) {
+        monitors.clear();
+    }
+}
+
+/// One fake monitor. The first is the primary one; every monitor supports
+/// all the controls, with 0-100 ranges.
+pub struct SimMonitor {
+    /// 1-based, as in the name.
+    index: usize,
+    name: String,
+    state: Arc<Mutex<SimState>>,
+}
+
+impl SimMonitor {
+    fn state(&self) -> std::sync::MutexGuard<'_, SimState> {
+        self.state.lock().unwrap()
+    }
+}
+
+impl MonitorHandle for SimMonitor {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn id(&self) -> String {
+        format!("SIM:{}", self.index)
+    }
+
+    fn poll_brightness(&mut self) -> anyhow::Result<(u32, u32, u32)> {
+        Ok((self.state().brightness, 0, 100))
+    }
+
+    fn set_brightness(&mut self, value: u32) -> anyhow::Result<()> {
+        self.state().brightness = value.min(100);
+        Ok(())
+    }
+
+    fn poll_contrast(&mut self) -> anyhow::Result<(u32, u32, u32)> {
+        Ok((self.state().contrast, 0, 100))
+    }
+
+    fn set_contrast(&mut self, value: u32) -> anyhow::Result<()> {
+        self.state().contrast = value.min(100);
+        Ok(())
+    }
+
+    fn list_color_presets(&mut self) -> Vec<(u8, String)> {
+        COLOR_PRESETS
+            .iter()
+            .map(|&(code, name)| (code, name.to_string()))
+            .collect()
+    }
+
+    fn poll_color_preset(&mut self) -> anyhow::Result<u8> {
+        Ok(self.state().color_preset)
+    }
+
+    fn set_color_preset(&mut self, preset: u8) -> anyhow::Result<()> {
+        if !COLOR_PRESETS.iter().any(|&(code, _)| code == preset) {
+            anyhow::bail!("{} has no colour preset {preset:#04x}", self.name);
+        }
+        self.state().color_preset = preset;
+        Ok(())
+    }
+
+    fn supports_brightness(&self) -> bool {
+        true
+    }
+
+    fn has_volume(&self) -> bool {
+        true
+    }
+
+    fn poll_volume(&mut self) -> anyhow::Result<(u32, u32, u32)> {
+        Ok((self.state().volume, 0, 100))
+    }
+
+    fn set_volume(&mut self, value: u32) -> anyhow::Result<()> {
+        self.state().volume = value.min(100);
+        Ok(())
+    }
+
+    fn get_vcp(&mut self, code: u8) -> anyhow::Result<(u16, u16)> {
+        let state = self.state();
+        let value = match code {
+            VCP_BRIGHTNESS => state.brightness as u16,
+            VCP_CONTRAST => state.contrast as u16,
+            VCP_VOLUME => state.volume as u16,
+            vcp::COLOR_PRESET => return Ok((state.color_preset.into(), 0)),
+            _ => state.vcp.get(&code).copied().unwrap_or(0),
+        };
+        Ok((value, 100))
+    }
+
+    fn set_vcp(&mut self, code: u8, value: u16) -> anyhow::Result<()> {
+        match code {
+            VCP_BRIGHTNESS => self.set_brightness(value.into()),
+            VCP_CONTRAST => self.set_contrast(value.into()),
+            VCP_VOLUME => self.set_volume(value.into()),
+            vcp::COLOR_PRESET => self.set_color_preset(value as u8),
+            _ => {
+                self.state().vcp.insert(code, value);
+                Ok(())
+            }
+        }
+    }
+
+    fn set_power_mode(&mut self, mode: u8) -> anyhow::Result<()> {
+        self.set_vcp(vcp::POWER_MODE, mode.into())
+    }
+
+    fn capabilities(&mut self) -> anyhow::Result<String> {
+        let presets: Vec<String> = COLOR_PRESETS
+            .iter()
+            .map(|&(code, _)| format!("{code:02X}"))
+            .collect();
+        Ok(format!(
+            "(prot(monitor)type(LCD)model(Simulated {})cmds(01 02 03 0C)vcp(10 12 14({}) 62)mccs_ver(2.2))",
+            self.index,
+            presets.join(" ")
+        ))
+    }
+
+    fn is_software(&self) -> bool {
+        false
+    }
+
+    fn is_primary(&self) -> bool {
+        self.index == 1
+    }
+
+    fn max_luminance(&self) -> Option<u32> {
+        None
+    }
+
+    fn display_mode(&self) -> Option<DisplayMode> {
+        Some(DisplayMode {
+            width: 2560,
+            height: 1440,
+            refresh_hz: Some(60.0),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_survive_re_enumeration() {
+        let mut provider = SimMonitorProvider::with_count(2);
+        let mut monitors = provider.get_monitors().unwrap();
+        monitors[1].set_brightness(80).unwrap();
+        monitors[1].set_vcp(0x60, 0x0f).unwrap();
+        provider.cleanup_monitors(&mut monitors);
+
+        let mut monitors = provider.get_monitors().unwrap();
+        assert_eq!(monitors[0].poll_brightness().unwrap(), (50, 0, 100));
+        assert_eq!(monitors[1].poll_brightness().unwrap(), (80, 0, 100));
+        assert_eq!(monitors[1].get_vcp(0x60).unwrap(), (0x0f, 100));
+    }
+
+    #[test]
+    fn vcp_codes_with_controls_share_their_values() {
+        let mut monitor = SimMonitorProvider::with_count(1)
+            .get_monitors()
+            .unwrap()
+            .remove(0);
+        monitor.set_vcp(VCP_BRIGHTNESS, 70).unwrap();
+        assert_eq!(monitor.poll_brightness().unwrap().0, 70);
+        monitor.set_volume(10).unwrap();
+        assert_eq!(monitor.get_vcp(VCP_VOLUME).unwrap(), (10, 100));
+        assert!(monitor.set_color_preset(0x01).is_err());
+    }
+}
@@ -0,0 +1,299 @@
+//! System tray icon and its context menu.
+//!
+//! Menu and click events are handled through `tray-icon`'s global event
+//! handlers rather than polled from the eframe loop, because the loop stops
+//! running while the window is hidden — which is when the tray is used most.
+//! The tooltip shows the average brightness, refreshed from the eframe loop
+//! and whenever the pointer enters the icon.
+
+use std::cell::RefCell;
+use std::sync::mpsc::{Receiver, Sender};
+
+use eframe::egui;
+
+use tray_icon::menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tray_icon::{Icon, MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+
+use crate::hotkeys;
+use crate::i18n::{tr, tr_args};
+use crate::os::{AutostartManager, PlatformAutostart};
+use crate::ui::{MonitorCmd, TrayRequest, load_icon_rgba};
+use crate::vcp;
+
+/// Brightness presets offered in each monitor's submenu, in percent.
+const BRIGHTNESS_PRESETS: [u32; 4] = [25, 50, 75, 100];
+
+/// Menu id prefix for brightness presets: `brightness:<monitor>:<percent>`.
+const BRIGHTNESS_ID_PREFIX: &str = "brightness:";
+
+/// Menu id prefix for display power modes: `power:<mode>` for every
+/// monitor, `power:<mode>:<monitor>` for one.
+const POWER_ID_PREFIX: &str = "power:";
+
+/// Menu id prefix for profiles: `profile:<name>`.
+const PROFILE_ID_PREFIX: &str = "profile:";
+
+/// Position of the first per-monitor submenu, after "Show" and a separator.
+const FIRST_MONITOR_POSITION: usize = 2;
+
+/// The tray icon and the parts of it that change after it's built.
+struct Tray {
+    icon: TrayIcon,
+    /// Average brightness from the worker, for the tooltip.
+    averages: Receiver<u32>,
+    /// Rebuilt whenever profiles are saved or deleted.
+    profiles: Submenu,
+    menu: Menu,
+    /// One brightness submenu per monitor, replaced when the list changes.
+    monitors: Vec<Submenu>,
+    /// Ticked when start-on-login is on, also switched from the settings
+    /// view.
+    autostart: CheckMenuItem,
+}
+
+thread_local! {
+    /// The icon has to stay alive, and on the thread that created it, for it
+    /// to remain in the tray. Kept next to the worker's average-brightness
+    /// channel and the profiles submenu so both can be refreshed from the
+    /// same thread.
+    static TRAY: RefCell<Option<Tray>> = const { RefCell::new(None) };
+}
+
+/// Build the tray icon on the current (event-loop) thread. `averages`
+/// carries the average brightness whenever it changes.
+pub fn create_tray_icon(
+    monitor_names: &[String],
+    profile_names: &[String],
+    averages: Receiver<u32>,
+) {
+    let (rgba, width, height) = load_icon_rgba();
+    let icon = Icon::from_rgba(rgba, width, height).expect("Failed to create tray icon");
+
+    // Create context menu
+    let menu = Menu::new();
+    let show_item = MenuItem::with_id("show", tr("show"), true, None);
+    let autostart_item = CheckMenuItem::with_id(
+        "autostart",
+        tr("start_on_login"),
+        true,
+        PlatformAutostart::new().is_startup_enabled(),
+        None,
+    );
+    let profiles = Submenu::new(tr("profiles"), true);
+    fill_profiles_submenu(&profiles, profile_names);
+    let reset_item = MenuItem::with_id("reset_all", tr("reset_all"), true, None);
+    let sleep_all_item = MenuItem::with_id(power_id(vcp::POWER_OFF), tr("sleep_all"), true, None);
+    let wake_all_item = MenuItem::with_id(power_id(vcp::POWER_ON), tr("wake_all"), true, None);
+    let quit_item = MenuItem::with_id("quit", tr("quit"), true, None);
+
+    menu.append(&show_item).unwrap();
+    menu.append(&PredefinedMenuItem::separator()).unwrap();
+    let monitors: Vec<Submenu> = monitor_names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| brightness_submenu(i, name))
+        .collect();
+    for submenu in &monitors {
+        menu.append(submenu).unwrap();
+    }
+    menu.append(&profiles).unwrap();
+    menu.append(&reset_item).unwrap();
+    menu.append(&sleep_all_item).unwrap();
+    menu.append(&wake_all_item).unwrap();
+    menu.append(&PredefinedMenuItem::separator()).unwrap();
+    menu.append(&autostart_item).unwrap();
+    menu.append(&quit_item).unwrap();
+
+    let tray_icon = TrayIconBuilder::new()
+        .with_menu(Box::new(menu.clone()))
+        .with_menu_on_left_click(false) // Only show menu on right-click
+        .with_tooltip(tr("tray_tooltip"))
+        .with_icon(icon)
+        .build()
+        .unwrap();
+
+    TRAY.with(|t| {
+        *t.borrow_mut() = Some(Tray {
+            icon: tray_icon,
+            averages,
+            profiles,
+            menu,
+            monitors,
+            autostart: autostart_item,
+        })
+    });
+    refresh_tooltip();
+}
+
+/// Show the latest average brightness in the tooltip. No-op when nothing
+/// changed or when called from a thread other than the tray's.
+pub fn refresh_tooltip() {
+    TRAY.with(|t| {
+        if let Some(tray) = t.borrow().as_ref()
+            && let Some(average) = tray.averages.try_iter().last()
+        {
+            let _ = tray.icon.set_tooltip(Some(tr_args(
+                "tray_tooltip_average",
+                &[("value", &average)],
+            )));
+        }
+    });
+}
+
+/// List `names` in the profiles submenu after they change. No-op when
+/// called from a thread other than the tray's.
+pub fn set_profiles(names: &[String]) {
+    TRAY.with(|t| {
+        if let Some(tray) = t.borrow().as_ref() {
+            fill_profiles_submenu(&tray.profiles, names);
+        }
+    });
+}
+
+/// Tick or untick "Start on login" after it's switched from the settings
+/// view. No-op when called from a thread other than the tray's.
+pub fn set_autostart_checked(checked: bool) {
+    TRAY.with(|t| {
+        if let Some(tray) = t.borrow().as_ref() {
+            tray.autostart.set_checked(checked);
+        }
+    });
+}
+
+/// Rebuild the per-monitor brightness submenus after the monitor list
+/// changes. No-op when called from a thread other than the tray's.
+pub fn set_monitors(names: &[String]) {
+    TRAY.with(|t| {
+        if let Some(tray) = t.borrow_mut().as_mut() {
+            for submenu in tray.monitors.drain(..) {
+                let _ = tray.menu.remove(&submenu);
+            }
+            for (i, name) in names.iter().enumerate() {
+                let submenu = brightness_submenu(i, name);
+                let _ = tray.menu.insert(&submenu, FIRST_MONITOR_POSITION + i);
+                tray.monitors.push(submenu);
+            }
+        }
+    });
+}
+
+/// Replace the submenu's items with one per profile, or a disabled
+/// placeholder when there are none.
+fn fill_profiles_submenu(submenu: &Submenu, names: &[String]) {
+    while submenu.remove_at(0).is_some() {}
+    if names.is_empty() {
+        let item = MenuItem::new(tr("no_profiles"), false, None);
+        submenu.append(&item).unwrap();
+    }
+    for name in names {
+        let item = MenuItem::with_id(format!("{PROFILE_ID_PREFIX}{name}"), name, true, None);
+        submenu.append(&item).unwrap();
+    }
+}
+
+fn brightness_submenu(index: usize, name: &str) -> Submenu {
+    let submenu = Submenu::new(name, true);
+    for percent in BRIGHTNESS_PRESETS {
+        let item = MenuItem::with_id(
+            format!("{BRIGHTNESS_ID_PREFIX}{index}:{percent}"),
+            format!("{percent}%"),
+            true,
+            None,
+        );
+        submenu.append(&item).unwrap();
+    }
+    let power_off = MenuItem::with_id(
+        format!("{}:{index}", power_id(vcp::POWER_OFF)),
+        tr("power_off"),
+        true,
+        None,
+    );
+    let wake = MenuItem::with_id(
+        format!("{}:{index}", power_id(vcp::POWER_ON)),
+        tr("wake_display"),
+        true,
+        None,
+    );
+    submenu.append(&PredefinedMenuItem::separator()).unwrap();
+    submenu.append(&power_off).unwrap();
+    submenu.append(&wake).unwrap();
+    submenu
+}
+
+/// Menu id setting every monitor to power `mode`.
+fn power_id(mode: u8) -> String {
+    format!("{POWER_ID_PREFIX}{mode}")
+}
+
+/// Parse a `power:<mode>` or `power:<mode>:<monitor>` menu id.
+fn parse_power_id(id: &str) -> Option<(Option<usize>, u8)> {
+    let rest = id.strip_prefix(POWER_ID_PREFIX)?;
+    match rest.split_once(':') {
+        Some((mode, index)) => Some((Some(index.parse().ok()?), mode.parse().ok()?)),
+        None => Some((None, rest.parse().ok()?)),
+    }
+}
+
+/// Parse a `brightness:<monitor>:<percent>` menu id.
+fn parse_brightness_id(id: &str) -> Option<(usize, u32)> {
+    let (index, percent) = id.strip_prefix(BRIGHTNESS_ID_PREFIX)?.split_once(':')?;
+    Some((index.parse().ok()?, percent.parse().ok()?))
+}
+
+pub fn setup_event_handlers(tx_cmd: Sender<MonitorCmd>, tx_ui: Sender<TrayRequest>) {
+    // Handle tray icon click events
+    TrayIconEvent::set_event_handler(Some(move |event: TrayIconEvent| match event {
+        TrayIconEvent::Click {
+            button: MouseButton::Left,
+            button_state: MouseButtonState::Up,
+            rect,
+            ..
+        } => {
+            let min = egui::pos2(rect.position.x as f32, rect.position.y as f32);
+            let size = egui::vec2(rect.size.width as f32, rect.size.height as f32);
+            crate::set_tray_anchor(egui::Rect::from_min_size(min, size));
+            crate::toggle_window_visibility();
+        }
+        // Not reported on Linux, where the eframe loop refresh has to do
+        TrayIconEvent::Enter { .. } => refresh_tooltip(),
+        _ => {}
+    }));
+
+    // Handle menu events
+    MenuEvent::set_event_handler(Some(move |event: MenuEvent| match event.id.0.as_str() {
+        "show" => {
+            crate::show_window();
+        }
+        "reset_all" => {
+            let _ = tx_cmd.send(MonitorCmd::ResetAll);
+        }
+        "autostart" => {
+            // The click already flipped the tick; it's put back to match
+            // the login entry until the change is confirmed or fails
+            let autostart = PlatformAutostart::new();
+            if autostart.is_startup_enabled() {
+                set_autostart_checked(true);
+                let _ = tx_ui.send(TrayRequest::ConfirmAutostartOff);
+                crate::show_window();
+            } else if !autostart.set_startup_enabled(true) {
+                set_autostart_checked(false);
+                let _ = tx_ui.send(TrayRequest::AutostartFailed);
+                crate::show_window();
+            }
+        }
+        "quit" => {
+            hotkeys::unregister();
+            // Exit immediately - can't rely on event loop when window is hidden
+            std::process::exit(0);
+        }
+        id => {
+            if let Some((index, percent)) = parse_brightness_id(id) {
+                let _ = tx_cmd.send(MonitorCmd::SetBrightnessPercent(index, percent));
+            } else if let Some((index, mode)) = parse_power_id(id) {
+                let _ = tx_cmd.send(MonitorCmd::PowerMode(index, mode));
+            } else if let Some(name) = id.strip_prefix(PROFILE_ID_PREFIX) {
+                let _ = tx_cmd.send(MonitorCmd::ApplyProfile(name.to_string()));
+            }
+        }
+    }));
+}
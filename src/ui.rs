@@ -1,35 +1,655 @@
 use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    path::{Path, PathBuf},
     sync::{
-        Arc,
+        Arc, Mutex,
         atomic::{AtomicBool, Ordering},
-        mpsc::{Receiver, RecvTimeoutError, Sender, channel},
+        mpsc::{Receiver, RecvTimeoutError, Sender, TryRecvError, channel},
     },
     time::{Duration, Instant},
 };
 
-use eframe::egui::{self, RichText};
+use eframe::egui::{self, Key, Modifiers, RichText};
 
-use crate::platform::{cleanup_monitors, get_monitors};
+use crate::ambient;
+use crate::config::{
+    BrightnessLimits, BrightnessScale, Config, PollSettings, Profile, ThemeMode, rekey_by_id,
+    rekey_set_by_id, setup_key,
+};
+use crate::edid;
+use crate::history::{self, History};
+use crate::i18n::{self, tr, tr_args};
+use crate::monitors::{
+    ConnectWatcher, ContentionTracker, VcpDump, WriteLimiter, apply_on_exit, clamp_presets,
+    decrease_brightness, dump_vcp, from_hardware, increase_brightness, limit_range,
+    offset_brightness, percent_of_range, primary_first, range_to_percent, test_ddc, to_hardware,
+};
+use crate::os::{
+    AmbientSensor, AutostartManager, Battery, DisplayMode, FullscreenDetector, MonitorHandle,
+    MonitorProvider, Notifier, PlatformAmbientSensor, PlatformAutostart, PlatformBattery,
+    PlatformFullscreenDetector, PlatformMonitorProvider, PlatformNotifier, SetupRequired,
+};
+use crate::schedule::{self, Curve, Schedule};
+use crate::vcp;
+
+pub enum MonitorCmd {
+    SetBrightness(usize, u32),                  // Monitor Index, value
+    SetContrast(usize, u32),                    // Monitor Index, value
+    SetColorPreset(usize, u8),                  // Monitor Index, VCP 0x14 value
+    SetVolume(usize, u32),                      // Monitor Index, value
+    SetBrightnessPercent(usize, u32),           // Monitor Index, percent of its range
+    IncreaseAll(u32),                           // Percent of each monitor's range
+    DecreaseAll(u32),                           // Percent of each monitor's range
+    RestoreAll,                                 // Every monitor to full brightness (panic hotkey)
+    Retry(usize),                               // Monitor Index to read again after an error
+    Identify(usize),                            // Monitor Index to flash so the user can find it
+    PowerMode(Option<usize>, u8),               // Monitor Index (None for all), VCP 0xD6 power mode
+    SetDisabled(usize, bool),                   // Monitor Index, hide from the panel
+    SetTheme(ThemeMode),                        // Persist the window theme
+    SetCompact(bool),                           // Persist the compact layout choice
+    SetAlwaysOnTop(bool),                       // Persist the pin button's state
+    SaveSettings(Box<Settings>),                // Everything from the settings view
+    Rescan,                                     // Enumerate monitors again right away
+    SetDefault(usize, Option<u32>),             // Monitor Index, brightness to reset to
+    ResetAll,                                   // Every monitor back to its default
+    Rename(usize, Option<String>),              // Monitor Index, custom name (None resets)
+    SetLimits(usize, Option<BrightnessLimits>), // Monitor Index, soft brightness range
+    SetInverted(usize, bool),                   // Monitor Index, draw its slider flipped
+    SetSyncOffset(usize, i32),                  // Monitor Index, percent added to the master slider
+    PowerChanged,                               // Woke from sleep or switched power source
+    TestDdc,                                    // Nudge every shown monitor to check DDC/CI
+    DismissDdcHelp,                             // Don't show the DDC/CI help again
+    DumpVcp,                                    // Read raw DDC/CI data for the debug panel
+    ApplyProfile(String),                       // Profile name
+    SaveProfile(String, Option<Profile>),       // Profile name, brightness by id (None deletes)
+    SaveGroup(String, Option<Vec<String>>),     // Group name, member ids (None deletes)
+}
+
+/// Things the tray menu needs the window for. The tray shows the window
+/// after sending one.
+pub enum TrayRequest {
+    /// "Start on login" was unticked; ask before removing the login entry.
+    ConfirmAutostartOff,
+    /// "Start on login" was ticked but the login entry couldn't be written.
+    AutostartFailed,
+}
+
+/// Latest name and `(current, min, max)` brightness per monitor, published
+/// by the worker for readers outside the UI such as the HTTP API.
+pub type MonitorSnapshot = Arc<Mutex<Vec<(String, (u32, u32, u32))>>>;
+
+/// The config fields edited in the settings view, saved together.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    theme: ThemeMode,
+    compact: bool,
+    polling: PollSettings,
+    step_percent: u32,
+    fine_step_percent: u32,
+    schedule_enabled: bool,
+    curve_enabled: bool,
+    notify_changes: bool,
+    hide_on_focus_loss: bool,
+    remember_per_setup: bool,
+}
+
+impl Settings {
+    fn apply_to(&self, config: &mut Config) {
+        config.theme = self.theme;
+        config.compact = self.compact;
+        config.polling = self.polling;
+        config.step_percent = self.step_percent;
+        config.fine_step_percent = self.fine_step_percent;
+        config.schedule.enabled = self.schedule_enabled;
+        config.curve.enabled = self.curve_enabled;
+        config.notify_changes = self.notify_changes;
+        config.hide_on_focus_loss = self.hide_on_focus_loss;
+        config.remember_per_setup = self.remember_per_setup;
+    }
+}
+
+/// Settings being edited, and what they were when the view opened so Save
+/// is only offered once something changed. Start-on-login isn't in the
+/// config; it's applied through [`AutostartManager`] on save.
+struct SettingsDraft {
+    saved: Settings,
+    settings: Settings,
+    autostart_saved: bool,
+    autostart: bool,
+}
+
+/// Commands drained from the channel in one worker tick, collapsed to the
+/// latest value per monitor.
+struct PendingCmds {
+    brightness: Vec<Option<u32>>,
+    /// What asked for each queued brightness, for the history log.
+    source: Vec<Option<history::Source>>,
+    /// Set by the panic key, whose full brightness gets past hiding, the
+    /// write limiter, the user's limits and the battery cap.
+    force_full: Vec<bool>,
+    contrast: Vec<Option<u32>>,
+    color_preset: Vec<Option<u8>>,
+    volume: Vec<Option<u32>>,
+    retry: Vec<bool>,
+    identify: Vec<bool>,
+    /// Power mode (VCP 0xD6) to set, sleep or wake.
+    power_mode: Vec<Option<u8>>,
+    disabled: Vec<Option<bool>>,
+    theme: Option<ThemeMode>,
+    compact: Option<bool>,
+    always_on_top: Option<bool>,
+    settings: Option<Box<Settings>>,
+    rescan: bool,
+    default: Vec<Option<Option<u32>>>,
+    reset_all: bool,
+    rename: Vec<Option<Option<String>>>,
+    limits: Vec<Option<Option<BrightnessLimits>>>,
+    inverted: Vec<Option<bool>>,
+    sync_offsets: Vec<Option<i32>>,
+    power_changed: bool,
+    test_ddc: bool,
+    dismiss_ddc_help: bool,
+    dump_vcp: bool,
+    apply_profile: Option<String>,
+    /// Profile saves and deletions, in the order they were sent.
+    profiles: Vec<(String, Option<Profile>)>,
+    /// Group changes, in the order they were sent.
+    groups: Vec<(String, Option<Vec<String>>)>,
+}
+
+impl PendingCmds {
+    fn new(monitor_count: usize) -> Self {
+        Self {
+            brightness: vec![None; monitor_count],
+            source: vec![None; monitor_count],
+            force_full: vec![false; monitor_count],
+            contrast: vec![None; monitor_count],
+            color_preset: vec![None; monitor_count],
+            volume: vec![None; monitor_count],
+            retry: vec![false; monitor_count],
+            identify: vec![false; monitor_count],
+            power_mode: vec![None; monitor_count],
+            disabled: vec![None; monitor_count],
+            theme: None,
+            compact: None,
+            always_on_top: None,
+            settings: None,
+            rescan: false,
+            default: vec![None; monitor_count],
+            reset_all: false,
+            rename: vec![None; monitor_count],
+            limits: vec![None; monitor_count],
+            inverted: vec![None; monitor_count],
+            sync_offsets: vec![None; monitor_count],
+            power_changed: false,
+            test_ddc: false,
+            dump_vcp: false,
+            dismiss_ddc_help: false,
+            apply_profile: None,
+            profiles: Vec::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    /// Drop everything queued for monitor `idx`.
+    fn clear(&mut self, idx: usize) {
+        self.brightness[idx] = None;
+        self.source[idx] = None;
+        self.force_full[idx] = false;
+        self.contrast[idx] = None;
+        self.color_preset[idx] = None;
+        self.volume[idx] = None;
+        self.retry[idx] = false;
+        self.identify[idx] = false;
+        self.power_mode[idx] = None;
+    }
+
+    /// Whether monitor `idx`, whose range tops out at `max`, still has the
+    /// panic key's full brightness queued rather than something sent after.
+    fn forces_full(&self, idx: usize, max: u32) -> bool {
+        self.force_full[idx] && self.brightness[idx] == Some(max)
+    }
+
+    /// Attribute brightness queued since the last call to `source`, unless
+    /// a command already claimed it.
+    fn tag(&mut self, source: history::Source) {
+        for (tag, value) in self.source.iter_mut().zip(&self.brightness) {
+            if value.is_some() {
+                tag.get_or_insert(source);
+            }
+        }
+    }
+
+    /// Fold a command into the pending set. Relative steps build on any
+    /// value already queued this tick, falling back to the last known level.
+    fn queue(&mut self, cmd: MonitorCmd, levels: &[(u32, u32, u32)]) {
+        match cmd {
+            // Indices can be stale if the UI sent them just before the
+            // monitor list changed — drop those rather than panic.
+            MonitorCmd::SetBrightness(idx, val) => {
+                if let Some(slot) = self.brightness.get_mut(idx) {
+                    *slot = Some(val);
+                    self.source[idx] = Some(history::Source::User);
+                }
+            }
+            MonitorCmd::SetContrast(idx, val) => {
+                if let Some(slot) = self.contrast.get_mut(idx) {
+                    *slot = Some(val);
+                }
+            }
+            MonitorCmd::SetColorPreset(idx, preset) => {
+                if let Some(slot) = self.color_preset.get_mut(idx) {
+                    *slot = Some(preset);
+                }
+            }
+            MonitorCmd::SetVolume(idx, val) => {
+                if let Some(slot) = self.volume.get_mut(idx) {
+                    *slot = Some(val);
+                }
+            }
+            MonitorCmd::SetBrightnessPercent(idx, percent) => {
+                if let (Some(slot), Some(&(_, min, max))) =
+                    (self.brightness.get_mut(idx), levels.get(idx))
+                {
+                    *slot = Some(percent_of_range(min, max, percent));
+                    self.source[idx] = Some(history::Source::User);
+                }
+            }
+            MonitorCmd::IncreaseAll(percent) => {
+                for (idx, &(cur, min, max)) in levels.iter().enumerate() {
+                    let base = self.brightness[idx].unwrap_or(cur);
+                    self.brightness[idx] = Some(increase_brightness(base, min, max, percent));
+                    self.source[idx] = Some(history::Source::Hotkey);
+                }
+            }
+            MonitorCmd::DecreaseAll(percent) => {
+                for (idx, &(cur, min, max)) in levels.iter().enumerate() {
+                    let base = self.brightness[idx].unwrap_or(cur);
+                    self.brightness[idx] = Some(decrease_brightness(base, min, max, percent));
+                    self.source[idx] = Some(history::Source::Hotkey);
+                }
+            }
+            MonitorCmd::RestoreAll => {
+                for (idx, &(_, _, max)) in levels.iter().enumerate() {
+                    self.brightness[idx] = Some(max);
+                    self.source[idx] = Some(history::Source::Hotkey);
+                    self.force_full[idx] = true;
+                }
+            }
+            MonitorCmd::Retry(idx) => {
+                if let Some(slot) = self.retry.get_mut(idx) {
+                    *slot = true;
+                }
+            }
+            MonitorCmd::Identify(idx) => {
+                if let Some(slot) = self.identify.get_mut(idx) {
+                    *slot = true;
+                }
+            }
+            MonitorCmd::PowerMode(Some(idx), mode) => {
+                if let Some(slot) = self.power_mode.get_mut(idx) {
+                    *slot = Some(mode);
+                }
+            }
+            MonitorCmd::PowerMode(None, mode) => {
+                self.power_mode.fill(Some(mode));
+            }
+            MonitorCmd::SetDisabled(idx, disabled) => {
+                if let Some(slot) = self.disabled.get_mut(idx) {
+                    *slot = Some(disabled);
+                }
+            }
+            MonitorCmd::SetTheme(theme) => self.theme = Some(theme),
+            MonitorCmd::SetCompact(compact) => self.compact = Some(compact),
+            MonitorCmd::SetAlwaysOnTop(on_top) => self.always_on_top = Some(on_top),
+            MonitorCmd::SaveSettings(settings) => self.settings = Some(settings),
+            MonitorCmd::Rescan => self.rescan = true,
+            MonitorCmd::SetDefault(idx, default) => {
+                if let Some(slot) = self.default.get_mut(idx) {
+                    *slot = Some(default);
+                }
+            }
+            MonitorCmd::ResetAll => self.reset_all = true,
+            MonitorCmd::Rename(idx, name) => {
+                if let Some(slot) = self.rename.get_mut(idx) {
+                    *slot = Some(name);
+                }
+            }
+            MonitorCmd::SetLimits(idx, limits) => {
+                if let Some(slot) = self.limits.get_mut(idx) {
+                    *slot = Some(limits);
+                }
+            }
+            MonitorCmd::SetInverted(idx, inverted) => {
+                if let Some(slot) = self.inverted.get_mut(idx) {
+                    *slot = Some(inverted);
+                }
+            }
+            MonitorCmd::SetSyncOffset(idx, offset) => {
+                if let Some(slot) = self.sync_offsets.get_mut(idx) {
+                    *slot = Some(offset);
+                }
+            }
+            MonitorCmd::PowerChanged => self.power_changed = true,
+            MonitorCmd::TestDdc => self.test_ddc = true,
+            MonitorCmd::DumpVcp => self.dump_vcp = true,
+            MonitorCmd::DismissDdcHelp => self.dismiss_ddc_help = true,
+            MonitorCmd::ApplyProfile(name) => self.apply_profile = Some(name),
+            MonitorCmd::SaveProfile(name, profile) => self.profiles.push((name, profile)),
+            MonitorCmd::SaveGroup(name, members) => self.groups.push((name, members)),
+        }
+    }
+}
 
-enum MonitorCmd {
-    SetBrightness(usize, u32), // Monitor Index, value
+enum MonitorUpdate {
+    Brightness {
+        index: usize,
+        brightness: u32,
+    },
+    /// The set of connected monitors changed, or the first discovery
+    /// finished; replaces every row in the UI.
+    ListChanged(Vec<MonitorInfo>),
+    /// One monitor was read again after the user pressed Retry.
+    Refreshed {
+        index: usize,
+        info: MonitorInfo,
+    },
+    /// Brightness read back after writing `requested` with
+    /// `verify_writes` on. Unlike a poll, this isn't stale, so it's shown
+    /// even while the user is interacting, unless they've moved on.
+    Verified {
+        index: usize,
+        requested: u32,
+        brightness: u32,
+    },
+    /// A requested rescan found the same monitors as before.
+    RescanUnchanged,
+    /// A brightness write to monitor `index` was refused.
+    WriteFailed {
+        index: usize,
+    },
+    /// Monitor `index` started or stopped failing reads and writes in a
+    /// row after having answered, as if another app held its DDC/CI bus.
+    Contended {
+        index: usize,
+        contended: bool,
+    },
+    /// Result of a DDC/CI test per monitor; `None` for hidden monitors,
+    /// which aren't tested.
+    DdcTested(Vec<Option<bool>>),
+    /// Raw DDC/CI data of every monitor, for the debug panel.
+    VcpDumped(Vec<VcpDump>),
 }
 
-struct MonitorUpdate {
-    index: usize,
+/// Everything the UI shows for one monitor, read once when the monitor is
+/// first seen.
+struct MonitorInfo {
+    name: String,
+    id: String,
     brightness: u32,
+    min_max: (u32, u32),
+    /// `None` when the monitor doesn't expose VCP 0x12.
+    contrast: Option<u32>,
+    contrast_min_max: (u32, u32),
+    color_presets: Vec<(u8, String)>,
+    color_preset: Option<u8>,
+    /// `None` when the monitor has no speakers (VCP 0x62).
+    volume: Option<u32>,
+    volume_min_max: (u32, u32),
+    /// Why brightness couldn't be read. The row shows this instead of a
+    /// slider until a retry succeeds.
+    error: Option<String>,
+    /// The error is one retrying won't fix: a [`SetupRequired`], or a
+    /// monitor that doesn't support brightness at all.
+    needs_setup: bool,
+    /// Dimmed in software; see [`MonitorHandle::is_software`].
+    software: bool,
+    /// See [`MonitorHandle::is_primary`].
+    primary: bool,
+    /// See [`MonitorHandle::max_luminance`].
+    max_luminance: Option<u32>,
+    /// See [`MonitorHandle::display_mode`].
+    display_mode: Option<DisplayMode>,
+}
+
+impl MonitorInfo {
+    /// Read everything from `mon`, brightness as a slider position on
+    /// `scale`. Brightness falls back to `fallback` when it can't be read.
+    fn read<M: MonitorHandle>(mon: &mut M, fallback: u32, scale: BrightnessScale) -> Self {
+        let polled = if mon.supports_brightness() {
+            mon.poll_brightness()
+        } else {
+            Err(anyhow::anyhow!(tr("brightness_unsupported")))
+        };
+        let ((brightness, min, max), error, needs_setup) = match polled {
+            Ok((cur, min, max)) => (
+                (from_hardware(scale, cur, None, min, max), min, max),
+                None,
+                false,
+            ),
+            Err(e) => {
+                log::warn!("Failed to read {}: {e}", mon.name());
+                (
+                    (fallback, fallback, fallback),
+                    Some(e.to_string()),
+                    e.is::<SetupRequired>() || !mon.supports_brightness(),
+                )
+            }
+        };
+
+        let (contrast, contrast_min_max) = match mon.poll_contrast() {
+            Ok((cur, min, max)) => (Some(cur), (min, max)),
+            Err(_) => (None, (0, 0)),
+        };
+
+        let color_presets = mon.list_color_presets();
+        let color_preset = if color_presets.is_empty() {
+            None
+        } else {
+            mon.poll_color_preset().ok()
+        };
+
+        let (volume, volume_min_max) = match mon.poll_volume() {
+            Ok((cur, min, max)) if mon.has_volume() => (Some(cur), (min, max)),
+            _ => (None, (0, 0)),
+        };
+
+        Self {
+            name: mon.name().to_string(),
+            id: mon.id(),
+            brightness,
+            min_max: (min, max),
+            contrast,
+            contrast_min_max,
+            color_presets,
+            color_preset,
+            volume,
+            volume_min_max,
+            error,
+            needs_setup,
+            software: mon.is_software(),
+            primary: mon.is_primary(),
+            max_luminance: mon.max_luminance(),
+            display_mode: mon.display_mode(),
+        }
+    }
+
+    fn level(&self) -> (u32, u32, u32) {
+        (self.brightness, self.min_max.0, self.min_max.1)
+    }
 }
 
 pub struct TrayBrightUI {
     monitor_names: Vec<String>,
     brightness_values: Vec<u32>,
     min_max: Vec<(u32, u32)>,
+    /// Current contrast per monitor, `None` when the monitor doesn't
+    /// expose VCP 0x12 — the contrast row is hidden in that case.
+    contrast_values: Vec<Option<u32>>,
+    contrast_min_max: Vec<(u32, u32)>,
+    /// Colour presets advertised by each monitor's capability string.
+    /// The dropdown is hidden when empty or the current preset can't be read.
+    color_presets: Vec<Vec<(u8, String)>>,
+    color_preset_values: Vec<Option<u8>>,
+    /// Current speaker volume per monitor, `None` when the monitor has no
+    /// speakers — the volume row is hidden in that case.
+    volume_values: Vec<Option<u32>>,
+    volume_min_max: Vec<(u32, u32)>,
+    /// Read error per monitor; such rows show the error and a retry button
+    /// in place of the brightness slider.
+    errors: Vec<Option<String>>,
+    /// Monitors with a retry in flight.
+    retrying: Vec<bool>,
+    /// Monitors whose error needs the user to install or enable something;
+    /// their rows show the error itself and no retry button.
+    needs_setup: Vec<bool>,
+    /// Monitors dimmed in software rather than by their backlight; their
+    /// names carry a label saying so.
+    software: Vec<bool>,
+    /// The OS's primary display, badged in its row.
+    primary: Vec<bool>,
+    /// Peak nits of HDR monitors that report it, shown next to the name.
+    max_luminance: Vec<Option<u32>>,
+    /// Resolution and refresh rate, shown next to the name.
+    display_mode: Vec<Option<DisplayMode>>,
+    /// The worker hasn't finished its first monitor discovery yet.
+    discovering: bool,
+    /// A rescan the user asked for is in flight.
+    rescanning: bool,
+    /// Monitors whose last brightness write was refused.
+    write_failed: Vec<bool>,
+    /// Monitors whose DDC/CI keeps failing after having worked, likely
+    /// because another app is driving them too.
+    contended: Vec<bool>,
+    /// The DDC/CI help has been closed for good. Mirrors the config.
+    ddc_help_dismissed: bool,
+    /// A DDC/CI test is in flight.
+    ddc_testing: bool,
+    /// Outcome of the last DDC/CI test per monitor, `None` if untested.
+    ddc_results: Vec<Option<bool>>,
+    /// Ids of monitors hidden from the panel. Mirrors the config's
+    /// disabled set, which the worker owns.
+    disabled: BTreeSet<String>,
+    /// Brightness each monitor's reset button restores, keyed by monitor
+    /// id. Mirrors the config like `disabled`.
+    defaults: BTreeMap<String, u32>,
+    /// Stable id per monitor, which custom names are keyed by.
+    monitor_ids: Vec<String>,
+    /// User-chosen names, keyed by monitor id. Mirrors the config.
+    custom_names: BTreeMap<String, String>,
+    /// Soft brightness range per monitor id. Mirrors the config.
+    limits: BTreeMap<String, BrightnessLimits>,
+    /// Ids of monitors whose slider is drawn flipped. Mirrors the config.
+    inverted: BTreeSet<String>,
+    /// Offset from the master slider per monitor id. Mirrors the config.
+    sync_offsets: BTreeMap<String, i32>,
+    /// Saved brightness setups by name. Mirrors the config.
+    profiles: BTreeMap<String, Profile>,
+    /// Member ids of each monitor group. Mirrors the config.
+    groups: BTreeMap<String, Vec<String>>,
+    /// Group slider positions while the user is dragging them.
+    group_values: BTreeMap<String, u32>,
+    /// Name typed for a new group in a monitor's menu.
+    new_group: String,
+    /// Profile picked in the dropdown, which Delete removes and Save
+    /// suggests overwriting.
+    selected_profile: Option<String>,
+    /// Name typed for a profile about to be saved.
+    naming_profile: Option<String>,
+    /// Monitor whose name is being edited, and the text so far.
+    renaming: Option<(usize, String)>,
+    /// Short confirmation shown under the monitors, and when it appeared.
+    toast: Option<(String, Instant)>,
+    /// Brightness each monitor last had outside a drag — sent from the
+    /// panel or reported by the worker.
+    committed: Vec<u32>,
+    /// Values each monitor had before its last few changes from the panel,
+    /// newest last, for undo.
+    history: Vec<VecDeque<u32>>,
+    /// Monitors just set below [`UNDO_BELOW`], offered an undo, and when
+    /// the offer appeared.
+    undo: Option<(Vec<usize>, Instant)>,
     tx_cmd: Sender<MonitorCmd>,
+    tx_tray: Sender<TrayRequest>,
+    rx_tray: Receiver<TrayRequest>,
+    /// Asking whether to turn start-on-login off, after the tray's item
+    /// was unticked.
+    confirm_autostart_off: bool,
     rx_update: Receiver<MonitorUpdate>,
+    /// Average brightness whenever it changes, for the tray tooltip. Taken
+    /// by the tray once it's created.
+    rx_average: Option<Receiver<u32>>,
+    snapshot: MonitorSnapshot,
     /// Tracks when the user last interacted with each monitor's slider.
     /// Poll updates are suppressed during this window so the slider
     /// doesn't fight the user.
     user_cooldowns: Vec<Option<Instant>>,
+    /// Worker timings, whose user cooldown is the length of that window.
+    polling: PollSettings,
+    /// Brightness slider ids from the previous frame, used to tell which
+    /// slider has keyboard focus before egui handles the keys itself.
+    slider_ids: Vec<Option<egui::Id>>,
+    /// Keyboard changes not yet sent to the worker, and when the last
+    /// keyboard command went out for each monitor.
+    key_pending: Vec<bool>,
+    last_key_send: Vec<Option<Instant>>,
+    /// Mouse-wheel travel over each brightness slider not yet turned into
+    /// a step (in notches), and when the wheel last moved a value that
+    /// hasn't been sent yet.
+    scroll_notches: Vec<f32>,
+    scroll_pending: Vec<Option<Instant>>,
+    /// When a click on each brightness slider that hasn't been sent yet
+    /// landed. Held back while it could still be the first half of a
+    /// double-click, which resets the monitor to its default instead.
+    click_pending: Vec<Option<Instant>>,
+    /// Percent of the range moved by Page Up/Down (and the hotkeys), and
+    /// by the arrow keys and each wheel notch.
+    step_percent: u32,
+    fine_step_percent: u32,
+    /// Quick-set brightness buttons, in percent.
+    presets: Vec<u32>,
+    /// Chosen theme, and the one last handed to egui.
+    theme: ThemeMode,
+    applied_theme: Option<ThemeMode>,
+    /// One-line monitor rows.
+    compact: bool,
+    /// Pinned above other windows, and whether that was last handed to
+    /// the window. Cleared while hidden so every show applies it again.
+    always_on_top: bool,
+    applied_on_top: Option<bool>,
+    /// Whether the schedule and the curve are switched on. Mirror the
+    /// config for the settings view.
+    schedule_enabled: bool,
+    curve_enabled: bool,
+    /// Notify of hotkey and schedule changes made while hidden. Mirrors
+    /// the config for the settings view.
+    notify_changes: bool,
+    /// Hide when another window takes focus. Mirrors the config for the
+    /// settings view.
+    hide_on_focus_loss: bool,
+    /// Keep brightness per set of connected monitors. Mirrors the config
+    /// for the settings view.
+    remember_per_setup: bool,
+    /// Whether the window had focus last frame, to catch it losing it.
+    was_focused: bool,
+    /// The settings view is open in place of the monitor list.
+    settings_draft: Option<SettingsDraft>,
+    /// `--debug` was passed, so the header links to the debug panel.
+    /// Ctrl+Shift+D opens it either way.
+    debug_button: bool,
+    /// The VCP debug panel is open in place of the monitor list.
+    debug_open: bool,
+    /// What the worker read for the debug panel; `None` while it reads.
+    vcp_dumps: Option<Vec<VcpDump>>,
+    /// Brightness keys are turned on in the config.
+    brightness_keys: bool,
+    /// They're on but the OS hasn't granted the permission they need.
+    keys_permission_missing: bool,
+    /// When true, a master slider drives every monitor at once.
+    sync_linked: bool,
+    /// Master slider position while the user is dragging it. `None` when
+    /// idle, in which case the slider shows the average brightness.
+    sync_value: Option<u32>,
     /// Shared visibility flag — when false, worker thread stops
     /// polling hardware and UI repaints less frequently.
     visible: Arc<AtomicBool>,
@@ -38,213 +658,3212 @@ pub struct TrayBrightUI {
     last_fps_check: Instant,
 }
 
-const DEFAULT_BRIGHTNESS: u32 = 0;
+/// Re-enumerate monitors every this many poll cycles to pick up hot-plugged
+/// displays (~30s while the window is visible).
+const REENUMERATE_EVERY: u32 = 6;
+
+/// Minimum gap between commands while a brightness key is held down.
+const KEY_REPEAT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Keys that step a focused brightness slider, with their direction
+/// (negative lowers brightness) and whether they take the fine step
+/// rather than the full one.
+const BRIGHTNESS_KEYS: [(Key, i32, bool); 4] = [
+    (Key::ArrowRight, 1, true),
+    (Key::ArrowLeft, -1, true),
+    (Key::PageUp, 1, false),
+    (Key::PageDown, -1, false),
+];
+
+/// Digit keys that jump a focused brightness slider straight to a percent
+/// of the monitor's range: 1 for 10% through 9 for 90%, and 0 for 100%.
+const DIGIT_KEYS: [(Key, u32); 10] = [
+    (Key::Num1, 10),
+    (Key::Num2, 20),
+    (Key::Num3, 30),
+    (Key::Num4, 40),
+    (Key::Num5, 50),
+    (Key::Num6, 60),
+    (Key::Num7, 70),
+    (Key::Num8, 80),
+    (Key::Num9, 90),
+    (Key::Num0, 100),
+];
+
+/// Enumerates monitors again, as the header's ⟳ button does.
+const RESCAN_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(Modifiers::COMMAND, Key::R);
+
+/// How long the wheel must rest before a scrolled value is sent.
+const SCROLL_IDLE: Duration = Duration::from_millis(150);
+
+/// How often the worker compares the wall clock against the schedule.
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often the ambient light sensor is sampled when auto-brightness is on.
+const AMBIENT_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often the battery is read when the battery cap is on.
+const BATTERY_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long brightness must stay unchanged before it's written to the
+/// config file, so a slider drag doesn't rewrite the file on every step.
+const CONFIG_SAVE_DELAY: Duration = Duration::from_secs(2);
+
+/// How often the worker checks for a fullscreen app when that rule is on.
+const FULLSCREEN_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long hotkey and schedule changes made while hidden must stop for
+/// before they're shown, so a run of key presses gives one notification.
+const NOTIFY_DELAY: Duration = Duration::from_millis(800);
+
+/// How long to wait after the last wake-up or power-source change before
+/// re-applying brightness. Monitors take a few seconds to answer DDC/CI
+/// after waking, and one resume often comes with several events.
+const POWER_SETTLE: Duration = Duration::from_secs(5);
+
+/// Window height while the settings view is open.
+const SETTINGS_HEIGHT: f32 = 340.0;
+
+/// Window height while the debug panel is open, as tall as it gets.
+const DEBUG_HEIGHT: f32 = 400.0;
+
+/// How long a confirmation toast stays on screen.
+const TOAST_DURATION: Duration = Duration::from_secs(3);
+
+/// Width of the monitor name in compact rows; longer names are cut short.
+const COMPACT_NAME_WIDTH: f32 = 80.0;
+
+/// Brightness (percent) below which a monitor can look switched off, so
+/// setting it there offers an undo.
+const UNDO_BELOW: u32 = 5;
+
+/// How long the undo offer stays on screen.
+const UNDO_DURATION: Duration = Duration::from_secs(6);
+
+/// Previous values kept per monitor.
+const UNDO_HISTORY: usize = 5;
+
+impl TrayBrightUI {
+    pub fn new() -> anyhow::Result<Self> {
+        Self::with_provider(PlatformMonitorProvider::new)
+    }
+
+    /// The app driving whatever monitors `make_provider` finds, such as
+    /// simulated ones. The provider is made on the worker thread, which
+    /// owns it from then on.
+    pub fn with_provider<P: MonitorProvider>(
+        make_provider: impl FnOnce() -> P + Send + 'static,
+    ) -> anyhow::Result<Self> {
+        // Before anything looks up a string, which fixes the language
+        let config = Config::load();
+        i18n::init(config.language.as_deref());
+
+        let (tx_cmd, rx_cmd) = channel::<MonitorCmd>();
+        let (tx_update, rx_update) = channel::<MonitorUpdate>();
+        let (tx_average, rx_average) = channel::<u32>();
+        let (tx_tray, rx_tray) = channel::<TrayRequest>();
+
+        let polling = config.polling;
+        let disabled = config.disabled.clone();
+        let defaults = config.default_brightness.clone();
+        let custom_names = config.names.clone();
+        let limits = config.limits.clone();
+        let inverted = config.inverted.clone();
+        let sync_offsets = config.sync_offsets.clone();
+        let profiles = config.profiles.clone();
+        let groups = config.groups.clone();
+        let step_percent = config.step_percent();
+        let fine_step_percent = config.fine_step_percent();
+        let theme = config.theme;
+        let compact = config.compact;
+        let always_on_top = config.always_on_top;
+        let schedule_enabled = config.schedule.enabled;
+        let curve_enabled = config.curve.enabled;
+        let notify_changes = config.notify_changes;
+        let hide_on_focus_loss = config.hide_on_focus_loss;
+        let remember_per_setup = config.remember_per_setup;
+        let brightness_keys = config.brightness_keys;
+        let presets = config.brightness_presets.clone();
+        let ddc_help_dismissed = config.ddc_help_dismissed;
+
+        let snapshot = MonitorSnapshot::default();
+        let visible = Arc::new(AtomicBool::new(false)); // starts hidden
+
+        let link = WorkerLink {
+            rx_cmd,
+            tx_update,
+            tx_average,
+            snapshot: snapshot.clone(),
+            visible: visible.clone(),
+            config_path: Config::path(),
+        };
+        std::thread::spawn(move || run_worker(make_provider(), config, link));
+
+        let mut app = Self {
+            monitor_names: Vec::new(),
+            brightness_values: Vec::new(),
+            min_max: Vec::new(),
+            contrast_values: Vec::new(),
+            contrast_min_max: Vec::new(),
+            color_presets: Vec::new(),
+            color_preset_values: Vec::new(),
+            volume_values: Vec::new(),
+            volume_min_max: Vec::new(),
+            errors: Vec::new(),
+            retrying: Vec::new(),
+            needs_setup: Vec::new(),
+            software: Vec::new(),
+            primary: Vec::new(),
+            max_luminance: Vec::new(),
+            display_mode: Vec::new(),
+            discovering: true,
+            rescanning: false,
+            write_failed: Vec::new(),
+            contended: Vec::new(),
+            ddc_help_dismissed,
+            ddc_testing: false,
+            ddc_results: Vec::new(),
+            disabled,
+            defaults,
+            monitor_ids: Vec::new(),
+            custom_names,
+            limits,
+            inverted,
+            sync_offsets,
+            profiles,
+            groups,
+            group_values: BTreeMap::new(),
+            new_group: String::new(),
+            selected_profile: None,
+            naming_profile: None,
+            renaming: None,
+            toast: None,
+            committed: Vec::new(),
+            history: Vec::new(),
+            undo: None,
+            tx_cmd,
+            tx_tray,
+            rx_tray,
+            confirm_autostart_off: false,
+            rx_update,
+            rx_average: Some(rx_average),
+            snapshot,
+            user_cooldowns: Vec::new(),
+            polling,
+            slider_ids: Vec::new(),
+            key_pending: Vec::new(),
+            last_key_send: Vec::new(),
+            scroll_notches: Vec::new(),
+            scroll_pending: Vec::new(),
+            click_pending: Vec::new(),
+            step_percent,
+            fine_step_percent,
+            presets,
+            theme,
+            applied_theme: None,
+            compact,
+            always_on_top,
+            applied_on_top: None,
+            schedule_enabled,
+            curve_enabled,
+            notify_changes,
+            hide_on_focus_loss,
+            remember_per_setup,
+            was_focused: false,
+            settings_draft: None,
+            debug_button: false,
+            debug_open: false,
+            vcp_dumps: None,
+            brightness_keys,
+            keys_permission_missing: false,
+            sync_linked: true,
+            sync_value: None,
+            visible,
+            frame_count: 0,
+            last_fps_check: Instant::now(),
+        };
+        app.set_monitors(Vec::new());
+        Ok(app)
+    }
+
+    /// Replace every per-monitor row, e.g. after a monitor is plugged in or
+    /// removed. Interaction state is reset since indices may have shifted.
+    fn set_monitors(&mut self, infos: Vec<MonitorInfo>) {
+        let count = infos.len();
+        self.monitor_names.clear();
+        self.monitor_ids.clear();
+        self.brightness_values.clear();
+        self.min_max.clear();
+        self.contrast_values.clear();
+        self.contrast_min_max.clear();
+        self.color_presets.clear();
+        self.color_preset_values.clear();
+        self.volume_values.clear();
+        self.volume_min_max.clear();
+        self.errors.clear();
+        self.needs_setup.clear();
+        self.software.clear();
+        self.primary.clear();
+        self.max_luminance.clear();
+        self.display_mode.clear();
+
+        for info in infos {
+            self.monitor_names.push(info.name);
+            self.monitor_ids.push(info.id);
+            self.brightness_values.push(info.brightness);
+            self.min_max.push(info.min_max);
+            self.contrast_values.push(info.contrast);
+            self.contrast_min_max.push(info.contrast_min_max);
+            self.color_presets.push(info.color_presets);
+            self.color_preset_values.push(info.color_preset);
+            self.volume_values.push(info.volume);
+            self.volume_min_max.push(info.volume_min_max);
+            self.errors.push(info.error);
+            self.needs_setup.push(info.needs_setup);
+            self.software.push(info.software);
+            self.primary.push(info.primary);
+            self.max_luminance.push(info.max_luminance);
+            self.display_mode.push(info.display_mode);
+        }
+        // The worker moves the config's copies the same way
+        let monitors: Vec<(&str, &str)> = self
+            .monitor_ids
+            .iter()
+            .zip(&self.monitor_names)
+            .map(|(id, name)| (id.as_str(), name.as_str()))
+            .collect();
+        rekey_by_id(&mut self.defaults, &monitors);
+        rekey_set_by_id(&mut self.disabled, &monitors);
+
+        self.user_cooldowns = vec![None; count];
+        self.slider_ids = vec![None; count];
+        self.key_pending = vec![false; count];
+        self.last_key_send = vec![None; count];
+        self.scroll_notches = vec![0.0; count];
+        self.scroll_pending = vec![None; count];
+        self.click_pending = vec![None; count];
+        self.retrying = vec![false; count];
+        self.committed = self.brightness_values.clone();
+        self.history = vec![VecDeque::new(); count];
+        self.undo = None;
+        self.write_failed = vec![false; count];
+        self.contended = vec![false; count];
+        self.ddc_results = vec![None; count];
+        self.rescanning = false;
+        self.renaming = None;
+        self.sync_value = None;
+    }
+
+    /// Hands the average-brightness channel to the tray. Can only be taken
+    /// once.
+    pub fn take_average_receiver(&mut self) -> Receiver<u32> {
+        self.rx_average
+            .take()
+            .expect("average receiver already taken")
+    }
+
+    /// Returns a handle to the worker's latest per-monitor brightness.
+    pub fn snapshot(&self) -> MonitorSnapshot {
+        self.snapshot.clone()
+    }
+
+    /// Returns a sender for queueing commands from outside the UI, such as
+    /// global hotkeys.
+    pub fn command_sender(&self) -> Sender<MonitorCmd> {
+        self.tx_cmd.clone()
+    }
+
+    /// Returns a sender for the tray menu's requests to the window.
+    pub fn tray_request_sender(&self) -> Sender<TrayRequest> {
+        self.tx_tray.clone()
+    }
+
+    /// Percent one hotkey press moves each monitor.
+    pub fn step_percent(&self) -> u32 {
+        self.step_percent
+    }
+
+    /// Whether the config asks for the keyboard's brightness keys to be
+    /// forwarded.
+    pub fn brightness_keys(&self) -> bool {
+        self.brightness_keys
+    }
+
+    /// Offer the debug panel in the header.
+    pub fn show_debug_button(&mut self) {
+        self.debug_button = true;
+    }
+
+    /// Show how to grant the permission the brightness keys are missing.
+    pub fn set_keys_permission_missing(&mut self) {
+        self.keys_permission_missing = true;
+    }
+
+    /// Names of the monitors as shown in the panel, in command index order.
+    /// Empty until the worker's first discovery arrives.
+    pub fn monitor_names(&self) -> Vec<String> {
+        (0..self.monitor_names.len())
+            .map(|i| self.display_name(i).to_string())
+            .collect()
+    }
+
+    /// Names of the saved profiles, in the order the tray lists them.
+    pub fn profile_names(&self) -> Vec<String> {
+        self.profiles.keys().cloned().collect()
+    }
+
+    /// The user's name for monitor `i` if they've set one, else the name the
+    /// platform reported.
+    fn display_name(&self, i: usize) -> &str {
+        self.custom_names
+            .get(&self.monitor_ids[i])
+            .unwrap_or(&self.monitor_names[i])
+    }
+
+    /// Returns a clone of the visibility flag for use by tray handlers.
+    pub fn visible_flag(&self) -> Arc<AtomicBool> {
+        self.visible.clone()
+    }
+
+    /// Initial window height that fits every monitor row without scrolling.
+    pub fn preferred_height(&self) -> f32 {
+        if self.settings_draft.is_some() {
+            return SETTINGS_HEIGHT;
+        }
+        if self.debug_open {
+            return DEBUG_HEIGHT;
+        }
+        if self.monitor_names.is_empty() {
+            return 160.0;
+        }
+        let shown: Vec<usize> = (0..self.monitor_names.len())
+            .filter(|&i| !self.is_disabled(i))
+            .collect();
+        let contrast_rows = shown
+            .iter()
+            .filter(|&&i| self.contrast_values[i].is_some())
+            .count();
+        let volume_rows = shown
+            .iter()
+            .filter(|&&i| self.volume_values[i].is_some())
+            .count();
+        let preset_rows = shown
+            .iter()
+            .filter(|&&i| self.color_preset_values[i].is_some())
+            .count();
+
+        let sync_rows = if shown.len() > 1 { 1 } else { 0 };
+        let preset_button_rows = if self.presets.is_empty() {
+            0
+        } else {
+            shown.len()
+        };
+        let hidden_rows = if shown.len() < self.monitor_names.len() {
+            1
+        } else {
+            0
+        };
+
+        let mut help_height = if self.show_ddc_help() { 120.0 } else { 0.0 };
+        if self.keys_permission_missing {
+            help_height += 36.0;
+        }
+        if self.compact {
+            return 110.0
+                + help_height
+                + 60.0 * sync_rows as f32
+                + 28.0 * shown.len() as f32
+                + 30.0 * hidden_rows as f32;
+        }
+
+        110.0
+            + help_height
+            + 60.0 * (shown.len() + sync_rows) as f32
+            + 30.0 * hidden_rows as f32
+            + 26.0 * preset_button_rows as f32
+            + 40.0 * (contrast_rows + volume_rows) as f32
+            + 28.0 * preset_rows as f32
+    }
+
+    fn build_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if self.settings_draft.is_some() {
+                if ui.small_button("←").on_hover_text(tr("back")).clicked() {
+                    self.close_settings(ui.ctx());
+                }
+                ui.heading(tr("settings"));
+                return;
+            }
+            if self.debug_open {
+                if ui.small_button("←").on_hover_text(tr("back")).clicked() {
+                    self.toggle_debug(ui.ctx());
+                }
+                ui.heading(tr("vcp_debug"));
+                return;
+            }
+            ui.heading(tr("app_name"));
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.small_button("⚙").on_hover_text(tr("settings")).clicked() {
+                    self.open_settings(ui.ctx());
+                }
+                if self.debug_button
+                    && ui
+                        .small_button("VCP")
+                        .on_hover_text(tr("vcp_debug"))
+                        .clicked()
+                {
+                    self.toggle_debug(ui.ctx());
+                }
+                self.build_theme_button(ui);
+                self.build_layout_button(ui);
+                self.build_pin_button(ui);
+                if self.rescanning {
+                    ui.spinner().on_hover_text(tr("scanning"));
+                } else {
+                    let shortcut = ui.ctx().format_shortcut(&RESCAN_SHORTCUT);
+                    let hint = tr_args("rescan_monitors", &[("shortcut", &shortcut)]);
+                    if ui.small_button("⟳").on_hover_text(hint).clicked() {
+                        self.rescan();
+                    }
+                }
+            });
+        });
+        ui.add_space(8.0);
+
+        if self.keys_permission_missing {
+            let warn = ui.visuals().warn_fg_color;
+            ui.label(
+                RichText::new(tr("brightness_keys_permission"))
+                    .small()
+                    .color(warn),
+            );
+            ui.add_space(4.0);
+        }
+
+        // Apply poll updates, but ignore them for monitors the user is
+        // currently interacting with — otherwise stale hardware reads
+        // yank the slider back mid-drag.
+        while let Ok(update) = self.rx_update.try_recv() {
+            match update {
+                MonitorUpdate::Brightness { index, brightness } => {
+                    let suppressed = self.user_cooldowns[index]
+                        .is_some_and(|t| t.elapsed() < self.polling.user_cooldown());
+                    if !suppressed && self.errors[index].is_none() {
+                        self.brightness_values[index] = brightness;
+                        self.committed[index] = brightness;
+                    }
+                }
+                MonitorUpdate::Verified {
+                    index,
+                    requested,
+                    brightness,
+                } => {
+                    if self.committed[index] == requested && self.errors[index].is_none() {
+                        self.brightness_values[index] = brightness;
+                        self.committed[index] = brightness;
+                    }
+                }
+                MonitorUpdate::ListChanged(infos) => {
+                    self.set_monitors(infos);
+                    crate::tray::set_monitors(&self.monitor_names());
+                    // The window opened sized for an empty panel
+                    if std::mem::take(&mut self.discovering) {
+                        self.resize_to_fit(ui.ctx());
+                    }
+                }
+                MonitorUpdate::Refreshed { index, info } => self.replace_monitor(index, info),
+                MonitorUpdate::RescanUnchanged => self.rescanning = false,
+                MonitorUpdate::WriteFailed { index } => {
+                    if let Some(failed) = self.write_failed.get_mut(index) {
+                        *failed = true;
+                    }
+                }
+                MonitorUpdate::Contended { index, contended } => {
+                    if let Some(flag) = self.contended.get_mut(index) {
+                        *flag = contended;
+                    }
+                }
+                MonitorUpdate::VcpDumped(dumps) => self.vcp_dumps = Some(dumps),
+                MonitorUpdate::DdcTested(results) => {
+                    self.ddc_testing = false;
+                    if results.len() == self.ddc_results.len() {
+                        self.ddc_results = results;
+                    }
+                }
+            }
+        }
+
+        if self.settings_draft.is_some() {
+            self.build_settings(ui);
+            return;
+        }
+        if self.debug_open {
+            self.build_vcp_debug(ui);
+            return;
+        }
+
+        if self.discovering {
+            ui.vertical_centered(|ui| {
+                ui.add_space(16.0);
+                ui.spinner();
+                ui.label(RichText::new(tr("discovering")).weak());
+            });
+            return;
+        }
+
+        if self.monitor_names.is_empty() {
+            self.build_empty_state(ui);
+            return;
+        }
+
+        if self.show_ddc_help() {
+            self.build_ddc_help(ui);
+            ui.add_space(8.0);
+        }
+
+        self.build_profile_row(ui);
+        ui.add_space(4.0);
+
+        if self.shows_sync_row() {
+            self.build_sync_row(ui);
+            ui.add_space(4.0);
+            ui.separator();
+            ui.add_space(4.0);
+        }
+
+        let mut first = true;
+        let mut grouped = BTreeSet::new();
+        for name in self.groups.keys().cloned().collect::<Vec<_>>() {
+            let members = self.group_members(&name);
+            if members.is_empty() {
+                continue;
+            }
+            if !first {
+                ui.add_space(if self.compact { 2.0 } else { 4.0 });
+                if !self.compact {
+                    ui.separator();
+                    ui.add_space(4.0);
+                }
+            }
+            first = false;
+            self.build_group_row(ui, &name, &members);
+            grouped.extend(members);
+        }
+        for i in 0..self.monitor_names.len() {
+            if self.is_disabled(i) || grouped.contains(&i) {
+                continue;
+            }
+            if self.compact {
+                if !first {
+                    ui.add_space(2.0);
+                }
+                first = false;
+                self.build_compact_row(ui, i);
+                continue;
+            }
+            if !first {
+                ui.add_space(4.0);
+                ui.separator();
+                ui.add_space(4.0);
+            }
+            first = false;
+            let mut hide = false;
+            let mut reset = None;
+            let default = self.defaults.get(&self.monitor_ids[i]).copied();
+            ui.horizontal(|ui| {
+                self.build_name_label(ui, i);
+                if self.primary[i] {
+                    ui.label(RichText::new(tr("primary")).small().weak())
+                        .on_hover_text(tr("primary_hint"));
+                }
+                if self.software[i] {
+                    ui.label(RichText::new(tr("software")).small().weak())
+                        .on_hover_text(tr("software_hint"));
+                }
+                if let Some(nits) = self.max_luminance[i] {
+                    ui.label(
+                        RichText::new(tr_args("max_nits", &[("value", &nits)]))
+                            .small()
+                            .weak(),
+                    );
+                }
+                if let Some(mode) = self.display_mode[i] {
+                    ui.label(RichText::new(mode.to_string()).small().weak());
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    hide = ui
+                        .small_button(tr("hide"))
+                        .on_hover_text(tr("hide_hint"))
+                        .clicked();
+                    if self.errors[i].is_none()
+                        && ui
+                            .small_button(tr("identify"))
+                            .on_hover_text(tr("identify_hint"))
+                            .clicked()
+                    {
+                        let _ = self.tx_cmd.send(MonitorCmd::Identify(i));
+                    }
+                    if let Some(default) = default
+                        && self.errors[i].is_none()
+                    {
+                        let (min, max) = self.brightness_range(i);
+                        let default = default.clamp(min, max);
+                        let changed = self.brightness_values[i] != default;
+                        if ui
+                            .add_enabled(changed, egui::Button::new("↺").small())
+                            .on_hover_text(tr_args("reset_to_default", &[("value", &default)]))
+                            .clicked()
+                        {
+                            reset = Some(default);
+                        }
+                    }
+                });
+            });
+            if hide {
+                self.set_disabled(i, true);
+                continue;
+            }
+            if let Some(default) = reset {
+                self.brightness_values[i] = default;
+                self.user_cooldowns[i] = Some(Instant::now());
+                self.send_brightness(i);
+            }
+            ui.add_space(4.0);
+            if self.errors[i].is_some() {
+                self.build_error_row(ui, i);
+                continue;
+            }
+            let slider_width = ui.available_width() - 60.0;
+            self.build_brightness_slider(ui, i, default, slider_width.max(100.0));
+            let (min, max) = self.brightness_range(i);
+            if self.contended[i] {
+                let warn = ui.visuals().warn_fg_color;
+                ui.label(RichText::new(tr("ddc_contended")).small().color(warn));
+            }
+
+            if !self.presets.is_empty() {
+                let mut chosen = None;
+                ui.horizontal(|ui| {
+                    for preset in clamp_presets(&self.presets, min, max) {
+                        if ui.small_button(format!("{preset}%")).clicked() {
+                            chosen = Some(preset);
+                        }
+                    }
+                });
+                if let Some(preset) = chosen {
+                    self.brightness_values[i] = preset;
+                    self.user_cooldowns[i] = Some(Instant::now());
+                    self.send_brightness(i);
+                }
+            }
+
+            // Contrast isn't polled, so there's no cooldown to manage —
+            // just send the final value when the drag ends.
+            if let Some(mut contrast) = self.contrast_values[i] {
+                let (min, max) = self.contrast_min_max[i];
+                ui.add_space(2.0);
+                ui.label(RichText::new(tr("contrast")).small().weak());
+                let slider = ui.add(
+                    egui::Slider::new(&mut contrast, min..=max)
+                        .suffix("%")
+                        .show_value(true),
+                );
+
+                if slider.changed() {
+                    self.contrast_values[i] = Some(contrast);
+                }
+
+                if slider.drag_stopped() {
+                    let _ = self.tx_cmd.send(MonitorCmd::SetContrast(i, contrast));
+                }
+            }
+
+            // Volume isn't polled either
+            if let Some(mut volume) = self.volume_values[i] {
+                let (min, max) = self.volume_min_max[i];
+                ui.add_space(2.0);
+                ui.label(RichText::new(tr("volume")).small().weak());
+                let slider = ui.add(egui::Slider::new(&mut volume, min..=max).show_value(true));
+
+                if slider.changed() {
+                    self.volume_values[i] = Some(volume);
+                }
+
+                if slider.drag_stopped() {
+                    let _ = self.tx_cmd.send(MonitorCmd::SetVolume(i, volume));
+                }
+            }
+
+            if let Some(current) = self.color_preset_values[i] {
+                let presets = &self.color_presets[i];
+                let selected_text = presets
+                    .iter()
+                    .find(|(value, _)| *value == current)
+                    .map(|(_, name)| name.clone())
+                    .unwrap_or_else(|| vcp::color_preset_name(current));
+                let mut selected = current;
+
+                ui.add_space(2.0);
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new(tr("color")).small().weak());
+                    egui::ComboBox::from_id_salt(("color_preset", i))
+                        .selected_text(selected_text)
+                        .show_ui(ui, |ui| {
+                            for (value, name) in presets {
+                                ui.selectable_value(&mut selected, *value, name);
+                            }
+                        });
+                });
+
+                if selected != current {
+                    self.color_preset_values[i] = Some(selected);
+                    let _ = self.tx_cmd.send(MonitorCmd::SetColorPreset(i, selected));
+                }
+            }
+        }
+
+        self.build_hidden_row(ui);
+        self.build_undo_toast(ui);
+
+        if let Some((message, shown_at)) = &self.toast {
+            if shown_at.elapsed() < TOAST_DURATION {
+                ui.add_space(4.0);
+                ui.label(RichText::new(message).small().weak());
+            } else {
+                self.toast = None;
+            }
+        }
+    }
+
+    /// Monitor `i`'s brightness slider, `width` wide plus its value box,
+    /// with keyboard, mouse-wheel and right-click handling.
+    fn build_brightness_slider(
+        &mut self,
+        ui: &mut egui::Ui,
+        i: usize,
+        default: Option<u32>,
+        width: f32,
+    ) {
+        let (min, max) = self.brightness_range(i);
+        let mut cur = self.brightness_values[i];
+
+        // Step by a percentage of the monitor's range rather than
+        // egui's default of one UI point per key press.
+        let key_step = self.take_key_step(ui, i);
+        if key_step > 0 {
+            cur = increase_brightness(cur, min, max, key_step.unsigned_abs());
+        } else if key_step < 0 {
+            cur = decrease_brightness(cur, min, max, key_step.unsigned_abs());
+        }
+
+        if let Some(percent) = self.take_digit_percent(ui, i) {
+            let (hw_min, hw_max) = self.min_max[i];
+            self.brightness_values[i] = percent_of_range(hw_min, hw_max, percent).clamp(min, max);
+            self.user_cooldowns[i] = Some(Instant::now());
+            self.send_brightness(i);
+            cur = self.brightness_values[i];
+        }
+
+        // Flipping the range flips how egui draws and drags the slider;
+        // the value itself is the same either way.
+        let range = if self.is_inverted(i) {
+            max..=min
+        } else {
+            min..=max
+        };
+        ui.spacing_mut().slider_width = width;
+        let hint = match default {
+            Some(default) => format!(
+                "{}\n{}",
+                tr("slider_keys_hint"),
+                tr_args("double_click_reset", &[("value", &default.clamp(min, max))])
+            ),
+            None => tr("slider_keys_hint").to_string(),
+        };
+        let slider = ui
+            .add(
+                egui::Slider::new(&mut cur, range)
+                    .suffix("%")
+                    .show_value(true),
+            )
+            .on_hover_text(hint);
+        self.slider_ids[i] = Some(slider.id);
+        slider.context_menu(|ui| {
+            if ui.button(tr("set_default")).clicked() {
+                self.set_default(i, Some(self.brightness_values[i]));
+                ui.close();
+            }
+            if default.is_some() && ui.button(tr("clear_default")).clicked() {
+                self.set_default(i, None);
+                ui.close();
+            }
+            ui.separator();
+            self.build_limits_menu(ui, i);
+            let mut inverted = self.is_inverted(i);
+            if ui.checkbox(&mut inverted, tr("invert_slider")).changed() {
+                self.set_inverted(i, inverted);
+            }
+            if self.shows_sync_row() {
+                self.build_sync_offset(ui, i);
+            }
+            ui.separator();
+            if !self.same_model(i).is_empty() && ui.button(tr("apply_same_model")).clicked() {
+                let updated = self.apply_to_same_model(i);
+                let message = if updated == 1 {
+                    tr("updated_one").to_string()
+                } else {
+                    tr_args("updated_many", &[("count", &updated)])
+                };
+                self.toast = Some((message, Instant::now()));
+                ui.close();
+            }
+        });
+
+        if slider.changed() || key_step != 0 {
+            self.brightness_values[i] = cur;
+            // Suppress poll updates while user is dragging
+            self.user_cooldowns[i] = Some(Instant::now());
+        }
+
+        if key_step != 0 {
+            self.key_pending[i] = true;
+        }
+        self.flush_key_change(ui, i);
+
+        // Only the slider under the pointer takes the wheel, so scrolling
+        // over one monitor never moves another.
+        let scroll_step = self.take_scroll_step(ui, &slider, i);
+        if scroll_step != 0 {
+            cur = if scroll_step > 0 {
+                increase_brightness(cur, min, max, scroll_step.unsigned_abs())
+            } else {
+                decrease_brightness(cur, min, max, scroll_step.unsigned_abs())
+            };
+            self.brightness_values[i] = cur;
+            self.user_cooldowns[i] = Some(Instant::now());
+            self.scroll_pending[i] = Some(Instant::now());
+        }
+        self.flush_scroll_change(ui, i);
+
+        if slider.drag_stopped() {
+            // Reset cooldown window from the moment of release
+            self.user_cooldowns[i] = Some(Instant::now());
+            // The slider only senses drags, so each click of a double-click
+            // ends one of its own; ask the pointer which this was
+            let (click, double_click) = ui.input(|input| {
+                (
+                    input.pointer.primary_clicked(),
+                    input
+                        .pointer
+                        .button_double_clicked(egui::PointerButton::Primary),
+                )
+            });
+            self.click_pending[i] = None;
+            match default {
+                Some(default) if double_click => {
+                    self.brightness_values[i] = default.clamp(min, max);
+                    self.send_brightness(i);
+                }
+                Some(_) if click => self.click_pending[i] = Some(Instant::now()),
+                _ => self.send_brightness(i),
+            }
+        }
+        self.flush_click(ui, i);
+    }
+
+    /// Single-line row for compact mode: short name, slider and percentage.
+    /// Everything else about the monitor is in the full layout.
+    fn build_compact_row(&mut self, ui: &mut egui::Ui, i: usize) {
+        let default = self.defaults.get(&self.monitor_ids[i]).copied();
+        ui.horizontal(|ui| {
+            ui.allocate_ui_with_layout(
+                egui::vec2(COMPACT_NAME_WIDTH, ui.spacing().interact_size.y),
+                egui::Layout::left_to_right(egui::Align::Center),
+                |ui| {
+                    ui.set_min_width(COMPACT_NAME_WIDTH);
+                    ui.add(
+                        egui::Label::new(RichText::new(self.display_name(i)).strong()).truncate(),
+                    );
+                },
+            );
+            if self.errors[i].is_some() {
+                self.build_error_status(ui, i);
+                return;
+            }
+            // Narrower than the full layout allows, but still draggable
+            let width = (ui.available_width() - 60.0).max(40.0);
+            self.build_brightness_slider(ui, i, default, width);
+        });
+    }
+
+    /// Send monitor `i`'s slider value to the worker, remembering what it
+    /// replaces. Going from a visible level to below [`UNDO_BELOW`] offers an
+    /// undo, since the monitor may now look switched off.
+    fn send_brightness(&mut self, i: usize) {
+        let value = self.brightness_values[i];
+        let previous = std::mem::replace(&mut self.committed[i], value);
+        let _ = self.tx_cmd.send(MonitorCmd::SetBrightness(i, value));
+        if previous == value {
+            return;
+        }
+
+        let history = &mut self.history[i];
+        if history.len() == UNDO_HISTORY {
+            history.pop_front();
+        }
+        history.push_back(previous);
+
+        if value < UNDO_BELOW && previous >= UNDO_BELOW {
+            match &mut self.undo {
+                Some((monitors, shown_at)) if shown_at.elapsed() < UNDO_DURATION => {
+                    if !monitors.contains(&i) {
+                        monitors.push(i);
+                    }
+                    *shown_at = Instant::now();
+                }
+                _ => self.undo = Some((vec![i], Instant::now())),
+            }
+        }
+    }
+
+    /// Offer to put back monitors that were just turned nearly off. Goes
+    /// away on its own after [`UNDO_DURATION`].
+    fn build_undo_toast(&mut self, ui: &mut egui::Ui) {
+        let Some((monitors, shown_at)) = &self.undo else {
+            return;
+        };
+        if shown_at.elapsed() >= UNDO_DURATION {
+            self.undo = None;
+            return;
+        }
+
+        let message = match monitors.as_slice() {
+            [i] => tr_args(
+                "undo_one",
+                &[
+                    ("name", &self.display_name(*i)),
+                    ("value", &self.brightness_values[*i]),
+                ],
+            ),
+            _ => tr_args("undo_many", &[("count", &monitors.len())]),
+        };
+        let mut restore = false;
+        ui.add_space(4.0);
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(message).small());
+            restore = ui.small_button(tr("undo")).clicked();
+        });
+        if !restore {
+            return;
+        }
+
+        let Some((monitors, _)) = self.undo.take() else {
+            return;
+        };
+        for i in monitors {
+            let Some(previous) = self.history[i].pop_back() else {
+                continue;
+            };
+            let (min, max) = self.brightness_range(i);
+            self.brightness_values[i] = previous.clamp(min, max);
+            self.committed[i] = self.brightness_values[i];
+            self.user_cooldowns[i] = Some(Instant::now());
+            let _ = self
+                .tx_cmd
+                .send(MonitorCmd::SetBrightness(i, self.brightness_values[i]));
+        }
+    }
+
+    /// Other visible, readable monitors of the same model as monitor `i`,
+    /// going by the EDID part of their ids.
+    fn same_model(&self, i: usize) -> Vec<usize> {
+        let Some(model) = edid::model_key(&self.monitor_ids[i]) else {
+            return Vec::new();
+        };
+        (0..self.monitor_ids.len())
+            .filter(|&j| j != i && !self.is_disabled(j) && self.errors[j].is_none())
+            .filter(|&j| edid::model_key(&self.monitor_ids[j]) == Some(model))
+            .collect()
+    }
+
+    /// Send monitor `i`'s brightness to every other monitor of its model,
+    /// clamped to each one's range. Returns how many were updated.
+    fn apply_to_same_model(&mut self, i: usize) -> usize {
+        let targets = self.same_model(i);
+        let value = self.brightness_values[i];
+        for &j in &targets {
+            let (min, max) = self.brightness_range(j);
+            self.brightness_values[j] = value.clamp(min, max);
+            self.user_cooldowns[j] = Some(Instant::now());
+            self.send_brightness(j);
+        }
+        targets.len()
+    }
+
+    /// Switches between full and compact rows, persists the choice and
+    /// resizes the window to fit.
+    fn build_layout_button(&mut self, ui: &mut egui::Ui) {
+        let toggle = ui
+            .add(egui::Button::selectable(self.compact, "☰").small())
+            .on_hover_text(tr("compact_layout"));
+        if toggle.clicked() {
+            self.compact = !self.compact;
+            let _ = self.tx_cmd.send(MonitorCmd::SetCompact(self.compact));
+            self.resize_to_fit(ui.ctx());
+        }
+    }
+
+    /// Pins the window above other windows or lets it go, and persists
+    /// the choice. [`eframe::App::update`] hands it to the window.
+    fn build_pin_button(&mut self, ui: &mut egui::Ui) {
+        let toggle = ui
+            .add(egui::Button::selectable(self.always_on_top, "📌").small())
+            .on_hover_text(tr("always_on_top"));
+        if toggle.clicked() {
+            self.always_on_top = !self.always_on_top;
+            let _ = self
+                .tx_cmd
+                .send(MonitorCmd::SetAlwaysOnTop(self.always_on_top));
+        }
+    }
+
+    fn settings(&self) -> Settings {
+        Settings {
+            theme: self.theme,
+            compact: self.compact,
+            polling: self.polling,
+            step_percent: self.step_percent,
+            fine_step_percent: self.fine_step_percent,
+            schedule_enabled: self.schedule_enabled,
+            curve_enabled: self.curve_enabled,
+            notify_changes: self.notify_changes,
+            hide_on_focus_loss: self.hide_on_focus_loss,
+            remember_per_setup: self.remember_per_setup,
+        }
+    }
+
+    /// Open or close the debug panel. Each opening reads the monitors
+    /// afresh.
+    fn toggle_debug(&mut self, ctx: &egui::Context) {
+        self.debug_open = !self.debug_open;
+        if self.debug_open {
+            self.settings_draft = None;
+            self.vcp_dumps = None;
+            let _ = self.tx_cmd.send(MonitorCmd::DumpVcp);
+        }
+        self.resize_to_fit(ctx);
+    }
+
+    /// The debug panel: each monitor's capability string and the VCP codes
+    /// it lists with their current values, for pasting into bug reports.
+    /// Read-only.
+    fn build_vcp_debug(&mut self, ui: &mut egui::Ui) {
+        ui.label(RichText::new(tr("vcp_debug_warning")).small().weak());
+        ui.add_space(4.0);
+        let Some(dumps) = &self.vcp_dumps else {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(RichText::new(tr("reading_monitors")).weak());
+            });
+            return;
+        };
+
+        let read_again = ui.small_button(tr("read_again")).clicked();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            if dumps.is_empty() {
+                ui.label(RichText::new(tr("no_displays")).weak());
+            }
+            for (i, dump) in dumps.iter().enumerate() {
+                egui::CollapsingHeader::new(&dump.name)
+                    .id_salt(("vcp_dump", i))
+                    .default_open(true)
+                    .show(ui, |ui| build_vcp_dump(ui, i, dump));
+            }
+        });
+
+        if read_again {
+            self.vcp_dumps = None;
+            let _ = self.tx_cmd.send(MonitorCmd::DumpVcp);
+        }
+    }
+
+    fn open_settings(&mut self, ctx: &egui::Context) {
+        let autostart = PlatformAutostart::new().is_startup_enabled();
+        self.settings_draft = Some(SettingsDraft {
+            saved: self.settings(),
+            settings: self.settings(),
+            autostart_saved: autostart,
+            autostart,
+        });
+        self.resize_to_fit(ctx);
+    }
+
+    /// Back to the monitor list, dropping anything not saved.
+    fn close_settings(&mut self, ctx: &egui::Context) {
+        self.settings_draft = None;
+        self.resize_to_fit(ctx);
+    }
+
+    /// The settings view: one row per setting, then Save and Cancel.
+    fn build_settings(&mut self, ui: &mut egui::Ui) {
+        let Some(draft) = self.settings_draft.as_mut() else {
+            return;
+        };
+        let settings = &mut draft.settings;
+
+        egui::Grid::new("settings")
+            .num_columns(2)
+            .spacing([12.0, 6.0])
+            .show(ui, |ui| {
+                ui.label(tr("start_on_login"));
+                ui.checkbox(&mut draft.autostart, "");
+                ui.end_row();
+
+                ui.label(tr("theme"));
+                let theme_label = |theme| match theme {
+                    ThemeMode::System => tr("theme_system"),
+                    ThemeMode::Light => tr("theme_light"),
+                    ThemeMode::Dark => tr("theme_dark"),
+                };
+                egui::ComboBox::from_id_salt("theme")
+                    .selected_text(theme_label(settings.theme))
+                    .show_ui(ui, |ui| {
+                        for theme in [ThemeMode::System, ThemeMode::Light, ThemeMode::Dark] {
+                            ui.selectable_value(&mut settings.theme, theme, theme_label(theme));
+                        }
+                    });
+                ui.end_row();
+
+                ui.label(tr("compact_layout"));
+                ui.checkbox(&mut settings.compact, "");
+                ui.end_row();
+
+                ui.label(tr("step"));
+                ui.add(
+                    egui::DragValue::new(&mut settings.step_percent)
+                        .range(1..=100)
+                        .suffix("%"),
+                );
+                ui.end_row();
+
+                ui.label(tr("fine_step"));
+                ui.add(
+                    egui::DragValue::new(&mut settings.fine_step_percent)
+                        .range(1..=100)
+                        .suffix("%"),
+                );
+                ui.end_row();
+
+                // Same bounds PollSettings clamps to
+                ui.label(tr("poll_interval"));
+                ui.add(
+                    egui::DragValue::new(&mut settings.polling.poll_interval_ms)
+                        .range(1000..=600_000)
+                        .speed(100)
+                        .suffix(" ms"),
+                );
+                ui.end_row();
+
+                ui.label(tr("user_cooldown"))
+                    .on_hover_text(tr("user_cooldown_hint"));
+                ui.add(
+                    egui::DragValue::new(&mut settings.polling.user_cooldown_ms)
+                        .range(0..=60_000)
+                        .speed(100)
+                        .suffix(" ms"),
+                );
+                ui.end_row();
+
+                ui.label(tr("follow_schedule"));
+                ui.checkbox(&mut settings.schedule_enabled, "");
+                ui.end_row();
+
+                ui.label(tr("follow_curve"));
+                ui.checkbox(&mut settings.curve_enabled, "");
+                ui.end_row();
+
+                if cfg!(target_os = "windows") {
+                    ui.label(tr("notify_changes"))
+                        .on_hover_text(tr("notify_changes_hint"));
+                    ui.checkbox(&mut settings.notify_changes, "");
+                    ui.end_row();
+                }
+
+                ui.label(tr("hide_on_focus_loss"))
+                    .on_hover_text(tr("hide_on_focus_loss_hint"));
+                ui.checkbox(&mut settings.hide_on_focus_loss, "");
+                ui.end_row();
+
+                ui.label(tr("remember_per_setup"))
+                    .on_hover_text(tr("remember_per_setup_hint"));
+                ui.checkbox(&mut settings.remember_per_setup, "");
+                ui.end_row();
+            });
+
+        let changed = draft.settings != draft.saved || draft.autostart != draft.autostart_saved;
+        ui.add_space(8.0);
+        let mut save = false;
+        let mut cancel = false;
+        ui.horizontal(|ui| {
+            save = ui
+                .add_enabled(changed, egui::Button::new(tr("save")))
+                .clicked();
+            cancel = ui.button(tr("cancel")).clicked();
+        });
+
+        if save {
+            self.save_settings();
+        }
+        if save || cancel {
+            self.close_settings(ui.ctx());
+        }
+    }
+
+    /// Create or remove the login entry, saying so if that fails, and tick
+    /// the tray's item to match whatever it ended up as.
+    fn set_autostart(&mut self, enabled: bool) {
+        let autostart = PlatformAutostart::new();
+        if !autostart.set_startup_enabled(enabled) {
+            self.toast = Some((tr("start_on_login_failed").to_string(), Instant::now()));
+        }
+        crate::tray::set_autostart_checked(autostart.is_startup_enabled());
+    }
+
+    /// Ask whether to turn start-on-login off, over the rest of the window.
+    fn build_autostart_confirm(&mut self, ctx: &egui::Context) {
+        let mut confirmed = false;
+        let mut cancelled = false;
+        let modal = egui::Modal::new(egui::Id::new("confirm_autostart_off")).show(ctx, |ui| {
+            ui.label(tr("stop_start_on_login"));
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                confirmed = ui.button(tr("turn_off")).clicked();
+                cancelled = ui.button(tr("cancel")).clicked();
+            });
+        });
+        if confirmed {
+            self.set_autostart(false);
+        }
+        if confirmed || cancelled || modal.should_close() {
+            self.confirm_autostart_off = false;
+        }
+    }
+
+    /// Apply the settings being edited here, hand them to the worker to
+    /// persist and switch start-on-login if that changed.
+    fn save_settings(&mut self) {
+        let Some(draft) = self.settings_draft.as_ref() else {
+            return;
+        };
+        let settings = draft.settings.clone();
+        if draft.autostart != draft.autostart_saved {
+            self.set_autostart(draft.autostart);
+        }
+
+        self.theme = settings.theme;
+        self.compact = settings.compact;
+        self.polling = settings.polling;
+        self.step_percent = settings.step_percent;
+        self.fine_step_percent = settings.fine_step_percent;
+        self.schedule_enabled = settings.schedule_enabled;
+        self.curve_enabled = settings.curve_enabled;
+        self.notify_changes = settings.notify_changes;
+        self.hide_on_focus_loss = settings.hide_on_focus_loss;
+        self.remember_per_setup = settings.remember_per_setup;
+        crate::hotkeys::set_step(self.step_percent);
+        let _ = self
+            .tx_cmd
+            .send(MonitorCmd::SaveSettings(Box::new(settings)));
+    }
+
+    /// Resizes the window to the current layout's height, keeping the
+    /// width the user gave it.
+    fn resize_to_fit(&self, ctx: &egui::Context) {
+        let width = ctx
+            .input(|i| i.viewport().inner_rect)
+            .map_or(WINDOW_WIDTH, |rect| rect.width());
+        let height = window_height(self.preferred_height());
+        ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(egui::vec2(width, height)));
+    }
+
+    /// Cycles System → Light → Dark and persists the choice.
+    fn build_theme_button(&mut self, ui: &mut egui::Ui) {
+        let (icon, label, next) = match self.theme {
+            ThemeMode::System => ("🖥", tr("theme_system"), ThemeMode::Light),
+            ThemeMode::Light => ("☀", tr("theme_light"), ThemeMode::Dark),
+            ThemeMode::Dark => ("🌙", tr("theme_dark"), ThemeMode::System),
+        };
+
+        if ui.small_button(icon).on_hover_text(label).clicked() {
+            self.theme = next;
+            let _ = self.tx_cmd.send(MonitorCmd::SetTheme(next));
+        }
+    }
+
+    fn is_inverted(&self, i: usize) -> bool {
+        self.inverted.contains(&self.monitor_ids[i])
+    }
+
+    fn sync_offset(&self, i: usize) -> i32 {
+        self.sync_offsets
+            .get(&self.monitor_ids[i])
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Field for how far monitor `i` sits above or below the master slider.
+    /// Sent to the worker when an edit finishes, like the limits.
+    fn build_sync_offset(&mut self, ui: &mut egui::Ui, i: usize) {
+        let mut offset = self.sync_offset(i);
+        let field = ui
+            .add(
+                egui::DragValue::new(&mut offset)
+                    .range(-100..=100)
+                    .prefix(tr("sync_offset"))
+                    .suffix("%")
+                    .custom_formatter(|value, _| format!("{value:+}")),
+            )
+            .on_hover_text(tr("sync_offset_hint"));
+        if offset != self.sync_offset(i) {
+            let id = self.monitor_ids[i].clone();
+            if offset == 0 {
+                self.sync_offsets.remove(&id);
+            } else {
+                self.sync_offsets.insert(id, offset);
+            }
+        }
+        if field.drag_stopped() || (field.changed() && !field.dragged()) {
+            let _ = self.tx_cmd.send(MonitorCmd::SetSyncOffset(i, offset));
+        }
+    }
+
+    /// Draw monitor `i`'s slider flipped, or back the usual way round. The
+    /// worker persists the choice.
+    fn set_inverted(&mut self, i: usize, inverted: bool) {
+        if inverted {
+            self.inverted.insert(self.monitor_ids[i].clone());
+        } else {
+            self.inverted.remove(&self.monitor_ids[i]);
+        }
+        let _ = self.tx_cmd.send(MonitorCmd::SetInverted(i, inverted));
+    }
+
+    fn is_disabled(&self, i: usize) -> bool {
+        self.disabled.contains(&self.monitor_ids[i])
+    }
+
+    /// Hide or show monitor `i`. The worker persists the change and stops
+    /// (or resumes) talking to the monitor.
+    fn set_disabled(&mut self, i: usize, disabled: bool) {
+        if disabled {
+            self.disabled.insert(self.monitor_ids[i].clone());
+        } else {
+            self.disabled.remove(&self.monitor_ids[i]);
+        }
+        let _ = self.tx_cmd.send(MonitorCmd::SetDisabled(i, disabled));
+    }
+
+    /// Monitor `i`'s name. Double-click or use the context menu to rename
+    /// it; Enter or clicking away saves, Escape cancels, and an empty name
+    /// goes back to the discovered one.
+    fn build_name_label(&mut self, ui: &mut egui::Ui, i: usize) {
+        if let Some((idx, text)) = self.renaming.as_mut()
+            && *idx == i
+        {
+            let edit = ui.add(egui::TextEdit::singleline(text).desired_width(160.0));
+            if !edit.has_focus() && !edit.lost_focus() {
+                edit.request_focus();
+            }
+            if ui.input(|input| input.key_pressed(Key::Escape)) {
+                self.renaming = None;
+            } else if edit.lost_focus() {
+                let name = text.trim().to_string();
+                self.renaming = None;
+                self.set_custom_name(i, Some(name).filter(|n| !n.is_empty()));
+            }
+            return;
+        }
+
+        let label = ui.add(
+            egui::Label::new(RichText::new(self.display_name(i)).strong())
+                .sense(egui::Sense::click()),
+        );
+        let label = if self.display_name(i) != self.monitor_names[i] {
+            label.on_hover_text(&self.monitor_names[i])
+        } else {
+            label
+        };
+        if label.double_clicked() {
+            self.renaming = Some((i, self.display_name(i).to_string()));
+        }
+        label.context_menu(|ui| {
+            if ui.button(tr("rename")).clicked() {
+                self.renaming = Some((i, self.display_name(i).to_string()));
+                ui.close();
+            }
+            if self.custom_names.contains_key(&self.monitor_ids[i])
+                && ui.button(tr("reset_name")).clicked()
+            {
+                self.set_custom_name(i, None);
+                ui.close();
+            }
+            let targets: Vec<usize> = (0..self.monitor_names.len())
+                .filter(|&j| j != i && self.is_controllable(j))
+                .collect();
+            if self.is_controllable(i) && !targets.is_empty() {
+                ui.menu_button(tr("copy_brightness_to"), |ui| {
+                    for j in targets {
+                        if ui.button(self.display_name(j)).clicked() {
+                            self.copy_brightness(i, j);
+                            ui.close();
+                        }
+                    }
+                });
+            }
+            ui.menu_button(tr("group"), |ui| self.build_group_menu(ui, i));
+        });
+    }
+
+    /// Existing groups to put monitor `i` in, and a field naming a new one.
+    fn build_group_menu(&mut self, ui: &mut egui::Ui, i: usize) {
+        let mut chosen = None;
+        for name in self.groups.keys() {
+            if ui.button(name).clicked() {
+                chosen = Some(name.clone());
+            }
+        }
+        if !self.groups.is_empty() {
+            ui.separator();
+        }
+        let edit = ui.add(
+            egui::TextEdit::singleline(&mut self.new_group)
+                .hint_text(tr("new_group"))
+                .desired_width(120.0),
+        );
+        if edit.lost_focus() && ui.input(|input| input.key_pressed(Key::Enter)) {
+            let name = std::mem::take(&mut self.new_group).trim().to_string();
+            chosen = Some(name).filter(|n| !n.is_empty());
+        }
+        if let Some(name) = chosen {
+            self.set_group(i, Some(name));
+            ui.close();
+        }
+    }
+
+    /// Visible monitors in group `name`, in panel order.
+    fn group_members(&self, name: &str) -> Vec<usize> {
+        let Some(ids) = self.groups.get(name) else {
+            return Vec::new();
+        };
+        (0..self.monitor_ids.len())
+            .filter(|&i| !self.is_disabled(i) && ids.contains(&self.monitor_ids[i]))
+            .collect()
+    }
+
+    /// Move monitor `i` into group `group`, creating it if it's new, or out
+    /// of its group with `None`. A group left with no members is deleted.
+    fn set_group(&mut self, i: usize, group: Option<String>) {
+        let id = self.monitor_ids[i].clone();
+        let mut changed = Vec::new();
+        for (name, members) in self.groups.iter_mut() {
+            if group.as_ref() != Some(name) && members.contains(&id) {
+                members.retain(|member| *member != id);
+                changed.push(name.clone());
+            }
+        }
+        if let Some(name) = group {
+            let members = self.groups.entry(name.clone()).or_default();
+            if !members.contains(&id) {
+                members.push(id);
+                changed.push(name);
+            }
+        }
+        for name in changed {
+            let members = self.groups.get(&name).filter(|m| !m.is_empty()).cloned();
+            if members.is_none() {
+                self.ungroup(&name);
+            } else {
+                let _ = self.tx_cmd.send(MonitorCmd::SaveGroup(name, members));
+            }
+        }
+    }
+
+    /// Delete group `name`, giving its monitors their own rows again.
+    fn ungroup(&mut self, name: &str) {
+        self.groups.remove(name);
+        self.group_values.remove(name);
+        let _ = self
+            .tx_cmd
+            .send(MonitorCmd::SaveGroup(name.to_string(), None));
+    }
+
+    /// One slider for group `name` in place of its `members`' rows. The
+    /// name's menu takes monitors out of the group or dissolves it.
+    fn build_group_row(&mut self, ui: &mut egui::Ui, name: &str, members: &[usize]) {
+        let held = self.group_values.get(name).copied();
+        let held = if self.compact {
+            ui.horizontal(|ui| {
+                ui.allocate_ui_with_layout(
+                    egui::vec2(COMPACT_NAME_WIDTH, ui.spacing().interact_size.y),
+                    egui::Layout::left_to_right(egui::Align::Center),
+                    |ui| {
+                        ui.set_min_width(COMPACT_NAME_WIDTH);
+                        self.build_group_label(ui, name, members);
+                    },
+                );
+                let width = (ui.available_width() - 60.0).max(40.0);
+                self.build_shared_slider(ui, members, held, width)
+            })
+            .inner
+        } else {
+            ui.horizontal(|ui| self.build_group_label(ui, name, members));
+            ui.add_space(4.0);
+            let width = (ui.available_width() - 60.0).max(100.0);
+            self.build_shared_slider(ui, members, held, width)
+        };
+        match held {
+            Some(value) => self.group_values.insert(name.to_string(), value),
+            None => self.group_values.remove(name),
+        };
+    }
+
+    fn build_group_label(&mut self, ui: &mut egui::Ui, name: &str, members: &[usize]) {
+        let names: Vec<String> = members
+            .iter()
+            .map(|&i| self.display_name(i).to_string())
+            .collect();
+        let label = ui
+            .add(
+                egui::Label::new(RichText::new(name).strong())
+                    .truncate()
+                    .sense(egui::Sense::click()),
+            )
+            .on_hover_text(names.join(", "));
+        label.context_menu(|ui| {
+            for (&i, member) in members.iter().zip(&names) {
+                if ui
+                    .button(tr_args("remove_from_group", &[("name", member)]))
+                    .clicked()
+                {
+                    self.set_group(i, None);
+                    ui.close();
+                }
+            }
+            ui.separator();
+            if ui.button(tr("ungroup")).clicked() {
+                self.ungroup(name);
+                ui.close();
+            }
+        });
+    }
+
+    /// Whether monitor `i` is shown with a working slider.
+    fn is_controllable(&self, i: usize) -> bool {
+        !self.is_disabled(i) && !self.needs_setup[i] && self.errors[i].is_none()
+    }
+
+    /// Give monitor `to` the brightness monitor `from` shows, within the
+    /// range `to`'s slider allows.
+    fn copy_brightness(&mut self, from: usize, to: usize) {
+        let (low, high) = self.brightness_range(to);
+        self.brightness_values[to] = self.brightness_values[from].clamp(low, high);
+        self.user_cooldowns[to] = Some(Instant::now());
+        self.send_brightness(to);
+    }
+
+    /// Give monitor `i` a custom name, or drop it with `None`.
+    fn set_custom_name(&mut self, i: usize, name: Option<String>) {
+        let id = self.monitor_ids[i].clone();
+        match &name {
+            Some(name) => self.custom_names.insert(id, name.clone()),
+            None => self.custom_names.remove(&id),
+        };
+        let _ = self.tx_cmd.send(MonitorCmd::Rename(i, name));
+    }
+
+    /// Monitor `i`'s brightness slider range: its hardware range narrowed to
+    /// any limits the user set.
+    fn brightness_range(&self, i: usize) -> (u32, u32) {
+        let (min, max) = self.min_max[i];
+        let limits = self.limits.get(&self.monitor_ids[i]);
+        limit_range(min, max, limits.map(|l| (l.min, l.max)))
+    }
+
+    /// Min and max fields for monitor `i`'s soft brightness range, shown in
+    /// its slider's context menu. Sent to the worker when an edit finishes
+    /// rather than on every drag step, since each change is saved.
+    fn build_limits_menu(&mut self, ui: &mut egui::Ui, i: usize) {
+        let (hw_min, hw_max) = self.min_max[i];
+        let (mut min, mut max) = self.brightness_range(i);
+        let mut finished = false;
+
+        ui.label(RichText::new(tr("brightness_limits")).small().weak());
+        ui.horizontal(|ui| {
+            let low = ui.add(
+                egui::DragValue::new(&mut min)
+                    .range(hw_min..=max)
+                    .prefix(tr("limit_min"))
+                    .suffix("%"),
+            );
+            let high = ui.add(
+                egui::DragValue::new(&mut max)
+                    .range(min..=hw_max)
+                    .prefix(tr("limit_max"))
+                    .suffix("%"),
+            );
+            for field in [low, high] {
+                finished |= field.drag_stopped() || (field.changed() && !field.dragged());
+            }
+        });
+
+        let id = self.monitor_ids[i].clone();
+        if (min, max) != self.brightness_range(i) {
+            if (min, max) == (hw_min, hw_max) {
+                self.limits.remove(&id);
+            } else {
+                self.limits
+                    .insert(id.clone(), BrightnessLimits { min, max });
+            }
+            self.brightness_values[i] = self.brightness_values[i].clamp(min, max);
+        }
+        if finished {
+            let limits = self.limits.get(&id).copied();
+            let _ = self.tx_cmd.send(MonitorCmd::SetLimits(i, limits));
+        }
+
+        if self.limits.contains_key(&id) && ui.button(tr("clear_limits")).clicked() {
+            self.limits.remove(&id);
+            let _ = self.tx_cmd.send(MonitorCmd::SetLimits(i, None));
+            ui.close();
+        }
+    }
+
+    /// Change the brightness monitor `i`'s reset button restores, or remove
+    /// it with `None`.
+    fn set_default(&mut self, i: usize, default: Option<u32>) {
+        match default {
+            Some(value) => self.defaults.insert(self.monitor_ids[i].clone(), value),
+            None => self.defaults.remove(&self.monitor_ids[i]),
+        };
+        let _ = self.tx_cmd.send(MonitorCmd::SetDefault(i, default));
+    }
+
+    /// Dropdown of saved profiles, applying whichever is picked, with
+    /// buttons to save the current brightness as a profile and to delete
+    /// the picked one.
+    fn build_profile_row(&mut self, ui: &mut egui::Ui) {
+        let mut apply = None;
+        let mut save = None;
+        let mut delete = false;
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(tr("profile")).strong());
+
+            if let Some(name) = self.naming_profile.as_mut() {
+                let edit = ui.add(egui::TextEdit::singleline(name).desired_width(120.0));
+                if !edit.has_focus() && !edit.lost_focus() {
+                    edit.request_focus();
+                }
+                if ui.input(|input| input.key_pressed(Key::Escape)) {
+                    self.naming_profile = None;
+                } else if edit.lost_focus() {
+                    let name = name.trim().to_string();
+                    self.naming_profile = None;
+                    save = Some(name).filter(|n| !n.is_empty());
+                }
+                return;
+            }
+
+            let selected = self.selected_profile.clone();
+            egui::ComboBox::from_id_salt("profile")
+                .width(120.0)
+                .selected_text(selected.as_deref().unwrap_or("—"))
+                .show_ui(ui, |ui| {
+                    for name in self.profiles.keys() {
+                        if ui
+                            .selectable_label(selected.as_ref() == Some(name), name)
+                            .clicked()
+                        {
+                            apply = Some(name.clone());
+                        }
+                    }
+                });
+            if ui
+                .small_button(tr("save"))
+                .on_hover_text(tr("save_profile_hint"))
+                .clicked()
+            {
+                self.naming_profile = Some(selected.clone().unwrap_or_default());
+            }
+            if selected.is_some() && ui.small_button(tr("delete")).clicked() {
+                delete = true;
+            }
+        });
+
+        if let Some(name) = apply {
+            self.apply_profile(&name);
+        }
+        if let Some(name) = save {
+            self.save_profile(name);
+        }
+        if delete && let Some(name) = self.selected_profile.take() {
+            self.profiles.remove(&name);
+            let _ = self.tx_cmd.send(MonitorCmd::SaveProfile(name, None));
+            crate::tray::set_profiles(&self.profile_names());
+        }
+    }
+
+    /// Set every listed monitor to its brightness in profile `name`.
+    fn apply_profile(&mut self, name: &str) {
+        let Some(profile) = self.profiles.get(name) else {
+            return;
+        };
+        for i in 0..self.monitor_ids.len() {
+            if let Some(&value) = profile.get(&self.monitor_ids[i]) {
+                let (min, max) = self.brightness_range(i);
+                self.brightness_values[i] = value.clamp(min, max);
+                self.committed[i] = self.brightness_values[i];
+                self.user_cooldowns[i] = Some(Instant::now());
+            }
+        }
+        self.selected_profile = Some(name.to_string());
+        let _ = self.tx_cmd.send(MonitorCmd::ApplyProfile(name.to_string()));
+    }
+
+    /// Store the brightness of every visible, readable monitor as profile
+    /// `name`, replacing any profile of that name.
+    fn save_profile(&mut self, name: String) {
+        let profile: Profile = (0..self.monitor_ids.len())
+            .filter(|&i| !self.is_disabled(i) && self.errors[i].is_none())
+            .map(|i| (self.monitor_ids[i].clone(), self.brightness_values[i]))
+            .collect();
+        self.profiles.insert(name.clone(), profile.clone());
+        self.selected_profile = Some(name.clone());
+        let _ = self
+            .tx_cmd
+            .send(MonitorCmd::SaveProfile(name, Some(profile)));
+        crate::tray::set_profiles(&self.profile_names());
+    }
+
+    /// "N hidden" line listing connected monitors the user has hidden, each
+    /// with a button to bring it back.
+    fn build_hidden_row(&mut self, ui: &mut egui::Ui) {
+        let hidden: Vec<usize> = (0..self.monitor_names.len())
+            .filter(|&i| self.is_disabled(i))
+            .collect();
+        if hidden.is_empty() {
+            return;
+        }
+
+        ui.add_space(4.0);
+        ui.separator();
+        let mut show = None;
+        egui::CollapsingHeader::new(
+            RichText::new(tr_args("hidden_count", &[("count", &hidden.len())]))
+                .small()
+                .weak(),
+        )
+        .id_salt("hidden_monitors")
+        .show(ui, |ui| {
+            for &i in &hidden {
+                ui.horizontal(|ui| {
+                    ui.label(self.display_name(i));
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.small_button(tr("show")).clicked() {
+                            show = Some(i);
+                        }
+                    });
+                });
+            }
+        });
+        if let Some(i) = show {
+            self.set_disabled(i, false);
+        }
+    }
+
+    /// Stands in for the brightness slider of a monitor that couldn't be
+    /// read: a disabled slider, the error, and a button to read it again.
+    fn build_error_row(&mut self, ui: &mut egui::Ui, i: usize) {
+        let slider_width = ui.available_width() - 60.0;
+        ui.spacing_mut().slider_width = slider_width.max(100.0);
+        ui.add_enabled(
+            false,
+            egui::Slider::new(&mut 0u32, 0..=100)
+                .suffix("%")
+                .show_value(false),
+        );
+
+        ui.horizontal(|ui| self.build_error_status(ui, i));
+    }
+
+    /// What's wrong with monitor `i`, shared by the full and compact rows:
+    /// the setup it needs, or "not responding" with a retry.
+    fn build_error_status(&mut self, ui: &mut egui::Ui, i: usize) {
+        let detail = self.errors[i].clone().unwrap_or_default();
+        let warn = ui.visuals().warn_fg_color;
+        if self.needs_setup[i] {
+            ui.label(RichText::new(detail).small().color(warn));
+            return;
+        }
+        ui.label(RichText::new(tr("ddc_not_responding")).small().color(warn))
+            .on_hover_text(detail);
+        if self.retrying[i] {
+            ui.label(RichText::new(tr("retrying")).small().weak());
+        } else if ui.small_button(tr("retry")).clicked() {
+            self.retrying[i] = true;
+            let _ = self.tx_cmd.send(MonitorCmd::Retry(i));
+        }
+    }
+
+    /// Whether to offer the DDC/CI help: it hasn't been dismissed, and no
+    /// shown monitor responds — each either couldn't be read or refused its
+    /// last write. Monitors waiting on setup, such as a missing ddcutil,
+    /// have their own message and don't count. Once a test has run the
+    /// help stays up so its results can be read.
+    fn show_ddc_help(&self) -> bool {
+        if self.ddc_help_dismissed {
+            return false;
+        }
+        if self.ddc_results.iter().any(Option::is_some) {
+            return true;
+        }
+        let mut shown = (0..self.monitor_names.len())
+            .filter(|&i| !self.is_disabled(i) && !self.needs_setup[i])
+            .peekable();
+        shown.peek().is_some() && shown.all(|i| self.errors[i].is_some() || self.write_failed[i])
+    }
+
+    /// Explains how to switch DDC/CI on, with a button that checks each
+    /// monitor by nudging its brightness.
+    fn build_ddc_help(&mut self, ui: &mut egui::Ui) {
+        egui::Frame::group(ui.style()).show(ui, |ui| {
+            ui.label(RichText::new(tr("ddc_help_title")).strong());
+            ui.label(RichText::new(tr("ddc_help_body")).small());
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                if self.ddc_testing {
+                    ui.label(RichText::new(tr("testing")).weak());
+                } else if ui.button(tr("test")).clicked() {
+                    self.ddc_testing = true;
+                    let _ = self.tx_cmd.send(MonitorCmd::TestDdc);
+                }
+                if ui.button(tr("dont_show_again")).clicked() {
+                    self.ddc_help_dismissed = true;
+                    let _ = self.tx_cmd.send(MonitorCmd::DismissDdcHelp);
+                }
+            });
+            for i in 0..self.monitor_names.len() {
+                let Some(passed) = self.ddc_results[i] else {
+                    continue;
+                };
+                let (mark, text) = if passed {
+                    ("✓", tr("responds"))
+                } else {
+                    ("✗", tr("no_response"))
+                };
+                ui.label(RichText::new(format!("{mark} {}: {text}", self.display_name(i))).small());
+            }
+        });
+    }
+
+    /// Shown instead of the monitor rows when discovery found nothing, e.g.
+    /// on a headless machine or with DDC/CI switched off on every monitor.
+    fn build_empty_state(&mut self, ui: &mut egui::Ui) {
+        ui.vertical_centered(|ui| {
+            ui.add_space(16.0);
+            ui.label(RichText::new(tr("no_displays")).strong());
+            ui.label(RichText::new(tr("check_ddc")).small().weak());
+            ui.add_space(8.0);
+            if self.rescanning {
+                ui.label(RichText::new(tr("scanning")).weak());
+            } else if ui.button(tr("rescan")).clicked() {
+                self.rescan();
+            }
+        });
+    }
+
+    /// Ask the worker to enumerate monitors again, unless it already is.
+    /// `rescanning` is cleared when the answer comes back.
+    fn rescan(&mut self) {
+        if !self.rescanning {
+            self.rescanning = true;
+            let _ = self.tx_cmd.send(MonitorCmd::Rescan);
+        }
+    }
+
+    /// Swap in a freshly read row for monitor `index` after a retry.
+    fn replace_monitor(&mut self, index: usize, info: MonitorInfo) {
+        if index >= self.monitor_names.len() {
+            return;
+        }
+        self.brightness_values[index] = info.brightness;
+        self.committed[index] = info.brightness;
+        self.min_max[index] = info.min_max;
+        self.contrast_values[index] = info.contrast;
+        self.contrast_min_max[index] = info.contrast_min_max;
+        self.color_presets[index] = info.color_presets;
+        self.color_preset_values[index] = info.color_preset;
+        self.volume_values[index] = info.volume;
+        self.volume_min_max[index] = info.volume_min_max;
+        self.errors[index] = info.error;
+        self.needs_setup[index] = info.needs_setup;
+        self.software[index] = info.software;
+        self.primary[index] = info.primary;
+        self.max_luminance[index] = info.max_luminance;
+        self.display_mode[index] = info.display_mode;
+        self.retrying[index] = false;
+        self.write_failed[index] = false;
+        self.contended[index] = false;
+    }
+
+    /// Consume brightness keys aimed at monitor `i`'s slider and return the
+    /// combined step in percent. Zero when the slider isn't focused. The
+    /// arrow keys follow the handle, so they swap on an inverted slider.
+    fn take_key_step(&self, ui: &mut egui::Ui, i: usize) -> i32 {
+        let focused = self.slider_ids[i].is_some_and(|id| ui.memory(|m| m.has_focus(id)));
+        if !focused {
+            return 0;
+        }
+        let flip = if self.is_inverted(i) { -1 } else { 1 };
+
+        ui.input_mut(|input| {
+            BRIGHTNESS_KEYS
+                .iter()
+                .map(|&(key, direction, fine)| {
+                    let (step, direction) = if fine {
+                        (self.fine_step_percent, direction * flip)
+                    } else {
+                        (self.step_percent, direction)
+                    };
+                    input.count_and_consume_key(Modifiers::NONE, key) as i32
+                        * direction
+                        * step as i32
+                })
+                .sum()
+        })
+    }
+
+    /// The percent for a digit key pressed while monitor `i`'s slider has
+    /// focus; the last one wins if several arrive in a frame. `None` while a
+    /// text field, such as the rename box, has the keyboard.
+    fn take_digit_percent(&self, ui: &mut egui::Ui, i: usize) -> Option<u32> {
+        let focused = self.slider_ids[i].is_some_and(|id| ui.memory(|m| m.has_focus(id)));
+        if !focused || ui.ctx().wants_keyboard_input() {
+            return None;
+        }
+
+        ui.input_mut(|input| {
+            DIGIT_KEYS
+                .iter()
+                .filter(|&&(key, _)| input.consume_key(Modifiers::NONE, key))
+                .map(|&(_, percent)| percent)
+                .last()
+        })
+    }
+
+    /// Send a pending keyboard change once the key is released, or every
+    /// KEY_REPEAT_INTERVAL while it's held so the channel isn't flooded.
+    fn flush_key_change(&mut self, ui: &egui::Ui, i: usize) {
+        if !self.key_pending[i] {
+            return;
+        }
+
+        let held = ui.input(|input| {
+            BRIGHTNESS_KEYS
+                .iter()
+                .any(|&(key, _, _)| input.key_down(key))
+        });
+        let throttled = self.last_key_send[i].is_some_and(|t| t.elapsed() < KEY_REPEAT_INTERVAL);
+        if held && throttled {
+            // Make sure the final value still goes out if no more events arrive
+            ui.ctx().request_repaint_after(KEY_REPEAT_INTERVAL);
+            return;
+        }
+
+        self.key_pending[i] = false;
+        self.last_key_send[i] = Some(Instant::now());
+        self.user_cooldowns[i] = Some(Instant::now());
+        self.send_brightness(i);
+    }
+
+    /// Turn mouse-wheel movement over monitor `i`'s slider into a step in
+    /// percent. Partial notches (e.g. from a touchpad) carry over to later
+    /// frames while the pointer stays on the slider. Like the arrow keys,
+    /// the wheel moves the other way on an inverted slider.
+    fn take_scroll_step(&mut self, ui: &egui::Ui, slider: &egui::Response, i: usize) -> i32 {
+        if !slider.contains_pointer() {
+            self.scroll_notches[i] = 0.0;
+            return 0;
+        }
+
+        let delta = ui.input(|input| input.raw_scroll_delta.y);
+        let notch = ui.ctx().options(|o| o.input_options.line_scroll_speed);
+        self.scroll_notches[i] += delta / notch;
+        let notches = self.scroll_notches[i].trunc();
+        self.scroll_notches[i] -= notches;
+        let flip = if self.is_inverted(i) { -1 } else { 1 };
+        notches as i32 * flip * self.fine_step_percent as i32
+    }
+
+    /// Send a scrolled value once the wheel has rested for SCROLL_IDLE, so
+    /// a quick spin turns into a single command.
+    fn flush_scroll_change(&mut self, ui: &egui::Ui, i: usize) {
+        let Some(last_scroll) = self.scroll_pending[i] else {
+            return;
+        };
+
+        let idle = last_scroll.elapsed();
+        if idle < SCROLL_IDLE {
+            // Make sure the value still goes out if no more events arrive
+            ui.ctx().request_repaint_after(SCROLL_IDLE - idle);
+            return;
+        }
+
+        self.scroll_pending[i] = None;
+        self.user_cooldowns[i] = Some(Instant::now());
+        self.send_brightness(i);
+    }
+
+    /// Send a clicked value once the click can no longer turn into a
+    /// double-click.
+    fn flush_click(&mut self, ui: &egui::Ui, i: usize) {
+        let Some(clicked_at) = self.click_pending[i] else {
+            return;
+        };
+
+        let window = ui
+            .ctx()
+            .options(|options| options.input_options.max_double_click_delay);
+        let window = Duration::from_secs_f64(window);
+        let waited = clicked_at.elapsed();
+        if waited < window {
+            ui.ctx().request_repaint_after(window - waited);
+            return;
+        }
+
+        self.click_pending[i] = None;
+        self.user_cooldowns[i] = Some(Instant::now());
+        self.send_brightness(i);
+    }
+
+    /// The master slider is only worth showing for two or more monitors.
+    fn shows_sync_row(&self) -> bool {
+        (0..self.monitor_names.len())
+            .filter(|&i| !self.is_disabled(i))
+            .count()
+            > 1
+    }
+
+    /// Master slider that moves every monitor together, each with its own
+    /// offset added. Shows the average brightness, offsets taken back out,
+    /// when idle and fans out one command per monitor on release.
+    fn build_sync_row(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(tr("all_monitors")).strong());
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.toggle_value(&mut self.sync_linked, tr("link"))
+                    .on_hover_text(tr("link_hint"));
+            });
+        });
+
+        if !self.sync_linked {
+            self.sync_value = None;
+            return;
+        }
+
+        ui.add_space(4.0);
+        let members: Vec<usize> = (0..self.monitor_names.len())
+            .filter(|&i| !self.is_disabled(i))
+            .collect();
+        let slider_width = ui.available_width() - 60.0;
+        self.sync_value =
+            self.build_shared_slider(ui, &members, self.sync_value, slider_width.max(100.0));
+    }
+
+    /// Slider that sets every monitor in `members` at once, each through its
+    /// sync offset. It rests at their average with the offsets taken out;
+    /// `held` is where it's being dragged instead. Returns the new `held`.
+    fn build_shared_slider(
+        &mut self,
+        ui: &mut egui::Ui,
+        members: &[usize],
+        held: Option<u32>,
+        width: f32,
+    ) -> Option<u32> {
+        let readable: Vec<u32> = members
+            .iter()
+            .filter(|&&i| self.errors[i].is_none())
+            .map(|&i| {
+                let value = self.brightness_values[i].saturating_add_signed(-self.sync_offset(i));
+                value.min(100)
+            })
+            .collect();
+        let average = readable.iter().sum::<u32>() / readable.len().max(1) as u32;
+        let mut value = held.unwrap_or(average);
+
+        ui.spacing_mut().slider_width = width;
+        let slider = ui.add(
+            egui::Slider::new(&mut value, 0..=100)
+                .suffix("%")
+                .show_value(true),
+        );
+
+        let mut held = held;
+        if slider.changed() {
+            held = Some(value);
+            for &i in members {
+                let range = self.brightness_range(i);
+                self.brightness_values[i] = offset_brightness(value, self.sync_offset(i), range);
+                self.user_cooldowns[i] = Some(Instant::now());
+            }
+        }
+
+        if slider.drag_stopped() {
+            held = None;
+            for &i in members {
+                self.user_cooldowns[i] = Some(Instant::now());
+                self.send_brightness(i);
+            }
+        }
+        held
+    }
+}
+
+/// The worker's ends of its channels to the UI, and the state it shares
+/// with it.
+struct WorkerLink {
+    rx_cmd: Receiver<MonitorCmd>,
+    tx_update: Sender<MonitorUpdate>,
+    tx_average: Sender<u32>,
+    snapshot: MonitorSnapshot,
+    visible: Arc<AtomicBool>,
+    /// Where config changes are saved, or `None` to keep them in memory.
+    config_path: Option<PathBuf>,
+}
+
+/// The worker thread: owns the monitors, applies the UI's commands and
+/// reports back what changed. Runs until the UI drops its command sender,
+/// then puts the monitors as `on_exit` says and releases them.
+fn run_worker<P: MonitorProvider>(mut provider: P, mut config: Config, link: WorkerLink) {
+    let WorkerLink {
+        rx_cmd,
+        tx_update,
+        tx_average,
+        snapshot,
+        visible,
+        config_path,
+    } = link;
+    let mut polling = config.polling;
+    // Reading every monitor takes seconds with several DDC/CI
+    // displays, so it happens here and the window opens right away.
+    // Discovery failing leaves an empty panel: re-enumeration still
+    // picks up monitors that show up later.
+    let mut monitors = provider.get_monitors().unwrap_or_else(|e| {
+        log::warn!("Monitor discovery failed: {e}");
+        Vec::new()
+    });
+    primary_first(&mut monitors, MonitorHandle::is_primary);
+    let mut infos: Vec<MonitorInfo> = monitors
+        .iter_mut()
+        .map(|mon| MonitorInfo::read(mon, polling.fallback_brightness(), config.brightness_scale))
+        .collect();
+
+    migrate_name_keys(&mut config, &infos, config_path.as_deref());
+
+    // Restore the last brightness the user chose for each monitor,
+    // or for this set of monitors. Applied on the first pass, before
+    // any schedule.
+    let ids: Vec<String> = infos.iter().map(|info| info.id.clone()).collect();
+    let mut setup = setup_key(&ids);
+    let mut restore: Vec<Option<u32>> = vec![None; infos.len()];
+    for (i, info) in infos.iter_mut().enumerate() {
+        if let Some(saved) = config.saved_brightness(&setup, &info.id) {
+            let (min, max) = info.min_max;
+            info.brightness = saved.clamp(min, max);
+            restore[i] = Some(saved);
+        }
+    }
+
+    // Last known (current, min, max) per monitor, so relative commands
+    // like IncreaseAll don't need a slow DDC read first.
+    let mut levels: Vec<(u32, u32, u32)> = infos.iter().map(MonitorInfo::level).collect();
+    let _ = tx_update.send(MonitorUpdate::ListChanged(infos));
+
+    let mut last_poll = Instant::now();
+    let mut cooldowns: Vec<Option<Instant>> = vec![None; monitors.len()];
+    let mut limiter = WriteLimiter::new(monitors.len());
+    let mut contention = ContentionTracker::new(monitors.len());
+    // Set when brightness changes; the config is saved once it has
+    // been quiet for CONFIG_SAVE_DELAY.
+    let mut config_dirty: Option<Instant> = None;
+    let mut polls_since_enumerate = 0u32;
+    // A rescan asked for just as the window was hidden, answered
+    // once it's shown again so the UI isn't left waiting
+    let mut rescan_requested = false;
+    let mut restore = Some(restore);
+    // When the user last changed each monitor's brightness, and which
+    // schedule entry was last applied to it.
+    let mut manual_at: Vec<Option<Instant>> = vec![None; monitors.len()];
+    let mut schedule_applied: Vec<Option<usize>> = vec![None; monitors.len()];
+    let mut last_schedule_check: Option<Instant> = None;
+    let mut last_curve_check: Option<Instant> = None;
+    let mut last_average: Option<u32> = None;
+    // Opened here since the sensor may not be Send. Stays `None`
+    // (mode off) when disabled or when there's no sensor.
+    let mut ambient_sensor = if config.ambient.enabled {
+        let sensor = PlatformAmbientSensor::open();
+        if sensor.is_none() {
+            log::warn!("No ambient light sensor; auto-brightness is off");
+        }
+        sensor
+    } else {
+        None
+    };
+    let mut ambient_applied: Option<u32> = None;
+    let mut last_ambient_check: Option<Instant> = None;
+    let mut fullscreen_detector = if config.fullscreen.enabled {
+        let detector = PlatformFullscreenDetector::open();
+        if detector.is_none() {
+            log::warn!("Can't detect fullscreen apps here; the fullscreen rule is off");
+        }
+        detector
+    } else {
+        None
+    };
+    // While a fullscreen app is in front: when it appeared, and each
+    // monitor's brightness before it did
+    let mut fullscreen_since: Option<(Instant, Vec<u32>)> = None;
+    let mut last_fullscreen_check: Option<Instant> = None;
+    let mut battery = if config.battery.enabled {
+        let battery = PlatformBattery::open();
+        if battery.is_none() {
+            log::warn!("No battery found; the battery cap is off");
+        }
+        battery
+    } else {
+        None
+    };
+    // Brightness (0-100) every write is held under while on battery
+    let mut battery_cap: Option<u32> = None;
+    let mut last_battery_check: Option<Instant> = None;
+    // When to re-apply saved brightness after a power event
+    let mut reapply_at: Option<Instant> = None;
+    let mut connect_watcher = ConnectWatcher::new(monitors.iter().map(|m| m.id()).collect());
+    // On-connect presets picked up by the last re-enumeration,
+    // queued with the next tick's commands
+    let mut connect_presets: Vec<MonitorCmd> = Vec::new();
+    let mut history_log = config.history_log.then(History::open).flatten();
+    // What asked for each monitor's latest queued brightness, kept
+    // across ticks for writes the limiter holds back
+    let mut sources: Vec<Option<history::Source>> = vec![None; monitors.len()];
+    // Hotkey and schedule changes made while hidden and not shown
+    // yet, as each monitor's name and percent, and when to show them
+    let mut unnotified: BTreeMap<usize, (String, u32)> = BTreeMap::new();
+    let mut notify_at: Option<Instant> = None;
+
+    loop {
+        if config_dirty.is_some_and(|t| t.elapsed() >= CONFIG_SAVE_DELAY) {
+            save_config(&config, config_path.as_deref());
+            config_dirty = None;
+        }
+
+        // Published for the HTTP API, with names as the panel shows them
+        let current: Vec<_> = monitors
+            .iter()
+            .zip(&levels)
+            .map(|(mon, &level)| {
+                let name = config
+                    .names
+                    .get(&mon.id())
+                    .map_or(mon.name(), String::as_str);
+                (name.to_string(), level)
+            })
+            .collect();
+        *snapshot.lock().unwrap() = current;
+
+        let average = average_brightness(&monitors, &levels, &config.disabled);
+        if average != last_average {
+            last_average = average;
+            if let Some(average) = average {
+                let _ = tx_average.send(average);
+            }
+        }
+
+        let is_visible = visible.load(Ordering::Relaxed);
+        let mut pending = PendingCmds::new(monitors.len());
+        let mut disconnected = false;
+        for cmd in connect_presets.drain(..) {
+            pending.queue(cmd, &levels);
+        }
+        for source in pending.source.iter_mut().flatten() {
+            *source = history::Source::Connect;
+        }
+
+        if is_visible {
+            // Visible: drain all pending commands, collapsing to only
+            // the latest value per monitor.
+            loop {
+                match rx_cmd.try_recv() {
+                    Ok(cmd) => pending.queue(cmd, &levels),
+                    Err(TryRecvError::Disconnected) => {
+                        disconnected = true;
+                        break;
+                    }
+                    Err(TryRecvError::Empty) => break,
+                }
+            }
+        } else {
+            // When hidden: block on channel, skip all hardware polling.
+            // Wake early if a held-back write is due.
+            let now = Instant::now();
+            let reapply_due = reapply_at.map(|at| at.saturating_duration_since(now));
+            let notify_due = notify_at.map(|at| at.saturating_duration_since(now));
+            let timeout = limiter
+                .next_due(now)
+                .into_iter()
+                .chain(reapply_due)
+                .chain(notify_due)
+                .min()
+                .map_or(Duration::from_secs(1), |due| {
+                    due.max(Duration::from_millis(1))
+                });
+            match rx_cmd.recv_timeout(timeout) {
+                Ok(cmd) => pending.queue(cmd, &levels),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => disconnected = true,
+            }
+        }
+
+        if disconnected {
+            if config_dirty.is_some() {
+                save_config(&config, config_path.as_deref());
+            }
+            // Before the handles are released, which on Windows
+            // destroys the physical monitors.
+            for (mon, &(_, min, max)) in monitors.iter_mut().zip(&levels) {
+                if config.disabled.contains(&mon.id()) || !mon.supports_brightness() {
+                    continue;
+                }
+                let limits = config.limits.get(&mon.id());
+                let range = limit_range(min, max, limits.map(|l| (l.min, l.max)));
+                let scale = config.brightness_scale;
+                if let Err(e) = apply_on_exit(mon, config.on_exit, scale, (min, max), range) {
+                    log::warn!("Failed to restore brightness on {}: {e}", mon.name());
+                }
+            }
+            provider.cleanup_monitors(&mut monitors);
+            return;
+        }
+
+        for (idx, disabled) in pending.disabled.iter().enumerate() {
+            let id = monitors[idx].id();
+            let changed = match disabled {
+                Some(true) => config.disabled.insert(id),
+                Some(false) => config.disabled.remove(&id),
+                None => false,
+            };
+            if changed {
+                config_dirty = Some(Instant::now());
+            }
+        }
 
-/// How long to suppress poll updates after user interaction.
-/// Covers DDC/CI round-trip (~1-2s) plus buffer.
-const USER_COOLDOWN: Duration = Duration::from_secs(4);
+        if let Some(theme) = pending.theme
+            && theme != config.theme
+        {
+            config.theme = theme;
+            config_dirty = Some(Instant::now());
+        }
+        if let Some(compact) = pending.compact
+            && compact != config.compact
+        {
+            config.compact = compact;
+            config_dirty = Some(Instant::now());
+        }
+        if let Some(on_top) = pending.always_on_top
+            && on_top != config.always_on_top
+        {
+            config.always_on_top = on_top;
+            config_dirty = Some(Instant::now());
+        }
+        // Saved straight away since the user pressed Save. New
+        // timings take effect from the next tick.
+        if let Some(settings) = pending.settings.take() {
+            settings.apply_to(&mut config);
+            polling = config.polling;
+            save_config(&config, config_path.as_deref());
+            config_dirty = None;
+        }
 
-/// How often to poll hardware for current brightness.
-const POLL_INTERVAL: Duration = Duration::from_secs(5);
+        // Saved straight away: defaults and names are set deliberately
+        // and rarely, unlike a slider drag
+        let mut saved_settings_changed = false;
+        for (idx, default) in pending.default.iter().enumerate() {
+            let id = monitors[idx].id();
+            saved_settings_changed |= match default {
+                Some(Some(value)) => config.default_brightness.insert(id, *value) != Some(*value),
+                Some(None) => config.default_brightness.remove(&id).is_some(),
+                None => false,
+            };
+        }
+        for (idx, name) in pending.rename.iter().enumerate() {
+            let id = monitors[idx].id();
+            saved_settings_changed |= match name {
+                Some(Some(name)) => config.names.insert(id, name.clone()).as_ref() != Some(name),
+                Some(None) => config.names.remove(&id).is_some(),
+                None => false,
+            };
+        }
+        for (idx, limits) in pending.limits.iter().enumerate() {
+            let Some(limits) = limits else { continue };
+            let id = monitors[idx].id();
+            saved_settings_changed |= match limits {
+                Some(limits) => config.limits.insert(id, *limits) != Some(*limits),
+                None => config.limits.remove(&id).is_some(),
+            };
+            // Pull the monitor into its new range right away
+            let (cur, min, max) = levels[idx];
+            let (low, high) = limit_range(min, max, limits.map(|l| (l.min, l.max)));
+            if !(low..=high).contains(&cur) {
+                pending.brightness[idx].get_or_insert(cur);
+            }
+        }
+        for (idx, inverted) in pending.inverted.iter().enumerate() {
+            let id = monitors[idx].id();
+            saved_settings_changed |= match inverted {
+                Some(true) => config.inverted.insert(id),
+                Some(false) => config.inverted.remove(&id),
+                None => false,
+            };
+        }
+        for (idx, offset) in pending.sync_offsets.iter().enumerate() {
+            let id = monitors[idx].id();
+            saved_settings_changed |= match *offset {
+                Some(0) => config.sync_offsets.remove(&id).is_some(),
+                Some(offset) => config.sync_offsets.insert(id, offset) != Some(offset),
+                None => false,
+            };
+        }
+        for (name, profile) in pending.profiles.drain(..) {
+            saved_settings_changed |= match profile {
+                Some(profile) => config.profiles.insert(name, profile.clone()) != Some(profile),
+                None => config.profiles.remove(&name).is_some(),
+            };
+        }
+        for (name, members) in pending.groups.drain(..) {
+            saved_settings_changed |= match members {
+                Some(members) => config.groups.insert(name, members.clone()) != Some(members),
+                None => config.groups.remove(&name).is_some(),
+            };
+        }
+        if pending.dismiss_ddc_help && !config.ddc_help_dismissed {
+            config.ddc_help_dismissed = true;
+            saved_settings_changed = true;
+        }
+        if saved_settings_changed {
+            save_config(&config, config_path.as_deref());
+        }
 
-/// How often the background thread checks for incoming commands.
-const CMD_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+        if pending.reset_all {
+            for (idx, mon) in monitors.iter().enumerate() {
+                if let Some(&default) = config.default_brightness.get(&mon.id()) {
+                    let (_, min, max) = levels[idx];
+                    pending.brightness[idx] = Some(default.clamp(min, max));
+                }
+            }
+        }
 
-impl TrayBrightUI {
-    pub fn new() -> anyhow::Result<Self> {
-        let mut monitors = get_monitors()?;
+        if let Some(name) = pending.apply_profile.take() {
+            match config.profiles.get(&name) {
+                Some(profile) => {
+                    for (idx, mon) in monitors.iter().enumerate() {
+                        if let Some(&value) = profile.get(&mon.id()) {
+                            let (_, min, max) = levels[idx];
+                            pending.brightness[idx] = Some(value.clamp(min, max));
+                        }
+                    }
+                }
+                None => log::warn!("No profile named {name}"),
+            }
+        }
 
-        let (tx_cmd, rx_cmd) = channel::<MonitorCmd>();
-        let (tx_update, rx_update) = channel::<MonitorUpdate>();
+        // Anything queued so far came from the user, apart from on-connect
+        // presets no command replaced, which mustn't hold off the schedule
+        for (idx, val) in pending.brightness.iter().enumerate() {
+            if val.is_some() && pending.source[idx] != Some(history::Source::Connect) {
+                manual_at[idx] = Some(Instant::now());
+            }
+        }
+        pending.tag(history::Source::User);
 
-        let mut monitor_names = vec![];
-        let mut brightness_values = vec![];
-        let mut min_max = vec![];
+        // Each new event pushes the re-apply back, so a burst of them
+        // costs one round of writes
+        if pending.power_changed {
+            reapply_at = Some(Instant::now() + POWER_SETTLE);
+        }
+        if reapply_at.is_some_and(|at| Instant::now() >= at) {
+            reapply_at = None;
+            for (slot, mon) in pending.brightness.iter_mut().zip(&monitors) {
+                if let Some(saved) = config.saved_brightness(&setup, &mon.id()) {
+                    slot.get_or_insert(saved);
+                }
+            }
+        }
 
-        for mon in monitors.iter_mut() {
-            let (cur, min, max) = mon.poll_brightness_values().unwrap_or((
-                DEFAULT_BRIGHTNESS,
-                DEFAULT_BRIGHTNESS,
-                DEFAULT_BRIGHTNESS,
-            ));
+        if let Some(restore) = restore.take() {
+            for (slot, saved) in pending.brightness.iter_mut().zip(restore) {
+                if slot.is_none() {
+                    *slot = saved;
+                }
+            }
+        }
+        pending.tag(history::Source::Restore);
 
-            monitor_names.push(mon.name.clone());
-            brightness_values.push(cur);
-            min_max.push((min, max));
+        if config.schedule.enabled
+            && last_schedule_check.is_none_or(|t| t.elapsed() >= SCHEDULE_CHECK_INTERVAL)
+        {
+            last_schedule_check = Some(Instant::now());
+            queue_scheduled(
+                &config.schedule,
+                &monitors,
+                &mut pending,
+                &mut schedule_applied,
+                &manual_at,
+            );
+            pending.tag(history::Source::Schedule);
         }
 
-        let monitor_count = monitors.len();
-        let visible = Arc::new(AtomicBool::new(false)); // starts hidden
-        let worker_visible = visible.clone();
+        if config.curve.enabled
+            && last_curve_check.is_none_or(|t| t.elapsed() >= config.curve.interval())
+        {
+            last_curve_check = Some(Instant::now());
+            queue_curve(&config.curve, &mut pending, &levels, &manual_at);
+            pending.tag(history::Source::Curve);
+        }
 
-        std::thread::spawn(move || {
-            let mut monitors = monitors;
-            let mut last_poll = Instant::now();
-            let mut cooldowns: Vec<Option<Instant>> = vec![None; monitor_count];
+        if let Some(sensor) = ambient_sensor.as_mut()
+            && last_ambient_check.is_none_or(|t| t.elapsed() >= AMBIENT_CHECK_INTERVAL)
+        {
+            last_ambient_check = Some(Instant::now());
+            if let Some(lux) = sensor.read_lux() {
+                let target = config.ambient.target_for(lux);
+                if ambient::should_apply(ambient_applied, target) {
+                    ambient_applied = Some(target);
+                    // User commands queued this tick take precedence
+                    for slot in pending.brightness.iter_mut() {
+                        slot.get_or_insert(target);
+                    }
+                    pending.tag(history::Source::Ambient);
+                }
+            }
+        }
 
-            loop {
-                // When hidden: block on channel, skip all hardware polling
-                if !worker_visible.load(Ordering::Relaxed) {
-                    match rx_cmd.recv_timeout(Duration::from_secs(1)) {
-                        Ok(MonitorCmd::SetBrightness(idx, val)) => {
-                            let _ = monitors[idx].set_brightness(val);
-                            cooldowns[idx] = Some(Instant::now());
-                            let _ = tx_update.send(MonitorUpdate {
-                                index: idx,
-                                brightness: val,
-                            });
-                        }
-                        Err(RecvTimeoutError::Timeout) => {}
-                        Err(RecvTimeoutError::Disconnected) => {
-                            cleanup_monitors(&mut monitors);
-                            return;
+        if let Some(detector) = fullscreen_detector.as_mut()
+            && last_fullscreen_check.is_none_or(|t| t.elapsed() >= FULLSCREEN_CHECK_INTERVAL)
+        {
+            last_fullscreen_check = Some(Instant::now());
+            let targets = match (&fullscreen_since, detector.is_fullscreen()) {
+                (None, true) => {
+                    let before: Vec<u32> = levels.iter().map(|&(cur, _, _)| cur).collect();
+                    let targets =
+                        config
+                            .fullscreen
+                            .targets(true, &before, &vec![false; before.len()]);
+                    fullscreen_since = Some((Instant::now(), before));
+                    targets
+                }
+                (Some((since, before)), false) => {
+                    let touched: Vec<bool> = manual_at
+                        .iter()
+                        .map(|t| t.is_some_and(|t| t > *since))
+                        .collect();
+                    let targets = config.fullscreen.targets(false, before, &touched);
+                    fullscreen_since = None;
+                    targets
+                }
+                _ => Vec::new(),
+            };
+            // User commands queued this tick take precedence
+            for (slot, target) in pending.brightness.iter_mut().zip(targets) {
+                if let Some(target) = target {
+                    slot.get_or_insert(target);
+                }
+            }
+            pending.tag(history::Source::Fullscreen);
+        }
+
+        if let Some(battery) = battery.as_mut()
+            && last_battery_check.is_none_or(|t| t.elapsed() >= BATTERY_CHECK_INTERVAL)
+        {
+            last_battery_check = Some(Instant::now());
+            let cap = battery
+                .charge_on_battery()
+                .and_then(|charge| config.battery.cap_for(charge, battery_cap));
+            if cap != battery_cap {
+                log::info!("Battery brightness cap is now {cap:?}");
+                battery_cap = cap;
+                // Monitors brighter than the new cap come down to it
+                if let Some(cap) = cap {
+                    for (slot, &(cur, min, max)) in pending.brightness.iter_mut().zip(&levels) {
+                        let cap = percent_of_range(min, max, cap);
+                        if cur > cap {
+                            slot.get_or_insert(cap);
                         }
                     }
-                    continue;
+                    pending.tag(history::Source::Battery);
                 }
+            }
+        }
+
+        // The panic key is for recovering from a screen too dark to
+        // use, so nothing below gets to hold it back
+        let forced: Vec<bool> = levels
+            .iter()
+            .enumerate()
+            .map(|(idx, &(_, _, max))| pending.forces_full(idx, max))
+            .collect();
+
+        // Disabled monitors are left alone entirely, as are ones that
+        // would ignore the writes
+        for (idx, mon) in monitors.iter().enumerate() {
+            if (config.disabled.contains(&mon.id()) && !forced[idx]) || !mon.supports_brightness() {
+                pending.clear(idx);
+                limiter.clear(idx);
+            }
+        }
+
+        for (source, &tag) in sources.iter_mut().zip(&pending.source) {
+            if tag.is_some() {
+                *source = tag;
+            }
+        }
 
-                // Visible: drain all pending commands, collapsing to only
-                // the latest value per monitor.
-                let mut pending: Vec<Option<u32>> = vec![None; monitor_count];
-                let mut disconnected = false;
+        // Hotkeys and the tray can send far faster than a monitor
+        // can take writes
+        let limited: Vec<Option<u32>> = pending
+            .brightness
+            .iter()
+            .zip(&forced)
+            .map(|(&value, &forced)| value.filter(|_| !forced))
+            .collect();
+        let mut due = limiter.take_due(&limited, Instant::now());
+        for (idx, &forced) in forced.iter().enumerate() {
+            if forced {
+                limiter.clear(idx);
+                due[idx] = pending.brightness[idx];
+            }
+        }
+        pending.brightness = due;
 
-                loop {
-                    match rx_cmd.try_recv() {
-                        Ok(MonitorCmd::SetBrightness(idx, val)) => {
-                            pending[idx] = Some(val);
+        // Apply only the final value for each monitor, inside any
+        // limits the user set, whoever asked for it. Backends may
+        // just queue the write (Windows DDC/CI monitors each have a
+        // writer thread), so every write goes out before any is
+        // waited on and a slow monitor doesn't hold up the rest.
+        let mut writes = Vec::new();
+        for (idx, &requested) in pending.brightness.iter().enumerate() {
+            if let Some(requested) = requested {
+                let (_, min, max) = levels[idx];
+                let (val, hardware) = if forced[idx] {
+                    (max, max)
+                } else {
+                    let limits = config.limits.get(&monitors[idx].id());
+                    let (low, high) = limit_range(min, max, limits.map(|l| (l.min, l.max)));
+                    let mut val = requested.clamp(low, high);
+                    // Over the user's own lower limit, too
+                    if let Some(cap) = battery_cap {
+                        val = val.min(percent_of_range(min, max, cap));
+                    }
+                    (val, to_hardware(config.brightness_scale, val, min, max))
+                };
+                let written = if config.verify_writes {
+                    crate::monitors::set_verified(&mut monitors[idx], hardware)
+                } else {
+                    monitors[idx].set_brightness(hardware).map(|()| hardware)
+                };
+                writes.push((idx, requested, val, written));
+            }
+        }
+        for (idx, requested, mut val, written) in writes {
+            let (_, min, max) = levels[idx];
+            let scale = config.brightness_scale;
+            let written = written.and_then(|actual| {
+                monitors[idx].confirm_brightness()?;
+                Ok(actual)
+            });
+            if !monitors[idx].is_software()
+                && let Some(contended) = contention.record(idx, written.is_ok())
+            {
+                let _ = tx_update.send(MonitorUpdate::Contended {
+                    index: idx,
+                    contended,
+                });
+            }
+            match written {
+                Ok(actual) => {
+                    val = from_hardware(scale, actual, Some(val), min, max);
+                    levels[idx].0 = val;
+                    cooldowns[idx] = Some(Instant::now());
+                    let update = if config.verify_writes {
+                        MonitorUpdate::Verified {
+                            index: idx,
+                            requested,
+                            brightness: val,
                         }
-                        Err(std::sync::mpsc::TryRecvError::Disconnected) => {
-                            disconnected = true;
-                            break;
+                    } else {
+                        MonitorUpdate::Brightness {
+                            index: idx,
+                            brightness: val,
                         }
-                        Err(std::sync::mpsc::TryRecvError::Empty) => break,
+                    };
+                    let _ = tx_update.send(update);
+                    config.brightness.insert(monitors[idx].id(), val);
+                    if config.remember_per_setup {
+                        config
+                            .setups
+                            .entry(setup.clone())
+                            .or_default()
+                            .insert(monitors[idx].id(), val);
+                    }
+                    config_dirty = Some(Instant::now());
+                    if let Some(history_log) = history_log.as_mut() {
+                        history_log.record(
+                            sources[idx].unwrap_or(history::Source::User),
+                            &monitors[idx].id(),
+                            range_to_percent(val, min, max),
+                        );
                     }
+                    if config.notify_changes
+                        && !is_visible
+                        && matches!(
+                            sources[idx],
+                            Some(history::Source::Hotkey | history::Source::Schedule)
+                        )
+                    {
+                        let id = monitors[idx].id();
+                        let name = config
+                            .names
+                            .get(&id)
+                            .map_or(monitors[idx].name(), String::as_str);
+                        let percent = range_to_percent(val, min, max);
+                        unnotified.insert(idx, (name.to_string(), percent));
+                        notify_at = Some(Instant::now() + NOTIFY_DELAY);
+                    }
+                }
+                // The level stays what it was, so the slider, the
+                // average and the next step don't build on a write
+                // the monitor never took
+                Err(_) => {
+                    let _ = tx_update.send(MonitorUpdate::WriteFailed { index: idx });
                 }
+            }
+        }
+
+        if notify_at.is_some_and(|at| at <= Instant::now()) {
+            notify_at = None;
+            let changes: Vec<_> = std::mem::take(&mut unnotified).into_values().collect();
+            // Opening the window in the meantime is feedback enough
+            if !visible.load(Ordering::Relaxed) {
+                PlatformNotifier::notify(&notification_text(&changes));
+            }
+        }
 
-                if disconnected {
-                    cleanup_monitors(&mut monitors);
-                    return;
+        // Runs to the end here on the worker, so the monitor is put
+        // back even if the window closes mid-flash
+        for (idx, &identify) in pending.identify.iter().enumerate() {
+            if identify {
+                let (cur, min, max) = levels[idx];
+                let hardware = to_hardware(config.brightness_scale, cur, min, max);
+                if let Err(e) = crate::monitors::identify(&mut monitors[idx], (hardware, min, max))
+                {
+                    log::warn!("Failed to identify {}: {e}", monitors[idx].name());
                 }
+                cooldowns[idx] = Some(Instant::now());
+            }
+        }
 
-                // Apply only the final value for each monitor
-                for (idx, val) in pending.iter().enumerate() {
-                    if let Some(val) = val {
-                        let _ = monitors[idx].set_brightness(*val);
-                        cooldowns[idx] = Some(Instant::now());
-                        let _ = tx_update.send(MonitorUpdate {
-                            index: idx,
-                            brightness: *val,
-                        });
+        // After any brightness written this tick, so the monitor
+        // wakes up at the level it was given last
+        for (idx, &mode) in pending.power_mode.iter().enumerate() {
+            // Gamma dimming has no screen to switch off
+            let Some(mode) = mode.filter(|_| !monitors[idx].is_software()) else {
+                continue;
+            };
+            if let Err(e) = monitors[idx].set_power_mode(mode) {
+                log::warn!(
+                    "Failed to set power mode {mode} on {}: {e}",
+                    monitors[idx].name()
+                );
+            }
+        }
+
+        for (idx, val) in pending.contrast.iter().enumerate() {
+            if let Some(val) = val {
+                let _ = monitors[idx].set_contrast(*val);
+            }
+        }
+
+        for (idx, preset) in pending.color_preset.iter().enumerate() {
+            if let Some(preset) = preset {
+                let _ = monitors[idx].set_color_preset(*preset);
+            }
+        }
+
+        for (idx, val) in pending.volume.iter().enumerate() {
+            if let Some(val) = val {
+                let _ = monitors[idx].set_volume(*val);
+            }
+        }
+
+        for (idx, &retry) in pending.retry.iter().enumerate() {
+            if retry {
+                let info = MonitorInfo::read(
+                    &mut monitors[idx],
+                    polling.fallback_brightness(),
+                    config.brightness_scale,
+                );
+                levels[idx] = info.level();
+                let _ = tx_update.send(MonitorUpdate::Refreshed { index: idx, info });
+            }
+        }
+
+        if pending.test_ddc {
+            let mut results = Vec::with_capacity(monitors.len());
+            for (idx, mon) in monitors.iter_mut().enumerate() {
+                if config.disabled.contains(&mon.id()) {
+                    results.push(None);
+                    continue;
+                }
+                let passed = match test_ddc(mon) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        log::info!("DDC/CI test failed for {}: {e}", mon.name());
+                        false
                     }
+                };
+                // Rows that showed an error get their controls back
+                if passed {
+                    let info = MonitorInfo::read(
+                        mon,
+                        polling.fallback_brightness(),
+                        config.brightness_scale,
+                    );
+                    levels[idx] = info.level();
+                    let _ = tx_update.send(MonitorUpdate::Refreshed { index: idx, info });
                 }
+                results.push(Some(passed));
+            }
+            let _ = tx_update.send(MonitorUpdate::DdcTested(results));
+        }
 
-                // Poll hardware on a longer interval, skipping monitors
-                // that were recently set (stale reads cause bounce-back)
-                if last_poll.elapsed() >= POLL_INTERVAL {
-                    for (i, mon) in monitors.iter_mut().enumerate() {
-                        if let Some(set_time) = cooldowns[i] {
-                            if set_time.elapsed() < USER_COOLDOWN {
-                                continue;
-                            }
-                            cooldowns[i] = None;
-                        }
+        if pending.dump_vcp {
+            let dumps = monitors.iter_mut().map(dump_vcp).collect();
+            let _ = tx_update.send(MonitorUpdate::VcpDumped(dumps));
+        }
 
-                        if let Ok((current_brightness, _, _)) = mon.poll_brightness_values() {
-                            let _ = tx_update.send(MonitorUpdate {
-                                index: i,
-                                brightness: current_brightness,
-                            });
-                        }
+        rescan_requested |= pending.rescan;
+        if !is_visible {
+            // Re-check the monitor list on the first poll after the
+            // window is shown again — docking usually happens while
+            // the app sits in the tray.
+            polls_since_enumerate = REENUMERATE_EVERY;
+            continue;
+        }
+        let rescan = std::mem::take(&mut rescan_requested);
+
+        // Poll hardware on a longer interval, skipping monitors
+        // that were recently set (stale reads cause bounce-back)
+        if rescan || last_poll.elapsed() >= polling.poll_interval() {
+            if rescan || polls_since_enumerate >= REENUMERATE_EVERY {
+                polls_since_enumerate = 0;
+                if let Some(infos) = refresh_monitors(
+                    &mut provider,
+                    &mut monitors,
+                    polling.fallback_brightness(),
+                    config.brightness_scale,
+                ) {
+                    levels = infos.iter().map(MonitorInfo::level).collect();
+                    cooldowns = vec![None; monitors.len()];
+                    limiter = WriteLimiter::new(monitors.len());
+                    contention = ContentionTracker::new(monitors.len());
+                    sources = vec![None; monitors.len()];
+                    unnotified.clear();
+                    manual_at = vec![None; monitors.len()];
+                    schedule_applied = vec![None; monitors.len()];
+                    // Indices have moved, so there's nothing to restore
+                    fullscreen_since = None;
+                    migrate_name_keys(&mut config, &infos, config_path.as_deref());
+                    let ids: Vec<String> = infos.iter().map(|info| info.id.clone()).collect();
+                    // Switching between setups, say docking a laptop,
+                    // brings back what was last used with the new one
+                    let key = setup_key(&ids);
+                    if key != setup && config.remember_per_setup {
+                        log::info!("Monitors now {key}, restoring their brightness");
+                        restore = Some(
+                            infos
+                                .iter()
+                                .map(|info| config.saved_brightness(&key, &info.id))
+                                .collect(),
+                        );
                     }
-                    last_poll = Instant::now();
+                    setup = key;
+                    for (idx, percent) in
+                        connect_watcher.update(ids, &config.on_connect, Instant::now())
+                    {
+                        log::info!("{} connected, setting it to {percent}%", infos[idx].name);
+                        connect_presets.push(MonitorCmd::SetBrightnessPercent(idx, percent));
+                    }
+                    let _ = tx_update.send(MonitorUpdate::ListChanged(infos));
+                } else if rescan {
+                    let _ = tx_update.send(MonitorUpdate::RescanUnchanged);
                 }
+            }
+            polls_since_enumerate += 1;
 
-                std::thread::sleep(CMD_CHECK_INTERVAL);
+            for cooldown in cooldowns.iter_mut() {
+                if cooldown.is_some_and(|t| t.elapsed() >= polling.user_cooldown()) {
+                    *cooldown = None;
+                }
             }
-        });
 
-        let user_cooldowns = vec![None; monitor_count];
+            let polled = poll_in_parallel(
+                &mut monitors,
+                &levels,
+                &cooldowns,
+                &config.disabled,
+                config.brightness_scale,
+                &tx_update,
+            );
+            for (idx, (level, polled)) in levels.iter_mut().zip(polled).enumerate() {
+                let Some(polled) = polled else {
+                    continue;
+                };
+                if !monitors[idx].is_software()
+                    && let Some(contended) = contention.record(idx, polled.is_ok())
+                {
+                    let _ = tx_update.send(MonitorUpdate::Contended {
+                        index: idx,
+                        contended,
+                    });
+                }
+                let Ok(polled) = polled else {
+                    continue;
+                };
+                if polled.0 != level.0
+                    && let Some(history_log) = history_log.as_mut()
+                {
+                    let (cur, min, max) = polled;
+                    let percent = range_to_percent(cur, min, max);
+                    history_log.record(history::Source::Poll, &monitors[idx].id(), percent);
+                }
+                *level = polled;
+            }
+            last_poll = Instant::now();
+        }
 
-        Ok(Self {
-            brightness_values,
-            min_max,
-            monitor_names,
-            tx_cmd,
-            rx_update,
-            user_cooldowns,
-            visible,
-            frame_count: 0,
-            last_fps_check: Instant::now(),
-        })
+        std::thread::sleep(polling.command_check());
     }
+}
 
-    /// Returns a clone of the visibility flag for use by tray handlers.
-    pub fn visible_flag(&self) -> Arc<AtomicBool> {
-        self.visible.clone()
-    }
+/// Enumerate monitors again and swap them in if the set changed (compared
+/// by id and name, so swapping in another unit of the same model counts).
+/// Returns the new rows for the UI, or `None` when nothing changed or
+/// enumeration failed. Whichever list is discarded has its native handles
+/// released. Unreadable monitors get `fallback` brightness.
+fn refresh_monitors<P: MonitorProvider>(
+    provider: &mut P,
+    monitors: &mut Vec<P::Monitor>,
+    fallback: u32,
+    scale: BrightnessScale,
+) -> Option<Vec<MonitorInfo>> {
+    let mut fresh = match provider.get_monitors() {
+        Ok(fresh) => fresh,
+        Err(e) => {
+            log::warn!("Monitor re-enumeration failed: {e}");
+            return None;
+        }
+    };
+    primary_first(&mut fresh, MonitorHandle::is_primary);
 
-    pub fn monitor_count(&self) -> usize {
-        self.monitor_names.len()
+    let unchanged = fresh.len() == monitors.len()
+        && fresh
+            .iter()
+            .zip(monitors.iter())
+            .all(|(a, b)| a.id() == b.id() && a.name() == b.name());
+    if unchanged {
+        provider.cleanup_monitors(&mut fresh);
+        return None;
     }
 
-    fn build_ui(&mut self, ui: &mut egui::Ui) {
-        ui.heading("Tray Bright");
-        ui.add_space(8.0);
+    provider.cleanup_monitors(monitors);
+    *monitors = fresh;
+    Some(
+        monitors
+            .iter_mut()
+            .map(|mon| MonitorInfo::read(mon, fallback, scale))
+            .collect(),
+    )
+}
 
-        // Apply poll updates, but ignore them for monitors the user is
-        // currently interacting with — otherwise stale hardware reads
-        // yank the slider back mid-drag.
-        while let Ok(update) = self.rx_update.try_recv() {
-            let suppressed =
-                self.user_cooldowns[update.index].is_some_and(|t| t.elapsed() < USER_COOLDOWN);
-            if !suppressed {
-                self.brightness_values[update.index] = update.brightness;
-            }
+/// Read brightness from every monitor at once, one scoped thread each, so a
+/// monitor stuck in a slow DDC/CI round-trip doesn't hold up the others.
+/// Each result is sent to the UI as soon as it arrives, as a slider
+/// position on `scale` near the last known one in `levels`. Monitors still
+/// in their cooldown, disabled or without brightness support are skipped
+/// and come back as `None`; failed reads come back as the error.
+fn poll_in_parallel<M: MonitorHandle>(
+    monitors: &mut [M],
+    levels: &[(u32, u32, u32)],
+    cooldowns: &[Option<Instant>],
+    disabled: &BTreeSet<String>,
+    scale: BrightnessScale,
+    tx_update: &Sender<MonitorUpdate>,
+) -> Vec<Option<anyhow::Result<(u32, u32, u32)>>> {
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = monitors
+            .iter_mut()
+            .zip(levels)
+            .zip(cooldowns)
+            .enumerate()
+            .map(|(index, ((mon, &(current, _, _)), cooldown))| {
+                let skip = cooldown.is_some()
+                    || disabled.contains(&mon.id())
+                    || !mon.supports_brightness();
+                let tx_update = tx_update.clone();
+                scope.spawn(move || {
+                    if skip {
+                        return None;
+                    }
+                    let polled = mon.poll_brightness().map(|(cur, min, max)| {
+                        (from_hardware(scale, cur, Some(current), min, max), min, max)
+                    });
+                    if let Ok((brightness, _, _)) = polled {
+                        let _ = tx_update.send(MonitorUpdate::Brightness { index, brightness });
+                    }
+                    Some(polled)
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().ok().flatten())
+            .collect()
+    })
+}
+
+/// Notification text for `changes`, each monitor's name and new percent:
+/// just the percent when they all ended up the same, else one line each.
+fn notification_text(changes: &[(String, u32)]) -> String {
+    match changes {
+        [(_, percent), rest @ ..] if rest.iter().all(|(_, p)| p == percent) => {
+            tr_args("brightness_notification", &[("percent", percent)])
         }
+        _ => changes
+            .iter()
+            .map(|(name, percent)| format!("{name} {percent}%"))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
 
-        for i in 0..self.monitor_names.len() {
-            if i > 0 {
-                ui.add_space(4.0);
-                ui.separator();
-                ui.add_space(4.0);
-            }
-            ui.label(RichText::new(&self.monitor_names[i]).strong());
-            ui.add_space(4.0);
-            let (min, max) = self.min_max[i];
-            let mut cur = self.brightness_values[i];
+/// Mean brightness of the monitors the app controls, for the tray tooltip.
+/// Disabled monitors and ones that couldn't be read (empty range) don't
+/// count. `None` when there's nothing left to average.
+fn average_brightness<M: MonitorHandle>(
+    monitors: &[M],
+    levels: &[(u32, u32, u32)],
+    disabled: &BTreeSet<String>,
+) -> Option<u32> {
+    let readable: Vec<u32> = monitors
+        .iter()
+        .zip(levels)
+        .filter(|(mon, (_, min, max))| max > min && !disabled.contains(&mon.id()))
+        .map(|(_, &(cur, _, _))| cur)
+        .collect();
+    (!readable.is_empty()).then(|| readable.iter().sum::<u32>() / readable.len() as u32)
+}
 
-            let slider_width = ui.available_width() - 60.0;
-            ui.spacing_mut().slider_width = slider_width.max(100.0);
-            let slider = ui.add(
-                egui::Slider::new(&mut cur, min..=max)
-                    .suffix("%")
-                    .show_value(true),
-            );
+/// Queue the active schedule entry for monitors it hasn't been applied to
+/// yet. Monitors the user adjusted within the override window are skipped
+/// and picked up on a later check once the window has passed.
+fn queue_scheduled<M: MonitorHandle>(
+    schedule: &Schedule,
+    monitors: &[M],
+    pending: &mut PendingCmds,
+    applied: &mut [Option<usize>],
+    manual_at: &[Option<Instant>],
+) {
+    let Some(active) = schedule.active_entry(schedule::local_minutes()) else {
+        return;
+    };
+    let entry = &schedule.entries[active];
+    let override_window = Duration::from_secs(schedule.manual_override_minutes as u64 * 60);
 
-            if slider.changed() {
-                self.brightness_values[i] = cur;
-                // Suppress poll updates while user is dragging
-                self.user_cooldowns[i] = Some(Instant::now());
-            }
+    for (idx, mon) in monitors.iter().enumerate() {
+        if applied[idx] == Some(active) {
+            continue;
+        }
+        if manual_at[idx].is_some_and(|t| t.elapsed() < override_window) {
+            continue;
+        }
+        pending.brightness[idx] = Some(entry.target_for(&mon.id(), mon.name()));
+        applied[idx] = Some(active);
+    }
+}
 
-            if slider.drag_stopped() {
-                // Reset cooldown window from the moment of release
-                self.user_cooldowns[i] = Some(Instant::now());
-                let _ = self.tx_cmd.send(MonitorCmd::SetBrightness(i, cur));
-            }
+/// Queue the curve's current brightness for every monitor not already at
+/// it. Monitors the user adjusted within the override window are skipped,
+/// and user commands queued this tick take precedence.
+fn queue_curve(
+    curve: &Curve,
+    pending: &mut PendingCmds,
+    levels: &[(u32, u32, u32)],
+    manual_at: &[Option<Instant>],
+) {
+    let Some(target) = curve.brightness_at(schedule::local_minutes()) else {
+        return;
+    };
+    let override_window = Duration::from_secs(curve.manual_override_minutes as u64 * 60);
+
+    for (idx, &(cur, _, _)) in levels.iter().enumerate() {
+        if cur == target || manual_at[idx].is_some_and(|t| t.elapsed() < override_window) {
+            continue;
         }
+        pending.brightness[idx].get_or_insert(target);
+    }
+}
+
+/// Move saved brightness that older versions keyed by display name to the
+/// ids of `infos`, saving the config straight away if anything moved.
+fn migrate_name_keys(config: &mut Config, infos: &[MonitorInfo], path: Option<&Path>) {
+    let monitors: Vec<(&str, &str)> = infos
+        .iter()
+        .map(|info| (info.id.as_str(), info.name.as_str()))
+        .collect();
+    if config.migrate_name_keys(&monitors) {
+        save_config(config, path);
+    }
+}
+
+fn save_config(config: &Config, path: Option<&Path>) {
+    let Some(path) = path else {
+        return;
+    };
+    if let Err(e) = config.save(path) {
+        log::warn!("Failed to save config: {e}");
     }
 }
 
 impl eframe::App for TrayBrightUI {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // --- frame-rate diagnostic (prints to stderr every 5s) ---
+        // --- frame-rate diagnostic (logged at debug level every 5s) ---
         self.frame_count += 1;
         let elapsed = self.last_fps_check.elapsed();
         if elapsed >= Duration::from_secs(5) {
             let fps = self.frame_count as f64 / elapsed.as_secs_f64();
             let vis = self.visible.load(Ordering::Relaxed);
-            eprintln!("[tray-bright] {:.1} frames/sec  visible={vis}", fps);
+            let dragging = ctx.dragged_id().is_some();
+            log::debug!("{:.1} frames/sec  visible={vis} dragging={dragging}", fps);
             self.frame_count = 0;
             self.last_fps_check = Instant::now();
         }
@@ -257,8 +3876,69 @@ impl eframe::App for TrayBrightUI {
             crate::hide_window();
         }
 
+        // Hide like a tray flyout once focus moves to another window. An
+        // open combo box or menu is drawn inside this window, but leave it
+        // be anyway so a hide can't cut a choice short.
+        let focused = ctx
+            .input(|i| i.viewport().focused)
+            .unwrap_or(self.was_focused);
+        if self.hide_on_focus_loss
+            && self.was_focused
+            && !focused
+            && self.visible.load(Ordering::Relaxed)
+            && !egui::Popup::is_any_open(ctx)
+        {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Visible(false));
+            self.visible.store(false, Ordering::Relaxed);
+            crate::hide_window_on_focus_loss();
+        }
+        self.was_focused = focused && self.visible.load(Ordering::Relaxed);
+
+        crate::tray::refresh_tooltip();
+
+        while let Ok(request) = self.rx_tray.try_recv() {
+            match request {
+                TrayRequest::ConfirmAutostartOff => self.confirm_autostart_off = true,
+                TrayRequest::AutostartFailed => {
+                    self.toast = Some((tr("start_on_login_failed").to_string(), Instant::now()));
+                }
+            }
+        }
+
+        if ctx.input_mut(|i| i.consume_key(Modifiers::COMMAND | Modifiers::SHIFT, Key::D)) {
+            self.toggle_debug(ctx);
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&RESCAN_SHORTCUT)) {
+            self.rescan();
+        }
+
+        // With the System preference egui picks light or dark from the OS
+        // theme eframe reports, which it updates when the OS setting changes.
+        if self.applied_theme != Some(self.theme) {
+            ctx.set_theme(match self.theme {
+                ThemeMode::System => egui::ThemePreference::System,
+                ThemeMode::Light => egui::ThemePreference::Light,
+                ThemeMode::Dark => egui::ThemePreference::Dark,
+            });
+            self.applied_theme = Some(self.theme);
+        }
+
         let is_visible = self.visible.load(Ordering::Relaxed);
 
+        // Some window managers drop the level when the window is hidden,
+        // so it's sent again on each show
+        if !is_visible {
+            self.applied_on_top = None;
+        } else if self.applied_on_top != Some(self.always_on_top) {
+            let level = if self.always_on_top {
+                egui::WindowLevel::AlwaysOnTop
+            } else {
+                egui::WindowLevel::Normal
+            };
+            ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(level));
+            self.applied_on_top = Some(self.always_on_top);
+        }
+
         if is_visible {
             ctx.request_repaint_after(Duration::from_secs(1));
         }
@@ -273,6 +3953,15 @@ impl eframe::App for TrayBrightUI {
             .show(ctx, |ui| {
                 self.build_ui(ui);
             });
+        if self.confirm_autostart_off {
+            self.build_autostart_confirm(ctx);
+        }
+
+        // Keep frames coming while a slider is dragged so it follows the
+        // pointer smoothly; at rest the 1s cadence above applies.
+        if is_visible && ctx.dragged_id().is_some() {
+            ctx.request_repaint();
+        }
     }
 }
 
@@ -285,7 +3974,117 @@ pub fn load_icon_rgba() -> (Vec<u8>, u32, u32) {
     (img.into_raw(), w, h)
 }
 
-pub fn get_app_options(monitor_count: usize) -> eframe::NativeOptions {
+/// One monitor's section of the debug panel. `index` keeps widget ids apart
+/// when two monitors share a name.
+fn build_vcp_dump(ui: &mut egui::Ui, index: usize, dump: &VcpDump) {
+    let probe = if dump.supports_brightness {
+        tr("brightness_supported")
+    } else {
+        tr("brightness_unsupported")
+    };
+    ui.label(RichText::new(probe).small().weak());
+    let caps = match &dump.capabilities {
+        Ok(caps) => caps,
+        Err(e) => {
+            ui.label(RichText::new(e).small().weak());
+            return;
+        }
+    };
+    ui.horizontal(|ui| {
+        ui.label(RichText::new(tr("capability_string")).small().strong());
+        if ui.small_button(tr("copy")).clicked() {
+            ui.ctx().copy_text(caps.clone());
+        }
+    });
+    ui.add(
+        egui::TextEdit::multiline(&mut caps.as_str())
+            .font(egui::TextStyle::Monospace)
+            .desired_rows(2)
+            .desired_width(f32::INFINITY),
+    );
+
+    if dump.features.is_empty() {
+        ui.label(RichText::new(tr("no_vcp_codes")).small().weak());
+        return;
+    }
+    egui::Grid::new(("vcp_codes", index))
+        .striped(true)
+        .show(ui, |ui| {
+            for feature in &dump.features {
+                ui.monospace(format!("{:02X}", feature.code));
+                ui.label(RichText::new(vcp::feature_name(feature.code).unwrap_or("")).small());
+                match &feature.reply {
+                    Ok((current, max)) => ui.monospace(format!("{current} / {max}")),
+                    Err(e) => ui
+                        .label(RichText::new(tr("vcp_read_failed")).small().weak())
+                        .on_hover_text(e),
+                };
+                let values: Vec<String> =
+                    feature.values.iter().map(|v| format!("{v:02X}")).collect();
+                ui.monospace(values.join(" "));
+                ui.end_row();
+            }
+        });
+}
+
+/// Initial and minimum window width.
+const WINDOW_WIDTH: f32 = 320.0;
+
+/// Window height for a layout that wants `preferred_height`, kept within
+/// what fits on a small screen.
+fn window_height(preferred_height: f32) -> f32 {
+    preferred_height.clamp(120.0, 400.0)
+}
+
+/// Gap, in points, between the window and the tray icon or a screen edge.
+const TRAY_MARGIN: f32 = 8.0;
+
+/// Move the window next to the tray icon at `anchor` (physical pixels):
+/// above it for a taskbar along the bottom, below it for a menu bar along
+/// the top, kept on screen. Does nothing until the window's size is known.
+pub fn move_near_tray(ctx: &egui::Context, anchor: egui::Rect) {
+    let (outer, monitor) = ctx.input(|i| (i.viewport().outer_rect, i.viewport().monitor_size));
+    let Some(outer) = outer else {
+        return;
+    };
+    let anchor = anchor / ctx.pixels_per_point();
+    let position = popup_position(anchor, outer.size(), monitor);
+    ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(position));
+}
+
+/// Top-left corner for a `size` window popping up from the tray icon at
+/// `anchor`, all in points. Kept inside `screen` when the icon is on it;
+/// icons on other monitors don't have their bounds here, so the window
+/// just follows the icon.
+fn popup_position(anchor: egui::Rect, size: egui::Vec2, screen: Option<egui::Vec2>) -> egui::Pos2 {
+    let screen = screen
+        .filter(|s| egui::Rect::from_min_size(egui::Pos2::ZERO, *s).contains(anchor.center()));
+    let bottom_half = screen.is_none_or(|s| anchor.center().y > s.y / 2.0);
+
+    let x = anchor.center().x - size.x / 2.0;
+    let y = if bottom_half {
+        anchor.top() - size.y - TRAY_MARGIN
+    } else {
+        anchor.bottom() + TRAY_MARGIN
+    };
+    let Some(screen) = screen else {
+        return egui::pos2(x, y);
+    };
+    egui::pos2(
+        x.clamp(
+            TRAY_MARGIN,
+            (screen.x - size.x - TRAY_MARGIN).max(TRAY_MARGIN),
+        ),
+        y.clamp(
+            TRAY_MARGIN,
+            (screen.y - size.y - TRAY_MARGIN).max(TRAY_MARGIN),
+        ),
+    )
+}
+
+/// With `hidden` the window is created invisible, rather than shown and
+/// then hidden once its handle exists.
+pub fn get_app_options(preferred_height: f32, hidden: bool) -> eframe::NativeOptions {
     let (rgba, width, height) = load_icon_rgba();
     let icon = egui::IconData {
         rgba,
@@ -293,14 +4092,161 @@ pub fn get_app_options(monitor_count: usize) -> eframe::NativeOptions {
         height,
     };
 
-    let height = (80.0 + 60.0 * monitor_count as f32).clamp(120.0, 400.0);
+    let height = window_height(preferred_height);
 
     eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
-            .with_inner_size([320.0, height])
-            .with_min_inner_size([320.0, 120.0])
+            .with_inner_size([WINDOW_WIDTH, height])
+            .with_min_inner_size([WINDOW_WIDTH, 120.0])
             .with_app_id("tray-bright")
-            .with_icon(Arc::new(icon)),
+            .with_icon(Arc::new(icon))
+            .with_visible(!hidden),
         ..Default::default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{SimMonitor, SimMonitorProvider};
+
+    /// Simulated monitors that note when the worker releases them.
+    struct TrackedProvider {
+        sim: SimMonitorProvider,
+        cleaned_up: Arc<AtomicBool>,
+    }
+
+    impl MonitorProvider for TrackedProvider {
+        type Monitor = SimMonitor;
+
+        fn new() -> Self {
+            Self {
+                sim: SimMonitorProvider::new(),
+                cleaned_up: Arc::default(),
+            }
+        }
+
+        fn get_monitors(&mut self) -> anyhow::Result<Vec<SimMonitor>> {
+            self.sim.get_monitors()
+        }
+
+        fn cleanup_monitors(&mut self, monitors: &mut Vec<SimMonitor>) {
+            self.cleaned_up.store(true, Ordering::Relaxed);
+            self.sim.cleanup_monitors(monitors);
+        }
+    }
+
+    /// A worker driving two simulated monitors on its own thread, with the
+    /// window shown so it polls.
+    struct Harness {
+        tx_cmd: Sender<MonitorCmd>,
+        rx_update: Receiver<MonitorUpdate>,
+        cleaned_up: Arc<AtomicBool>,
+        worker: std::thread::JoinHandle<()>,
+    }
+
+    impl Harness {
+        /// `cmds` are queued before the worker starts, so its first tick
+        /// takes them all at once.
+        fn start(config: Config, cmds: Vec<MonitorCmd>) -> Self {
+            let (tx_cmd, rx_cmd) = channel();
+            let (tx_update, rx_update) = channel();
+            let (tx_average, _) = channel();
+            for cmd in cmds {
+                tx_cmd.send(cmd).unwrap();
+            }
+            let provider = TrackedProvider::new();
+            let cleaned_up = provider.cleaned_up.clone();
+            let link = WorkerLink {
+                rx_cmd,
+                tx_update,
+                tx_average,
+                snapshot: MonitorSnapshot::default(),
+                visible: Arc::new(AtomicBool::new(true)),
+                config_path: None,
+            };
+            let worker = std::thread::spawn(move || run_worker(provider, config, link));
+            Self {
+                tx_cmd,
+                rx_update,
+                cleaned_up,
+                worker,
+            }
+        }
+
+        /// Brightness updates as `(index, brightness)`, up to and including
+        /// the first one `last` matches.
+        fn brightness_until(&self, last: impl Fn(usize) -> bool) -> Vec<(usize, u32)> {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            let mut seen = Vec::new();
+            loop {
+                let left = deadline.saturating_duration_since(Instant::now());
+                let update = self.rx_update.recv_timeout(left).expect("no update");
+                if let MonitorUpdate::Brightness { index, brightness } = update {
+                    seen.push((index, brightness));
+                    if last(index) {
+                        return seen;
+                    }
+                }
+            }
+        }
+
+        /// Hang up like the closing UI does and wait for the worker to
+        /// exit. Returns whether it released the monitors.
+        fn stop(self) -> bool {
+            drop(self.tx_cmd);
+            self.worker.join().expect("worker panicked");
+            self.cleaned_up.load(Ordering::Relaxed)
+        }
+    }
+
+    #[test]
+    fn rapid_writes_collapse_to_the_last_value() {
+        let cmds = [20, 40, 60, 80]
+            .map(|value| MonitorCmd::SetBrightness(0, value))
+            .into();
+        let harness = Harness::start(Config::default(), cmds);
+        assert_eq!(harness.brightness_until(|index| index == 0), [(0, 80)]);
+        harness.stop();
+    }
+
+    #[test]
+    fn polls_skip_monitors_in_their_cooldown() {
+        let config = Config {
+            polling: PollSettings {
+                poll_interval_ms: 1000,
+                user_cooldown_ms: 60_000,
+                ..PollSettings::default()
+            },
+            ..Config::default()
+        };
+        let harness = Harness::start(config, vec![MonitorCmd::SetBrightness(0, 80)]);
+        // The write, then the first poll, which only reads the other monitor
+        assert_eq!(
+            harness.brightness_until(|index| index == 1),
+            [(0, 80), (1, 50)]
+        );
+        harness.stop();
+    }
+
+    #[test]
+    fn panic_key_ignores_brightness_limits() {
+        let limits = BrightnessLimits { min: 10, max: 60 };
+        let config = Config {
+            limits: BTreeMap::from([("SIM:1".into(), limits), ("SIM:2".into(), limits)]),
+            ..Config::default()
+        };
+        let harness = Harness::start(config, vec![MonitorCmd::RestoreAll]);
+        assert_eq!(
+            harness.brightness_until(|index| index == 1),
+            [(0, 100), (1, 100)]
+        );
+        harness.stop();
+    }
+
+    #[test]
+    fn hanging_up_releases_the_monitors() {
+        let harness = Harness::start(Config::default(), Vec::new());
+        assert!(harness.stop());
+    }
+}
@@ -0,0 +1,170 @@
+//! Platform-independent DDC/CI helpers.
+//!
+//! Every backend can fetch the raw MCCS capability string from a monitor;
+//! the parsing and value naming live here so the three platforms agree on
+//! what a monitor supports.
+
+use crate::i18n::{tr, tr_args};
+
+/// VCP feature code for the colour preset / colour temperature selector.
+pub const COLOR_PRESET: u8 = 0x14;
+
+/// VCP feature code for the display power mode (DPMS).
+pub const POWER_MODE: u8 = 0xD6;
+
+/// [`POWER_MODE`] value that turns the display on, waking it from sleep.
+pub const POWER_ON: u8 = 0x01;
+
+/// [`POWER_MODE`] value that puts the display to sleep as if the computer
+/// had blanked it (DPMS off). Input or a write of [`POWER_ON`] wakes it.
+pub const POWER_OFF: u8 = 0x04;
+
+/// Parse the `vcp(...)` section of a capability string into a list of
+/// `(code, allowed_values)` pairs. Continuous features have no value list.
+///
+/// Example input: `(prot(monitor)vcp(10 12 14(05 08 0B) 60(0F 11))mccs_ver(2.1))`
+pub fn parse_vcp_features(caps: &str) -> Vec<(u8, Vec<u8>)> {
+    let Some(section) = vcp_section(caps) else {
+        return Vec::new();
+    };
+
+    let mut features: Vec<(u8, Vec<u8>)> = Vec::new();
+    let mut token = String::new();
+    let mut depth = 0usize;
+
+    let flush = |token: &mut String, depth: usize, features: &mut Vec<(u8, Vec<u8>)>| {
+        if let Ok(value) = u8::from_str_radix(token, 16) {
+            match depth {
+                0 => features.push((value, Vec::new())),
+                1 => {
+                    if let Some((_, values)) = features.last_mut() {
+                        values.push(value);
+                    }
+                }
+                // Deeper nesting isn't defined by MCCS — ignore it.
+                _ => {}
+            }
+        }
+        token.clear();
+    };
+
+    for c in section.chars() {
+        match c {
+            '(' => {
+                flush(&mut token, depth, &mut features);
+                depth += 1;
+            }
+            ')' => {
+                flush(&mut token, depth, &mut features);
+                depth = depth.saturating_sub(1);
+            }
+            c if c.is_ascii_hexdigit() => token.push(c),
+            _ => flush(&mut token, depth, &mut features),
+        }
+    }
+    flush(&mut token, depth, &mut features);
+
+    features
+}
+
+/// Allowed values for a single VCP code, or `None` if the monitor doesn't
+/// list the code at all.
+pub fn feature_values(caps: &str, code: u8) -> Option<Vec<u8>> {
+    parse_vcp_features(caps)
+        .into_iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, values)| values)
+}
+
+/// Colour presets advertised in the capability string, with display names.
+pub fn color_presets(caps: &str) -> Vec<(u8, String)> {
+    feature_values(caps, COLOR_PRESET)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|v| (v, color_preset_name(v)))
+        .collect()
+}
+
+/// Human-readable name for a VCP 0x14 value, per the MCCS spec.
+pub fn color_preset_name(value: u8) -> String {
+    match value {
+        0x01 => "sRGB".to_string(),
+        0x02 => tr("preset_native").to_string(),
+        0x03 => "4000K".to_string(),
+        0x04 => "5000K".to_string(),
+        0x05 => "6500K".to_string(),
+        0x06 => "7500K".to_string(),
+        0x07 => "8200K".to_string(),
+        0x08 => "9300K".to_string(),
+        0x09 => "10000K".to_string(),
+        0x0A => "11500K".to_string(),
+        0x0B..=0x0D => tr_args("preset_user", &[("n", &(value - 0x0A))]),
+        other => tr_args("preset_other", &[("code", &format!("{other:02X}"))]),
+    }
+}
+
+/// Name of a VCP code as given in the MCCS spec, for the codes monitors
+/// commonly list. `None` for the rest, including manufacturer codes.
+pub fn feature_name(code: u8) -> Option<&'static str> {
+    Some(match code {
+        0x02 => "New control value",
+        0x04 => "Restore factory defaults",
+        0x05 => "Restore brightness/contrast",
+        0x06 => "Restore geometry",
+        0x08 => "Restore color",
+        0x0B => "Color temperature increment",
+        0x0C => "Color temperature request",
+        0x10 => "Brightness",
+        0x12 => "Contrast",
+        COLOR_PRESET => "Color preset",
+        0x16 => "Red gain",
+        0x18 => "Green gain",
+        0x1A => "Blue gain",
+        0x52 => "Active control",
+        0x60 => "Input source",
+        0x62 => "Speaker volume",
+        0x6C => "Red black level",
+        0x6E => "Green black level",
+        0x70 => "Blue black level",
+        0x87 => "Sharpness",
+        0x8D => "Audio mute",
+        0xAC => "Horizontal frequency",
+        0xAE => "Vertical frequency",
+        0xB2 => "Subpixel layout",
+        0xB6 => "Display technology",
+        0xC0 => "Display usage time",
+        0xC6 => "Application enable key",
+        0xC8 => "Display controller type",
+        0xC9 => "Firmware level",
+        0xCA => "OSD",
+        0xCC => "OSD language",
+        POWER_MODE => "Power mode",
+        0xDC => "Display mode",
+        0xDF => "VCP version",
+        _ => return None,
+    })
+}
+
+/// Slice out the body of the top-level `vcp(...)` group. Capability strings
+/// are frequently truncated by the monitor, so an unterminated group is
+/// returned as-is rather than rejected.
+fn vcp_section(caps: &str) -> Option<&str> {
+    let start = caps.to_ascii_lowercase().find("vcp(")? + 4;
+    let body = &caps[start..];
+
+    let mut depth = 1usize;
+    for (i, c) in body.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&body[..i]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Some(body)
+}